@@ -0,0 +1,346 @@
+use cpu::consts;
+use apu::channel::{Square, Wave, Noise};
+
+// rate of the samples given to the frontend, in stereo.
+pub const SAMPLE_RATE: u32 = 44100;
+
+const NR50_INDEX: usize = 0x14;
+const NR51_INDEX: usize = 0x15;
+const NR52_INDEX: usize = 0x16;
+const WAVE_RAM_INDEX: usize = 0x20;
+
+// the frame sequencer steps at 512Hz: length counters on even steps, the sweep
+// on steps 2 and 6 and the envelopes on step 7.
+const FRAME_SEQUENCER_CYCLES: u32 = consts::CPU_FREQUENCY_HZ / 512;
+
+// bits that always read as 1: write-only and unused bits.
+const READ_MASKS: [u8; 0x20] = [0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+                                0xFF, 0x3F, 0x00, 0xFF, 0xBF, // unused, NR21-NR24
+                                0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+                                0xFF, 0xFF, 0x00, 0x00, 0xBF, // unused, NR41-NR44
+                                0x00, 0x00, 0x70, // NR50-NR52
+                                0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]; // unused
+
+// a DAC turns the 0-15 output of a channel into -1.0 to 1.0; one that's off
+// doesn't output anything.
+fn dac(output: Option<u8>) -> f32 {
+    match output {
+        Some(value) => value as f32 / 7.5 - 1.0,
+        None => 0.0,
+    }
+}
+
+// the capacitor that removes the DC offset of the DACs from each output.
+fn high_pass(input: f32, capacitor: &mut f32) -> f32 {
+    let output: f32 = input - *capacitor;
+    *capacitor = input - output * 0.996;
+    output
+}
+
+fn to_sample(value: f32) -> i16 {
+    (value.max(-1.0).min(1.0) * i16::max_value() as f32) as i16
+}
+
+// Sound registers (0xFF10-0xFF2F) and wave pattern ram (0xFF30-0xFF3F), and the
+// four channels they control. Samples are only kept while capturing, since there's
+// no audio output yet; they're stereo (left first) at SAMPLE_RATE. Each channel can
+// also be captured on its own (a stem), in mono and before NR50/NR51.
+pub struct Apu {
+    registers: [u8; 0x30],
+    square1: Square,
+    square2: Square,
+    wave: Wave,
+    noise: Noise,
+    frame_sequencer_cycles: u32,
+    frame_sequencer_step: u8,
+    sample_clock: u32,
+    capacitors: (f32, f32),
+    capture: bool,
+    samples: Vec<i16>,
+    stem_capacitors: [f32; 4],
+    capture_stems: bool,
+    stems: [Vec<i16>; 4],
+}
+
+impl Default for Apu {
+    fn default() -> Apu {
+        Apu {
+            registers: [0; 0x30],
+            square1: Square::new(true),
+            square2: Square::new(false),
+            wave: Wave::default(),
+            noise: Noise::default(),
+            frame_sequencer_cycles: 0,
+            frame_sequencer_step: 0,
+            sample_clock: 0,
+            capacitors: (0.0, 0.0),
+            capture: false,
+            samples: Vec::new(),
+            stem_capacitors: [0.0; 4],
+            capture_stems: false,
+            stems: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+        }
+    }
+}
+
+impl Apu {
+    fn is_on(&self) -> bool {
+        self.registers[NR52_INDEX] & 0b1000_0000 == 0b1000_0000
+    }
+
+    // bits 0-3 of NR52 tell which channels are playing.
+    fn channels_status(&self) -> u8 {
+        let mut status: u8 = 0;
+        let enabled: [bool; 4] = [self.square1.is_enabled(),
+                                  self.square2.is_enabled(),
+                                  self.wave.is_enabled(),
+                                  self.noise.is_enabled()];
+        for (channel, is_enabled) in enabled.iter().enumerate() {
+            if *is_enabled {
+                status |= 0b1 << channel;
+            }
+        }
+        status
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        let index: usize = (address - consts::SOUND_REGISTERS_ADDR_START) as usize;
+        if index >= WAVE_RAM_INDEX {
+            self.registers[index]
+        } else if index == NR52_INDEX {
+            self.registers[index] | READ_MASKS[index] | self.channels_status()
+        } else {
+            self.registers[index] | READ_MASKS[index]
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        let index: usize = (address - consts::SOUND_REGISTERS_ADDR_START) as usize;
+        if index >= WAVE_RAM_INDEX {
+            self.registers[index] = value;
+        } else if index == NR52_INDEX {
+            // only the power bit is writable.
+            let was_on: bool = self.is_on();
+            self.registers[index] = value & 0b1000_0000;
+            if !self.is_on() {
+                // turning the sound off clears every register and channel.
+                for reg in &mut self.registers[..NR52_INDEX] {
+                    *reg = 0;
+                }
+                self.square1 = Square::new(true);
+                self.square2 = Square::new(false);
+                self.wave = Wave::default();
+                self.noise = Noise::default();
+            } else if !was_on {
+                self.frame_sequencer_cycles = 0;
+                self.frame_sequencer_step = 0;
+            }
+        } else if self.is_on() {
+            self.registers[index] = value;
+            match index {
+                0x00...0x04 => self.square1.write(index, value, &self.registers[0x00..0x05]),
+                0x05...0x09 => {
+                    self.square2.write(index - 0x05, value, &self.registers[0x05..0x0A])
+                }
+                0x0A...0x0E => self.wave.write(index - 0x0A, value, &self.registers[0x0A..0x0F]),
+                0x0F...0x13 => self.noise.write(index - 0x0F, value),
+                _ => (),
+            }
+        } else {
+            // on DMG the length counters can still be loaded while the sound is off.
+            match index {
+                0x01 => self.square1.load_length(value),
+                0x06 => self.square2.load_length(value),
+                0x0B => self.wave.load_length(value),
+                0x10 => self.noise.load_length(value),
+                _ => (),
+            }
+        }
+    }
+
+    // stores 'value' without any of the side effects of writing it (no trigger,
+    // no DAC or length changes), so channels that are playing carry on.
+    pub fn load_register(&mut self, address: u16, value: u8) {
+        let index: usize = (address - consts::SOUND_REGISTERS_ADDR_START) as usize;
+        self.registers[index] = if index == NR52_INDEX {
+            value & 0b1000_0000
+        } else {
+            value
+        };
+    }
+
+    // stems are only captured along with the mixed samples.
+    pub fn set_capture(&mut self, capture: bool, stems: bool) {
+        if capture != self.capture {
+            self.samples.clear();
+        }
+        if stems != self.capture_stems {
+            for stem in &mut self.stems {
+                stem.clear();
+            }
+        }
+        self.capture = capture;
+        self.capture_stems = stems;
+    }
+
+    // the samples captured since the last call.
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        let mut samples: Vec<i16> = Vec::new();
+        ::std::mem::swap(&mut samples, &mut self.samples);
+        samples
+    }
+
+    // the stems captured since the last call, channel 1 first.
+    pub fn take_stems(&mut self) -> [Vec<i16>; 4] {
+        let mut stems: [Vec<i16>; 4] = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+        ::std::mem::swap(&mut stems, &mut self.stems);
+        stems
+    }
+
+    fn step_frame_sequencer(&mut self) {
+        if self.frame_sequencer_step % 2 == 0 {
+            self.square1.step_length();
+            self.square2.step_length();
+            self.wave.step_length();
+            self.noise.step_length();
+        }
+        if self.frame_sequencer_step == 2 || self.frame_sequencer_step == 6 {
+            self.square1.step_sweep();
+        }
+        if self.frame_sequencer_step == 7 {
+            self.square1.step_envelope();
+            self.square2.step_envelope();
+            self.noise.step_envelope();
+        }
+        self.frame_sequencer_step = (self.frame_sequencer_step + 1) % 8;
+    }
+
+    // the DAC outputs of the four channels.
+    fn channel_outputs(&self) -> [f32; 4] {
+        [dac(self.square1.output()),
+         dac(self.square2.output()),
+         dac(self.wave.output(&self.registers[WAVE_RAM_INDEX..])),
+         dac(self.noise.output())]
+    }
+
+    // NR51 selects which channels go to each side and NR50 sets their volume.
+    fn mix(&mut self, outputs: &[f32; 4]) -> (i16, i16) {
+        if !self.is_on() {
+            return (0, 0);
+        }
+        let panning: u8 = self.registers[NR51_INDEX];
+        let (mut left, mut right): (f32, f32) = (0.0, 0.0);
+        for (channel, output) in outputs.iter().enumerate() {
+            if (panning >> (channel + 4)) & 0b1 == 0b1 {
+                left += *output;
+            }
+            if (panning >> channel) & 0b1 == 0b1 {
+                right += *output;
+            }
+        }
+        let volume: u8 = self.registers[NR50_INDEX];
+        left *= (((volume >> 4) & 0b111) + 1) as f32 / 8.0 / 4.0;
+        right *= ((volume & 0b111) + 1) as f32 / 8.0 / 4.0;
+        (to_sample(high_pass(left, &mut self.capacitors.0)),
+         to_sample(high_pass(right, &mut self.capacitors.1)))
+    }
+
+    // 'cycles' are at the normal speed, even in CGB double speed mode.
+    pub fn tick(&mut self, cycles: u32) {
+        if !self.capture {
+            // nobody listens to the channels, so only what can be read back has to
+            // keep up: the frame sequencer turns channels off (the status in NR52).
+            if self.is_on() {
+                self.frame_sequencer_cycles += cycles;
+                while self.frame_sequencer_cycles >= FRAME_SEQUENCER_CYCLES {
+                    self.frame_sequencer_cycles -= FRAME_SEQUENCER_CYCLES;
+                    self.step_frame_sequencer();
+                }
+            }
+            return;
+        }
+        for _ in 0..cycles {
+            if self.is_on() {
+                self.square1.tick();
+                self.square2.tick();
+                self.wave.tick();
+                self.noise.tick();
+                self.frame_sequencer_cycles += 1;
+                if self.frame_sequencer_cycles == FRAME_SEQUENCER_CYCLES {
+                    self.frame_sequencer_cycles = 0;
+                    self.step_frame_sequencer();
+                }
+            }
+            if self.capture {
+                self.sample_clock += SAMPLE_RATE;
+                if self.sample_clock >= consts::CPU_FREQUENCY_HZ {
+                    self.sample_clock -= consts::CPU_FREQUENCY_HZ;
+                    let outputs: [f32; 4] = self.channel_outputs();
+                    let (left, right) = self.mix(&outputs);
+                    self.samples.push(left);
+                    self.samples.push(right);
+                    if self.capture_stems {
+                        for (channel, output) in outputs.iter().enumerate() {
+                            let stem: f32 = high_pass(*output,
+                                                      &mut self.stem_capacitors[channel]);
+                            self.stems[channel].push(to_sample(stem));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Apu, FRAME_SEQUENCER_CYCLES};
+
+    const NR11: u16 = 0xFF11;
+    const NR12: u16 = 0xFF12;
+    const NR14: u16 = 0xFF14;
+    const NR52: u16 = 0xFF26;
+
+    // channel 1 playing for 2 length steps, the 1st and 3rd frame sequencer steps.
+    fn play_channel1(apu: &mut Apu) {
+        apu.write(NR52, 0x80);
+        apu.write(NR11, 62);
+        apu.write(NR12, 0xF0);
+        apu.write(NR14, 0xC0);
+    }
+
+    #[test]
+    fn the_length_turns_a_channel_off_without_capturing() {
+        let mut apu: Apu = Apu::default();
+        play_channel1(&mut apu);
+        assert_eq!(apu.read(NR52), 0xF1);
+
+        apu.tick(FRAME_SEQUENCER_CYCLES * 3 - 1);
+        assert_eq!(apu.read(NR52), 0xF1);
+        apu.tick(1);
+        assert_eq!(apu.read(NR52), 0xF0);
+    }
+
+    #[test]
+    fn the_length_turns_a_channel_off_the_same_while_capturing() {
+        let mut apu: Apu = Apu::default();
+        apu.set_capture(true, false);
+        play_channel1(&mut apu);
+
+        apu.tick(FRAME_SEQUENCER_CYCLES * 3 - 1);
+        assert_eq!(apu.read(NR52), 0xF1);
+        apu.tick(1);
+        assert_eq!(apu.read(NR52), 0xF0);
+        assert!(!apu.take_samples().is_empty());
+    }
+
+    #[test]
+    fn the_frame_sequencer_stops_while_the_sound_is_off() {
+        let mut apu: Apu = Apu::default();
+        apu.tick(FRAME_SEQUENCER_CYCLES * 3 + 5);
+        play_channel1(&mut apu);
+
+        apu.tick(FRAME_SEQUENCER_CYCLES * 3 - 1);
+        assert_eq!(apu.read(NR52), 0xF1);
+    }
+}
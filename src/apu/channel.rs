@@ -0,0 +1,636 @@
+// The four sound channels. Each one produces a digital value from 0 to 15 that
+// goes through its DAC; a channel whose DAC is off outputs nothing at all.
+// Registers are given relative to the channel: 0 is NRx0, ..., 4 is NRx4.
+
+const DUTY_PATTERNS: [u8; 4] = [0b0000_0001, // 12.5%
+                                0b1000_0001, // 25%
+                                0b1000_0111, // 50%
+                                0b0111_1110]; // 75%
+
+const NOISE_DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+fn frequency(low: u8, high: u8) -> u16 {
+    ((high as u16 & 0b111) << 8) | low as u16
+}
+
+// Counts down at 256Hz while enabled (NRx4 bit 6) and turns the channel off
+// when it reaches 0. A trigger with the counter at 0 reloads it with the maximum.
+struct Length {
+    counter: u16,
+    max: u16,
+    enabled: bool,
+}
+
+impl Length {
+    fn new(max: u16) -> Length {
+        Length {
+            counter: 0,
+            max: max,
+            enabled: false,
+        }
+    }
+
+    fn load(&mut self, value: u8) {
+        self.counter = self.max - value as u16;
+    }
+
+    fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.max;
+        }
+    }
+
+    // returns whether the channel has to be turned off.
+    fn step(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+}
+
+// Volume envelope of the square and noise channels (NRx2), stepped at 64Hz.
+struct Envelope {
+    initial_volume: u8,
+    increase: bool,
+    period: u8,
+    volume: u8,
+    timer: u8,
+}
+
+impl Envelope {
+    fn new() -> Envelope {
+        Envelope {
+            initial_volume: 0,
+            increase: false,
+            period: 0,
+            volume: 0,
+            timer: 0,
+        }
+    }
+
+    fn write(&mut self, value: u8) {
+        self.initial_volume = value >> 4;
+        self.increase = (value >> 3) & 0b1 == 0b1;
+        self.period = value & 0b111;
+    }
+
+    // the DAC is only on if the upper 5 bits of NRx2 aren't all 0.
+    fn is_dac_on(&self) -> bool {
+        self.initial_volume != 0 || self.increase
+    }
+
+    fn trigger(&mut self) {
+        self.volume = self.initial_volume;
+        self.timer = self.period;
+    }
+
+    fn step(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.increase && self.volume < 15 {
+                self.volume += 1;
+            } else if !self.increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+}
+
+// Channels 1 and 2; only channel 1 has the frequency sweep (NR10).
+pub struct Square {
+    enabled: bool,
+    has_sweep: bool,
+    duty: u8,
+    frequency: u16,
+    timer: u32,
+    duty_position: u8,
+    length: Length,
+    envelope: Envelope,
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_timer: u8,
+    sweep_enabled: bool,
+    shadow_frequency: u16,
+}
+
+impl Square {
+    pub fn new(has_sweep: bool) -> Square {
+        Square {
+            enabled: false,
+            has_sweep: has_sweep,
+            duty: 0,
+            frequency: 0,
+            timer: 0,
+            duty_position: 0,
+            length: Length::new(64),
+            envelope: Envelope::new(),
+            sweep_period: 0,
+            sweep_negate: false,
+            sweep_shift: 0,
+            sweep_timer: 0,
+            sweep_enabled: false,
+            shadow_frequency: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // the length bits of NRx1, without the duty.
+    pub fn load_length(&mut self, value: u8) {
+        self.length.load(value & 0b11_1111);
+    }
+
+    pub fn write(&mut self, reg: usize, value: u8, regs: &[u8]) {
+        match reg {
+            0 => {
+                self.sweep_period = (value >> 4) & 0b111;
+                self.sweep_negate = (value >> 3) & 0b1 == 0b1;
+                self.sweep_shift = value & 0b111;
+            }
+            1 => {
+                self.duty = value >> 6;
+                self.load_length(value);
+            }
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.is_dac_on() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = frequency(value, regs[4]),
+            4 => {
+                self.frequency = frequency(regs[3], value);
+                self.length.enabled = (value >> 6) & 0b1 == 0b1;
+                if (value >> 7) & 0b1 == 0b1 {
+                    self.trigger();
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.is_dac_on();
+        self.length.trigger();
+        self.envelope.trigger();
+        self.timer = (2048 - self.frequency as u32) * 4;
+        if self.has_sweep {
+            self.shadow_frequency = self.frequency;
+            self.sweep_timer = if self.sweep_period == 0 {
+                8
+            } else {
+                self.sweep_period
+            };
+            self.sweep_enabled = self.sweep_period != 0 || self.sweep_shift != 0;
+            if self.sweep_shift != 0 {
+                self.sweep_frequency();
+            }
+        }
+    }
+
+    // the next frequency of the sweep; going over 2047 turns the channel off.
+    fn sweep_frequency(&mut self) -> u16 {
+        let delta: u16 = self.shadow_frequency >> self.sweep_shift;
+        let frequency: u16 = if self.sweep_negate {
+            self.shadow_frequency - delta
+        } else {
+            self.shadow_frequency + delta
+        };
+        if frequency > 2047 {
+            self.enabled = false;
+        }
+        frequency
+    }
+
+    pub fn step_sweep(&mut self) {
+        if self.sweep_timer > 0 {
+            self.sweep_timer -= 1;
+        }
+        if self.sweep_timer != 0 {
+            return;
+        }
+        self.sweep_timer = if self.sweep_period == 0 {
+            8
+        } else {
+            self.sweep_period
+        };
+        if self.sweep_enabled && self.sweep_period != 0 {
+            let frequency: u16 = self.sweep_frequency();
+            if frequency <= 2047 && self.sweep_shift != 0 {
+                self.frequency = frequency;
+                self.shadow_frequency = frequency;
+                // checked again with the new frequency, but not applied.
+                self.sweep_frequency();
+            }
+        }
+    }
+
+    pub fn step_length(&mut self) {
+        if self.length.step() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn step_envelope(&mut self) {
+        self.envelope.step();
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = (2048 - self.frequency as u32) * 4;
+            self.duty_position = (self.duty_position + 1) % 8;
+        }
+    }
+
+    pub fn output(&self) -> Option<u8> {
+        if !self.envelope.is_dac_on() {
+            return None;
+        }
+        let high: bool = (DUTY_PATTERNS[self.duty as usize] >> (7 - self.duty_position)) & 0b1 ==
+                         0b1;
+        if self.enabled && high {
+            Some(self.envelope.volume)
+        } else {
+            Some(0)
+        }
+    }
+}
+
+// Channel 3 plays the 32 4 bit samples of wave ram.
+pub struct Wave {
+    enabled: bool,
+    dac_on: bool,
+    volume_shift: u8,
+    frequency: u16,
+    timer: u32,
+    position: u8,
+    length: Length,
+}
+
+impl Default for Wave {
+    fn default() -> Wave {
+        Wave {
+            enabled: false,
+            dac_on: false,
+            volume_shift: 0,
+            frequency: 0,
+            timer: 0,
+            position: 0,
+            length: Length::new(256),
+        }
+    }
+}
+
+impl Wave {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn load_length(&mut self, value: u8) {
+        self.length.load(value);
+    }
+
+    pub fn write(&mut self, reg: usize, value: u8, regs: &[u8]) {
+        match reg {
+            0 => {
+                self.dac_on = (value >> 7) & 0b1 == 0b1;
+                if !self.dac_on {
+                    self.enabled = false;
+                }
+            }
+            1 => self.load_length(value),
+            2 => {
+                // 0: mute, 1: 100%, 2: 50%, 3: 25%.
+                self.volume_shift = match (value >> 5) & 0b11 {
+                    0b00 => 4,
+                    code => code - 1,
+                };
+            }
+            3 => self.frequency = frequency(value, regs[4]),
+            4 => {
+                self.frequency = frequency(regs[3], value);
+                self.length.enabled = (value >> 6) & 0b1 == 0b1;
+                if (value >> 7) & 0b1 == 0b1 {
+                    self.enabled = self.dac_on;
+                    self.length.trigger();
+                    self.timer = (2048 - self.frequency as u32) * 2;
+                    self.position = 0;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn step_length(&mut self) {
+        if self.length.step() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = (2048 - self.frequency as u32) * 2;
+            self.position = (self.position + 1) % 32;
+        }
+    }
+
+    pub fn output(&self, wave_ram: &[u8]) -> Option<u8> {
+        if !self.dac_on {
+            return None;
+        }
+        if !self.enabled {
+            return Some(0);
+        }
+        // the upper nibble is played first.
+        let byte: u8 = wave_ram[self.position as usize / 2];
+        let sample: u8 = if self.position % 2 == 0 {
+            byte >> 4
+        } else {
+            byte & 0xF
+        };
+        Some(sample >> self.volume_shift)
+    }
+}
+
+// Channel 4: pseudo random noise from a 15 bit linear feedback shift register.
+pub struct Noise {
+    enabled: bool,
+    clock_shift: u8,
+    width_7: bool, // the register is 7 bits wide, which sounds more like a tone.
+    divisor_code: u8,
+    timer: u32,
+    lfsr: u16,
+    length: Length,
+    envelope: Envelope,
+}
+
+impl Default for Noise {
+    fn default() -> Noise {
+        Noise {
+            enabled: false,
+            clock_shift: 0,
+            width_7: false,
+            divisor_code: 0,
+            timer: 0,
+            lfsr: 0x7FFF,
+            length: Length::new(64),
+            envelope: Envelope::new(),
+        }
+    }
+}
+
+impl Noise {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn load_length(&mut self, value: u8) {
+        self.length.load(value & 0b11_1111);
+    }
+
+    fn period(&self) -> u32 {
+        NOISE_DIVISORS[self.divisor_code as usize] << self.clock_shift
+    }
+
+    pub fn write(&mut self, reg: usize, value: u8) {
+        match reg {
+            0 => (), // there's no NR40.
+            1 => self.load_length(value),
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.is_dac_on() {
+                    self.enabled = false;
+                }
+            }
+            3 => {
+                self.clock_shift = value >> 4;
+                self.width_7 = (value >> 3) & 0b1 == 0b1;
+                self.divisor_code = value & 0b111;
+            }
+            4 => {
+                self.length.enabled = (value >> 6) & 0b1 == 0b1;
+                if (value >> 7) & 0b1 == 0b1 {
+                    self.enabled = self.envelope.is_dac_on();
+                    self.length.trigger();
+                    self.envelope.trigger();
+                    self.timer = self.period();
+                    self.lfsr = 0x7FFF;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn step_length(&mut self) {
+        if self.length.step() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn step_envelope(&mut self) {
+        self.envelope.step();
+    }
+
+    pub fn tick(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = self.period();
+            let bit: u16 = (self.lfsr ^ (self.lfsr >> 1)) & 0b1;
+            self.lfsr = (self.lfsr >> 1) | (bit << 14);
+            if self.width_7 {
+                self.lfsr = (self.lfsr & !(0b1 << 6)) | (bit << 6);
+            }
+        }
+    }
+
+    pub fn output(&self) -> Option<u8> {
+        if !self.envelope.is_dac_on() {
+            return None;
+        }
+        // the output is the inverted bit 0.
+        if self.enabled && self.lfsr & 0b1 == 0b0 {
+            Some(self.envelope.volume)
+        } else {
+            Some(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Noise, Square, Wave};
+
+    // writes NRx0..NRx4 in order, so the last write is the one that can trigger.
+    fn write_square(square: &mut Square, regs: [u8; 5]) {
+        for reg in 0..5 {
+            square.write(reg, regs[reg], &regs);
+        }
+    }
+
+    fn write_wave(wave: &mut Wave, regs: [u8; 5]) {
+        for reg in 0..5 {
+            wave.write(reg, regs[reg], &regs);
+        }
+    }
+
+    #[test]
+    fn a_sweep_overflowing_on_trigger_turns_the_channel_off() {
+        let mut square: Square = Square::new(true);
+        // period 1, adding shift 1: 0x7FF + 0x3FF is over 2047.
+        write_square(&mut square, [0b0001_0001, 0, 0xF0, 0xFF, 0x87]);
+
+        assert!(!square.is_enabled());
+    }
+
+    #[test]
+    fn a_sweep_overflowing_on_a_step_turns_the_channel_off() {
+        let mut square: Square = Square::new(true);
+        // 0x500 + 0x280 still fits, the check after applying it doesn't.
+        write_square(&mut square, [0b0001_0001, 0, 0xF0, 0x00, 0x85]);
+        assert!(square.is_enabled());
+
+        square.step_sweep();
+        assert_eq!(square.frequency, 0x780);
+        assert!(!square.is_enabled());
+    }
+
+    #[test]
+    fn a_negated_sweep_never_overflows() {
+        let mut square: Square = Square::new(true);
+        write_square(&mut square, [0b0001_1001, 0, 0xF0, 0xFF, 0x87]);
+
+        square.step_sweep();
+        assert_eq!(square.frequency, 0x400);
+        assert!(square.is_enabled());
+    }
+
+    #[test]
+    fn the_length_counter_turns_the_channel_off_when_it_expires() {
+        let mut square: Square = Square::new(false);
+        // 64 - 62 = 2 steps, with the length enabled.
+        write_square(&mut square, [0, 62, 0xF0, 0, 0xC0]);
+
+        square.step_length();
+        assert!(square.is_enabled());
+        square.step_length();
+        assert!(!square.is_enabled());
+    }
+
+    #[test]
+    fn the_length_counter_only_counts_when_enabled() {
+        let mut square: Square = Square::new(false);
+        write_square(&mut square, [0, 63, 0xF0, 0, 0x80]);
+
+        for _ in 0..64 {
+            square.step_length();
+        }
+        assert!(square.is_enabled());
+    }
+
+    #[test]
+    fn triggering_after_the_length_expired_reloads_the_full_length() {
+        let mut wave: Wave = Wave::default();
+        write_wave(&mut wave, [0x80, 0xFF, 0x20, 0, 0xC0]);
+        wave.step_length();
+        assert!(!wave.is_enabled());
+
+        wave.write(4, 0xC0, &[0x80, 0xFF, 0x20, 0, 0xC0]);
+        for _ in 0..255 {
+            wave.step_length();
+        }
+        assert!(wave.is_enabled());
+        wave.step_length();
+        assert!(!wave.is_enabled());
+    }
+
+    #[test]
+    fn the_7_bit_noise_feeds_back_into_bit_6_too() {
+        let mut noise: Noise = Noise::default();
+        noise.write(2, 0xF0);
+        noise.write(3, 0b0000_1000); // 7 bits, divisor 8, no shift.
+        noise.write(4, 0x80);
+
+        let mut lfsrs: Vec<u16> = Vec::new();
+        for _ in 0..8 {
+            for _ in 0..8 {
+                noise.tick();
+            }
+            lfsrs.push(noise.lfsr);
+        }
+        assert_eq!(lfsrs,
+                   vec![0x3FBF, 0x1F9F, 0x0F8F, 0x0787, 0x0383, 0x0181, 0x40C0, 0x2020]);
+    }
+
+    #[test]
+    fn the_7_bit_noise_repeats_every_127_steps() {
+        let mut noise: Noise = Noise::default();
+        noise.write(2, 0xF0);
+        noise.write(3, 0b0000_1000);
+        noise.write(4, 0x80);
+
+        let mut outputs: Vec<Option<u8>> = Vec::new();
+        for _ in 0..(127 * 3) {
+            for _ in 0..8 {
+                noise.tick();
+            }
+            outputs.push(noise.output());
+        }
+        assert_eq!(outputs[127..254], outputs[254..]);
+        assert!(outputs[127..254] != outputs[128..255]);
+    }
+
+    #[test]
+    fn the_wave_ram_is_played_upper_nibble_first() {
+        let mut wave: Wave = Wave::default();
+        let wave_ram: [u8; 16] = [0x12, 0x34, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        // 100% volume, frequency 2047: a sample every 2 cycles.
+        write_wave(&mut wave, [0x80, 0, 0x20, 0xFF, 0x87]);
+
+        let mut samples: Vec<Option<u8>> = vec![wave.output(&wave_ram)];
+        for _ in 0..3 {
+            wave.tick();
+            wave.tick();
+            samples.push(wave.output(&wave_ram));
+        }
+        assert_eq!(samples, vec![Some(0x1), Some(0x2), Some(0x3), Some(0x4)]);
+    }
+
+    #[test]
+    fn the_wave_volume_shifts_the_samples() {
+        let wave_ram: [u8; 16] = [0xF0; 16];
+        let expected: [(u8, u8); 4] = [(0x00, 0x0), (0x20, 0xF), (0x40, 0x7), (0x60, 0x3)];
+        for &(nr32, sample) in &expected {
+            let mut wave: Wave = Wave::default();
+            write_wave(&mut wave, [0x80, 0, nr32, 0, 0x80]);
+            assert_eq!(wave.output(&wave_ram), Some(sample));
+        }
+    }
+
+    #[test]
+    fn the_wave_channel_is_silent_with_the_dac_off() {
+        let mut wave: Wave = Wave::default();
+        write_wave(&mut wave, [0x00, 0, 0x20, 0, 0x80]);
+
+        assert!(!wave.is_enabled());
+        assert_eq!(wave.output(&[0xFF; 16]), None);
+    }
+}
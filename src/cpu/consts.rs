@@ -29,14 +29,24 @@ pub const OBP_1_REGISTER_ADDR: u16 = 0xFF49;
 
 pub const JOYPAD_REGISTER_ADDR: u16 = 0xFF00;
 
-pub const CPU_FREQUENCY_HZ: u32 = 4194304; //that is, number of cycles per second.
+// Serial transfer data and control
+pub const SB_REGISTER_ADDR: u16 = 0xFF01;
+pub const SC_REGISTER_ADDR: u16 = 0xFF02;
+
+// Sound registers and wave pattern ram
+pub const SOUND_REGISTERS_ADDR_START: u16 = 0xFF10;
+pub const SOUND_REGISTERS_ADDR_END: u16 = 0xFF3F;
+
+// any write disables the bootstrap rom
+pub const BOOTSTRAP_REGISTER_ADDR: u16 = 0xFF50;
 
-pub const DIV_REGISTER_UPDATE_RATE_HZ: u32 = 16384;
-pub const DIV_REGISTER_UPDATE_RATE_CYCLES: u32 = CPU_FREQUENCY_HZ / DIV_REGISTER_UPDATE_RATE_HZ;
+// CGB only: prepare speed switch
+pub const KEY1_REGISTER_ADDR: u16 = 0xFF4D;
+
+pub const CPU_FREQUENCY_HZ: u32 = 4194304; //that is, number of cycles per second.
 
-pub const DMA_DURATION_CYCLES: u32 = CPU_FREQUENCY_HZ / (1000000 / 160);
+pub const SERIAL_CLOCK_HZ: u32 = 8192;
 
-pub const STAT_MODE_0_DURATION_CYCLES: u32 = 201;
-pub const STAT_MODE_1_DURATION_CYCLES: u32 = 456;
-pub const STAT_MODE_2_DURATION_CYCLES: u32 = 77;
-pub const STAT_MODE_3_DURATION_CYCLES: u32 = 169;
+pub const SCANLINE_DURATION_CYCLES: u32 = 456;
+pub const STAT_MODE_2_DURATION_CYCLES: u32 = 80;
+pub const STAT_MODE_3_DURATION_CYCLES: u32 = 172;
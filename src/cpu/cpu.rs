@@ -1,9 +1,9 @@
 use std::fmt;
-use super::super::mem::mem;
+use super::super::mem::bus::Bus;
 use super::super::util::util;
 use super::super::debugger;
-use cpu::{ioregister, interrupt, consts};
-use super::super::timeline::{Event, EventType};
+use cpu::{interrupt, consts, opcodes};
+use cpu::opcodes::{Opcode, Op, Operand, Cond, Alu};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 enum Flag {
@@ -13,6 +13,7 @@ enum Flag {
     C,
 }
 
+// the order matters, see REG_INDEX.
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Reg {
     A,
@@ -31,34 +32,6 @@ pub enum Reg {
     PC,
 }
 
-impl Reg {
-    #[inline]
-    pub fn pair_from_ddd(byte: u8) -> Reg {
-        match byte & 0b111 {
-            0b000 => Reg::B,
-            0b001 => Reg::C,
-            0b010 => Reg::D,
-            0b011 => Reg::E,
-            0b100 => Reg::H,
-            0b101 => Reg::L,
-            0b110 => Reg::HL,
-            0b111 => Reg::A,
-            _ => unreachable!(),
-        }
-    }
-
-    #[inline]
-    pub fn pair_from_dd(byte: u8) -> Reg {
-        match byte & 0b11 {
-            0b00 => Reg::BC,
-            0b01 => Reg::DE,
-            0b10 => Reg::HL,
-            0b11 => Reg::SP,
-            _ => unreachable!(),
-        }
-    }
-}
-
 #[derive(Copy, Clone, Debug)]
 pub struct Instruction {
     pub prefix: Option<u8>,
@@ -125,9 +98,14 @@ pub struct Cpu {
     regs: [u8; 12],
     ime_flag: bool, // interrupt master enable flag
     halt_flag: bool, // cpu doesn't run until an interrupt occurs.
+    // HALT executed with IME=0 and an interrupt already pending doesn't halt, but the
+    // byte after it is read twice because PC fails to increment.
+    halt_bug: bool,
+    stop_flag: bool, // system clock is halted until a joypad line goes low.
+    locked: bool, // an illegal opcode hung the cpu; only a restart gets it back.
     last_instruction: Option<Instruction>,
-    disable_interrupts: bool,
-    enable_interrupts: bool,
+    enable_interrupts: bool, // EI was run: IME is set after the next instruction.
+    cycles: u32, // cycles spent by the instruction being run so far.
 }
 
 impl fmt::Display for Cpu {
@@ -144,6 +122,9 @@ impl fmt::Display for Cpu {
 
             i += 2;
         }
+        if self.locked {
+            regs = regs + "LOCKED";
+        }
         write!(f, "{} {}", flags, regs)
     }
 }
@@ -154,56 +135,66 @@ impl Default for Cpu {
             regs: [0; 12],
             ime_flag: true,
             halt_flag: false,
+            halt_bug: false,
+            stop_flag: false,
+            locked: false,
             last_instruction: None,
-            disable_interrupts: false,
             enable_interrupts: false,
+            cycles: 0,
         }
     }
 }
 
+// index in Cpu::regs of each register, in the order of Reg.
+const REG_INDEX: [usize; 14] = [0, 1, 2, 3, 4, 5, 6, 7, 0, 2, 4, 6, 8, 10];
+
 impl Cpu {
     #[inline]
     fn reg_index(reg: Reg) -> usize {
-        match reg {
-            Reg::A | Reg::AF => 0,
-            Reg::F => 1,
-            Reg::B | Reg::BC => 2,
-            Reg::C => 3,
-            Reg::D | Reg::DE => 4,
-            Reg::E => 5,
-            Reg::H | Reg::HL => 6,
-            Reg::L => 7,
-            Reg::SP => 8,
-            Reg::PC => 10,
-        }
+        REG_INDEX[reg as usize]
     }
 
     #[inline]
     fn reg_is8(reg: Reg) -> bool {
-        match reg {
-            Reg::A |
-            Reg::F |
-            Reg::B |
-            Reg::C |
-            Reg::D |
-            Reg::E |
-            Reg::H |
-            Reg::L => true,
-            _ => false,
-        }
+        // the 8 bit registers come first in Reg.
+        reg as u8 <= Reg::L as u8
+    }
+
+    pub fn ime(&self) -> bool {
+        self.ime_flag
+    }
+
+    pub fn set_ime(&mut self, ime: bool) {
+        self.ime_flag = ime;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop_flag
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    // a joypad line went low.
+    pub fn resume_from_stop(&mut self) {
+        self.stop_flag = false;
     }
 
     pub fn restart(&mut self) {
         self.regs = [0; 12];
         self.ime_flag = true;
         self.halt_flag = false;
+        self.halt_bug = false;
+        self.stop_flag = false;
+        self.locked = false;
         self.last_instruction = None;
-        self.disable_interrupts = false;
         self.enable_interrupts = false;
+        self.cycles = 0;
     }
 
     #[inline]
-    fn reg_set16(&mut self, reg: Reg, value: u16) {
+    pub fn reg_set16(&mut self, reg: Reg, value: u16) {
         let index: usize = Cpu::reg_index(reg);
         if Cpu::reg_is8(reg) {
             self.regs[index] = value as u8;
@@ -214,7 +205,7 @@ impl Cpu {
     }
 
     #[inline]
-    fn reg_set8(&mut self, reg: Reg, value: u8) {
+    pub fn reg_set8(&mut self, reg: Reg, value: u8) {
         self.reg_set16(reg, value as u16);
     }
 
@@ -229,7 +220,7 @@ impl Cpu {
     }
 
     #[inline]
-    fn reg8(&self, reg: Reg) -> u8 {
+    pub fn reg8(&self, reg: Reg) -> u8 {
         if !Cpu::reg_is8(reg) {
             panic!("Trying to get 8 bits from 16-bit register: {:?}", reg)
         }
@@ -290,27 +281,27 @@ impl Cpu {
     }
 
     #[inline]
-    fn push_sp8(&mut self, value: u8, memory: &mut mem::Memory) {
-        let sp: u16 = self.reg16(Reg::SP) - 1;
+    fn push_sp8<B: Bus>(&mut self, value: u8, memory: &mut B) {
+        let sp: u16 = self.reg16(Reg::SP).wrapping_sub(1);
         self.mem_write(sp, value, memory);
         self.reg_set16(Reg::SP, sp);
     }
 
     #[inline]
-    fn push_sp16(&mut self, value: u16, memory: &mut mem::Memory) {
+    fn push_sp16<B: Bus>(&mut self, value: u16, memory: &mut B) {
         self.push_sp8((value >> 8) as u8, memory);
         self.push_sp8(value as u8, memory);
     }
 
     #[inline]
-    fn pop_sp8(&mut self, memory: &mem::Memory) -> u8 {
+    fn pop_sp8<B: Bus>(&mut self, memory: &mut B) -> u8 {
         let sp: u16 = self.reg16(Reg::SP);
-        self.reg_set16(Reg::SP, sp + 1);
-        memory.read_byte(sp)
+        self.reg_set16(Reg::SP, sp.wrapping_add(1));
+        self.mem_read(sp, memory)
     }
 
     #[inline]
-    fn pop_sp16(&mut self, memory: &mem::Memory) -> u16 {
+    fn pop_sp16<B: Bus>(&mut self, memory: &mut B) -> u16 {
         let lo: u8 = self.pop_sp8(memory);
         let hi: u8 = self.pop_sp8(memory);
         ((hi as u16) << 8) | lo as u16
@@ -336,14 +327,36 @@ impl Cpu {
         }
     }
 
+    // Every memory access takes one M-cycle (4 cycles). The rest of the system
+    // is brought up to date before the access happens, so that it sees the
+    // access at the right time (e.g. a write to VRAM in the middle of mode 3).
+    #[inline]
+    fn mem_read<B: Bus>(&mut self, address: u16, memory: &mut B) -> u8 {
+        self.internal_cycle(memory);
+        memory.read_byte(address)
+    }
+
+    #[inline]
+    fn mem_write<B: Bus>(&mut self, address: u16, value: u8, memory: &mut B) {
+        self.internal_cycle(memory);
+        memory.write_byte(address, value);
+    }
+
+    // M-cycle in which the cpu doesn't access memory.
+    #[inline]
+    fn internal_cycle<B: Bus>(&mut self, memory: &mut B) {
+        memory.tick(4);
+        self.cycles += 4;
+    }
+
     #[inline]
-    fn mem_at_reg(&self, reg: Reg, memory: &mem::Memory) -> u8 {
+    fn mem_at_reg<B: Bus>(&mut self, reg: Reg, memory: &mut B) -> u8 {
         let addr: u16 = self.reg16(reg);
-        memory.read_byte(addr)
+        self.mem_read(addr, memory)
     }
 
     #[inline]
-    fn mem_next8(&mut self, memory: &mem::Memory) -> u8 {
+    fn mem_next8<B: Bus>(&mut self, memory: &mut B) -> u8 {
         let value: u8 = self.mem_at_reg(Reg::PC, memory);
         self.increment_reg(Reg::PC);
         value
@@ -351,1039 +364,851 @@ impl Cpu {
 
     // next 2 bytes.
     #[inline]
-    fn mem_next16(&mut self, memory: &mem::Memory) -> u16 {
+    fn mem_next16<B: Bus>(&mut self, memory: &mut B) -> u16 {
         let n1: u16 = self.mem_next8(memory) as u16;
         let n2: u16 = self.mem_next8(memory) as u16;
 
         (n2 << 8) | n1
     }
 
-    // function for having control of memory writes
-    #[inline]
-    fn mem_write(&self, address: u16, value: u8, memory: &mut mem::Memory) {
-        let value: u8 = match address {
-            consts::DIV_REGISTER_ADDR | consts::LY_REGISTER_ADDR => 0,
-            _ => value,
-        };
-        memory.write_byte(address, value);
-    }
-
-    pub fn handle_interrupts(&mut self, memory: &mut mem::Memory) {
-        if self.ime_flag {
-            if let Some(interrupt) = interrupt::next_request(memory) {
-                self.halt_flag = false;
-                self.ime_flag = false;
-                let pc: u16 = self.reg16(Reg::PC);
-                self.push_sp16(pc, memory);
+    // Dispatching takes 5 M-cycles: two idle ones, pushing PC and jumping to the
+    // handler. Returns the number of cycles spent (0 if there was nothing to dispatch).
+    pub fn handle_interrupts<B: Bus>(&mut self, memory: &mut B) -> u32 {
+        if self.locked || !self.ime_flag || interrupt::next_request(memory).is_none() {
+            return 0;
+        }
+        self.cycles = 0;
+        self.halt_flag = false;
+        self.ime_flag = false;
+        self.internal_cycle(memory);
+        self.internal_cycle(memory);
+        let pc: u16 = self.reg16(Reg::PC);
+        self.push_sp8((pc >> 8) as u8, memory);
+        // The interrupt to be serviced is only chosen now: if SP was 0x0000, the push
+        // above has just overwritten IE and may have disabled the requested interrupt.
+        let next: Option<interrupt::Interrupt> = interrupt::next_request(memory);
+        self.push_sp8(pc as u8, memory);
+        match next {
+            Some(interrupt) => {
                 self.reg_set16(Reg::PC, interrupt::address(interrupt));
                 interrupt::remove_request(interrupt, memory);
-                // since the interrupt request is removed and interrupts are disabled,
-                // simply returning to the main loop seems correct.
+            }
+            None => {
+                // dispatch cancelled.
+                self.reg_set16(Reg::PC, 0x0000);
             }
         }
+        self.internal_cycle(memory);
+        self.cycles
     }
 
-    pub fn run_instruction(&mut self, memory: &mut mem::Memory) -> (Instruction, Option<Event>) {
+    pub fn run_instruction<B: Bus>(&mut self, memory: &mut B) -> Instruction {
 
-        if self.halt_flag {
-            return (self.last_instruction.unwrap(), None);
+        self.cycles = 0;
+        if self.stop_flag {
+            // nothing runs, not even the timer or the lcd.
+            let mut instruction: Instruction = self.last_instruction.unwrap();
+            instruction.cycles = 0;
+            return instruction;
         }
-
-        // Actually performs DI and EI at the right time.
-        // The order of these if's *has* to be like this.
-        if self.disable_interrupts {
-            self.ime_flag = false;
-            self.disable_interrupts = false;
-        } else if self.enable_interrupts {
-            self.ime_flag = true;
-            self.enable_interrupts = false;
+        if self.locked {
+            // the rest of the system keeps running, but the cpu never fetches again.
+            self.internal_cycle(memory);
+            return self.last_instruction.unwrap();
         }
-        if let Some(ref last_instr) = self.last_instruction {
-            match last_instr.opcode {
-                0xF3 => {
-                    // DI
-                    self.disable_interrupts = true;
-                }
-                0xFB => {
-                    // EI
-                    self.enable_interrupts = true;
-                }
-                _ => (),
+        if self.halt_flag {
+            // any enabled interrupt wakes the cpu up, even with IME=0 (in which case
+            // it just carries on after the HALT without jumping to the handler).
+            self.internal_cycle(memory);
+            if interrupt::next_request(memory).is_some() {
+                self.halt_flag = false;
             }
+            return self.last_instruction.unwrap();
         }
-        // *********************************************
 
-        let mut event: Option<Event> = None;
+        // EI only takes effect after the instruction following it.
+        let enable_interrupts: bool = self.enable_interrupts;
+
         let addr: u16 = self.reg16(Reg::PC);
-        let byte: u8 = self.mem_next8(memory);
+        // the HALT bug makes the cpu read the opcode without incrementing PC.
+        let halt_bug: bool = self.halt_bug;
+        self.halt_bug = false;
+        let byte: u8 = if halt_bug {
+            self.mem_at_reg(Reg::PC, memory)
+        } else {
+            self.mem_next8(memory)
+        };
         let mut instruction: Instruction = Instruction::default();
         instruction.opcode = byte;
-        match byte {
+        instruction.address = addr;
+
+        let mut opcode: &Opcode = &opcodes::OPCODES[byte as usize];
+        if opcode.op == Op::Prefix {
+            let cb_byte: u8 = self.mem_next8(memory);
+            instruction.prefix = Some(byte);
+            instruction.opcode = cb_byte;
+            opcode = &opcodes::CB_OPCODES[cb_byte as usize];
+        }
+        // immediates always come right after the opcode.
+        match opcode.operand {
+            Operand::None => (),
+            Operand::Imm8 => instruction.imm8 = Some(self.mem_next8(memory)),
+            Operand::Imm16 => instruction.imm16 = Some(self.mem_next16(memory)),
+        }
+        let fetched: u16 = self.reg16(Reg::PC).wrapping_sub(addr) + halt_bug as u16;
+        debug_assert!(fetched == opcode.length as u16,
+                      "{}: expected {} bytes, fetched {}",
+                      opcode.mnemonic,
+                      opcode.length,
+                      fetched);
+
+        let branch_taken: bool = self.execute(opcode.op, &instruction, memory);
+        instruction.cycles = if branch_taken {
+            opcode.branch_cycles
+        } else {
+            opcode.cycles
+        };
+        debug_assert!(instruction.cycles == self.cycles,
+                      "{}: expected {} cycles, ran {}",
+                      opcode.mnemonic,
+                      instruction.cycles,
+                      self.cycles);
+
+        if enable_interrupts && self.enable_interrupts {
+            self.ime_flag = true;
+            self.enable_interrupts = false;
+        }
+        self.last_instruction = Some(instruction);
+        instruction
+    }
+
+    // returns true if a conditional branch was taken. Always inlined, otherwise
+    // dispatching through the table is slower than the old match on the opcode.
+    #[inline(always)]
+    fn execute<B: Bus>(&mut self,
+                       op: Op,
+                       instruction: &Instruction,
+                       memory: &mut B)
+                       -> bool {
+        let mut branch_taken: bool = false;
+        match op {
             /***************************************/
             /*      Misc/Control instructions      */
             /***************************************/
-            0x0 => {
-                //NOP
-                instruction.cycles = 4;
-                if addr == 0x100 {
-                    event = Some(Event::new(0, EventType::BootstrapFinished));
+            Op::Nop => (),
+            Op::Stop => {
+                // the byte after STOP is skipped.
+                if !memory.speed_switch() {
+                    self.stop_flag = true;
                 }
-            },
-            0x10 => {
-                //STOP
-                self.halt_flag = true;
-                instruction.cycles = 4;
-                ioregister::LCDCRegister::disable_lcd(memory);
-            },
-            0x76 => {
-                //HALT
-                instruction.cycles = 4;
-                self.halt_flag = true;
-            },
-            0xF3 | 0xFB => {
-                //DI | EI
-                instruction.cycles = 4;
-            },
-            0xCB => {
-                //CB-prefixed
-                instruction = self.exec_cb_prefixed(memory);
-            },
-            /**************************************/
-            /*      8 bit rotations/shifts        */
-            /**************************************/
-            0x07 | 0x17 | 0x0F | 0x1F => {
-                //RLCA; RLA; RRCA; RRA
-                instruction = self.exec_rotates_shifts(byte);
-            },
+                memory.write_byte(consts::DIV_REGISTER_ADDR, 0);
+            }
+            Op::Halt => {
+                // an EI right before HALT has already enabled interrupts by the time
+                // the cpu halts, so it doesn't trigger the bug.
+                let ime: bool = self.ime_flag || self.enable_interrupts;
+                if !ime && interrupt::next_request(memory).is_some() {
+                    self.halt_bug = true;
+                } else {
+                    self.halt_flag = true;
+                }
+            }
+            Op::Di => {
+                self.ime_flag = false;
+                self.enable_interrupts = false;
+            }
+            Op::Ei => {
+                self.enable_interrupts = true;
+            }
+            Op::Prefix => unreachable!(),
+            Op::Illegal => {
+                // 0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC and 0xFD
+                // don't exist: the cpu locks up.
+                self.locked = true;
+            }
             /**************************************/
             /* 8 bit load/store/move instructions */
             /**************************************/
-            0x02 | 0x12 => {
-                //LD (rr),A;
-                instruction = self.exec_ld_nn_a(byte, memory);
-            },
-            0x22 => {
-                //LD (HL+),A
-                instruction = self.exec_ld_nn_a(byte, memory);
-                self.increment_reg(Reg::HL);
-            },
-            0x32 => {
-                //LD (HL-),A
-                instruction = self.exec_ld_nn_a(byte, memory);
-                self.decrement_reg(Reg::HL);
-            },
-            0x0A | 0x1A => {
-                //LD A,(rr);
-                instruction = self.exec_ld_a_nn(byte, memory);
-            },
-            0x2A => {
-                //LD A,(HL+);
-                instruction = self.exec_ld_a_nn(byte, memory);
-                self.increment_reg(Reg::HL);
-            },
-            0x3A => {
-                //LD A,(HL-)
-                instruction = self.exec_ld_a_nn(byte, memory);
-                self.decrement_reg(Reg::HL);
-            },
-            0x06 | 0x16 | 0x26 |
-            0x0E | 0x1E | 0x2E |
-            0x3E | 0x36 => {
-                //LD r,n; LD (HL),n
-                let reg: Reg = Reg::pair_from_ddd(byte >> 3);
-                let immediate: u8 = self.mem_next8(memory);
-
-                let cycles: u32;
-                if reg == Reg::HL {
-                    // LD (HL),n
-                    let addr: u16 = self.reg16(Reg::HL);
-                    self.mem_write(addr, immediate, memory);
-                    cycles = 12;
+            Op::Ld(lhs, rhs) => {
+                let value: u8 = self.read_operand8(rhs, memory);
+                self.write_operand8(lhs, value, memory);
+            }
+            Op::LdImm(reg) => {
+                self.write_operand8(reg, instruction.imm8.unwrap(), memory);
+            }
+            Op::LdIndirectA(reg) => {
+                let addr: u16 = self.reg16(reg);
+                let a: u8 = self.reg8(Reg::A);
+                self.mem_write(addr, a, memory);
+            }
+            Op::LdAIndirect(reg) => {
+                let value: u8 = self.mem_at_reg(reg, memory);
+                self.reg_set8(Reg::A, value);
+            }
+            Op::LdHliA | Op::LdHldA => {
+                let addr: u16 = self.reg16(Reg::HL);
+                let a: u8 = self.reg8(Reg::A);
+                self.mem_write(addr, a, memory);
+                if op == Op::LdHliA {
+                    self.increment_reg(Reg::HL);
                 } else {
-                    // LD r,n
-                    self.reg_set8(reg, immediate);
-                    cycles = 8
+                    self.decrement_reg(Reg::HL);
                 }
-
-                instruction.cycles = cycles;
-                instruction.imm8 = Some(immediate);
-            },
-            0x40 ... 0x75 |
-            0x77 ... 0x7F => {
-                //LD r,r; LD r,(HL); LD (HL),r
-                let reg_rhs: Reg = Reg::pair_from_ddd(byte);
-                let reg_lhs: Reg = Reg::pair_from_ddd(byte >> 3);
-
-                let cycles: u32;
-                if reg_rhs == Reg::HL {
-                    let value: u8 = self.mem_at_reg(Reg::HL, memory);
-                    self.reg_set8(reg_lhs, value);
-                    cycles = 8;
-                } else if reg_lhs == Reg::HL {
-                    let addr: u16 = self.reg16(Reg::HL);
-                    let rhs_val: u8 = self.reg8(reg_rhs);
-                    self.mem_write(addr, rhs_val, memory);
-                    cycles = 8;
+            }
+            Op::LdAHli | Op::LdAHld => {
+                let value: u8 = self.mem_at_reg(Reg::HL, memory);
+                self.reg_set8(Reg::A, value);
+                if op == Op::LdAHli {
+                    self.increment_reg(Reg::HL);
                 } else {
-                    let rhs_val: u8 = self.reg8(reg_rhs);
-                    self.reg_set8(reg_lhs, rhs_val);
-                    cycles = 4;
-                }
-
-                instruction.cycles = cycles;
-            },
-            0xE0 => {
-                //LDH (n),A
-                let immediate: u16 = 0xFF00 + (self.mem_next8(memory) as u16);
-                if immediate == consts::DMA_REGISTER_ADDR {
-                    let mut e: Event = Event::new(
-                        consts::DMA_DURATION_CYCLES,
-                        EventType::DMATransfer);
-                    e.additional_value = self.reg8(Reg::A);
-                    event = Some(e);
-                } else if immediate == consts::JOYPAD_REGISTER_ADDR {
-                    let e: Event = Event::new(
-                        0, EventType::JoypadPressed);
-                    event = Some(e);
+                    self.decrement_reg(Reg::HL);
                 }
-                self.mem_write(immediate, self.reg8(Reg::A), memory);
-                instruction.cycles = 12;
-                instruction.imm8 = Some(immediate as u8);
-            },
-            0xF0 => {
-                //LDH A,(n)
-                let immediate: u8 = self.mem_next8(memory);
-                let value: u8 = memory.read_byte(0xFF00 + (immediate as u16));
+            }
+            Op::LdhImmA | Op::LdhCA => {
+                let addr: u16 = if op == Op::LdhImmA {
+                    0xFF00 + (instruction.imm8.unwrap() as u16)
+                } else {
+                    0xFF00 + (self.reg8(Reg::C) as u16)
+                };
+                let a: u8 = self.reg8(Reg::A);
+                self.mem_write(addr, a, memory);
+            }
+            Op::LdhAImm | Op::LdhAC => {
+                let addr: u16 = if op == Op::LdhAImm {
+                    0xFF00 + (instruction.imm8.unwrap() as u16)
+                } else {
+                    0xFF00 + (self.reg8(Reg::C) as u16)
+                };
+                let value: u8 = self.mem_read(addr, memory);
                 self.reg_set8(Reg::A, value);
-                instruction.cycles = 12;
-                instruction.imm8 = Some(immediate);
-            },
-            0xE2 => {
-                //LD (C),A
-                let addr: u16 = 0xFF00 + (self.reg8(Reg::C) as u16);
-                if addr == consts::DMA_REGISTER_ADDR {
-                    let mut e: Event = Event::new(
-                        consts::DMA_DURATION_CYCLES,
-                        EventType::DMATransfer);
-                    e.additional_value = self.reg8(Reg::A);
-                    event = Some(e);
-                } else if addr == consts::JOYPAD_REGISTER_ADDR {
-                    let e: Event = Event::new(
-                        0, EventType::JoypadPressed);
-                    event = Some(e);
-                }
-                self.mem_write(addr, self.reg8(Reg::A), memory);
-                instruction.cycles = 8
-            },
-            0xF2 => {
-                //LD A,(C)
-                let value: u8 = memory.read_byte(0xFF00 + (self.reg8(Reg::C) as u16));
+            }
+            Op::LdAbsA => {
+                let a: u8 = self.reg8(Reg::A);
+                self.mem_write(instruction.imm16.unwrap(), a, memory);
+            }
+            Op::LdAAbs => {
+                let value: u8 = self.mem_read(instruction.imm16.unwrap(), memory);
                 self.reg_set8(Reg::A, value);
-                instruction.cycles = 8
-            },
-            0xEA => {
-                //LD (nn),A
-                let val: u16 = self.mem_next16(memory);
-                self.mem_write(val, self.reg8(Reg::A), memory);
-                instruction.cycles = 16;
-                instruction.imm16 = Some(val);
-            },
-            0xFA => {
-                //LD A,(nn)
-                let addr: u16 = self.mem_next16(memory);
-                let val: u8 = memory.read_byte(addr);
-                self.reg_set8(Reg::A, val);
-                instruction.cycles = 16;
-                instruction.imm16 = Some(addr);
-            },
+            }
             /***************************************/
             /* 16 bit load/store/move instructions */
             /***************************************/
-            0x01 | 0x11 | 0x21 | 0x31 => {
-                //LD rr,nn
-                let reg: Reg = Reg::pair_from_dd(byte >> 4);
-                let val: u16 = self.mem_next16(memory);
-                self.reg_set16(reg, val);
-                instruction.cycles = 12;
-                instruction.imm16 = Some(val);
-            },
-            0x08 => {
-                //LD (nn), SP
-                let addr: u16 = self.mem_next16(memory);
+            Op::LdImm16(reg) => {
+                self.reg_set16(reg, instruction.imm16.unwrap());
+            }
+            Op::LdAbsSp => {
+                let addr: u16 = instruction.imm16.unwrap();
                 let val: u16 = self.reg16(Reg::SP);
                 self.mem_write(addr, val as u8, memory);
-                self.mem_write(addr+1, (val >> 8) as u8, memory);
-                instruction.cycles = 20;
-                instruction.imm16 = Some(addr);
-            },
-            0xC1 | 0xD1 | 0xE1 | 0xF1 => {
-                //POP rr
-                let mut reg: Reg = Reg::pair_from_dd(byte >> 4);
-                if reg == Reg::SP {
-                    reg = Reg::AF;
-                }
-                let sp_val: u16 = self.pop_sp16(memory);
-                self.reg_set16(reg, sp_val);
-                instruction.cycles = 12;
-            },
-            0xC5 | 0xD5 | 0xE5 | 0xF5 => {
-                //PUSH rr
-                let mut reg: Reg = Reg::pair_from_dd(byte >> 4);
-                if reg == Reg::SP {
-                    reg = Reg::AF;
+                self.mem_write(addr.wrapping_add(1), (val >> 8) as u8, memory);
+            }
+            Op::Pop(reg) => {
+                let val: u16 = self.pop_sp16(memory);
+                if reg == Reg::AF {
+                    // the lower nibble of F doesn't exist.
+                    self.reg_set16(reg, val & 0xFFF0);
+                } else {
+                    self.reg_set16(reg, val);
                 }
+            }
+            Op::Push(reg) => {
                 let val: u16 = self.reg16(reg);
+                self.internal_cycle(memory);
                 self.push_sp16(val, memory);
-                instruction.cycles = 16;
-            },
-            0xF8 => {
-                //LD HL,SP+n
-                let immediate: u16 = util::sign_extend(self.mem_next8(memory));
-                let sp: u16 = self.reg16(Reg::SP);
-                if util::is_neg16(immediate) {
-                    let res: u16 = sp.wrapping_sub(util::twos_complement(immediate));
-                    self.reg_set16(Reg::HL, res);
-                    self.flag_set((res & 0xff) <= (sp & 0xff), Flag::C);
-                    self.flag_set((res & 0xf) <= (sp & 0xf), Flag::H);
-                } else {
-                    let res: u16 = sp.wrapping_add(immediate);
-                    self.reg_set16(Reg::HL, res);
-                    self.flag_set((sp & 0xff) as u32 + immediate as u32 > 0xff, Flag::C);
-                    self.flag_set((sp & 0xf) + (immediate & 0xf) > 0xf, Flag::H);
-                }
-                self.flag_set(false, Flag::Z);
-                self.flag_set(false, Flag::N);
-                instruction.cycles = 12;
-                instruction.imm8 = Some(immediate as u8);
-            },
-            0xF9 => {
-                //LD SP,HL
+            }
+            Op::LdHlSpImm => {
+                let res: u16 = self.add_sp_imm8(instruction.imm8.unwrap());
+                self.reg_set16(Reg::HL, res);
+                self.internal_cycle(memory);
+            }
+            Op::LdSpHl => {
                 let hl: u16 = self.reg16(Reg::HL);
                 self.reg_set16(Reg::SP, hl);
-                instruction.cycles = 8;
-            },
+                self.internal_cycle(memory);
+            }
             /*****************************************/
             /* 8 bit arithmetic/logical instructions */
             /*****************************************/
-            0x80 ... 0xBF |
-            0xC6 | 0xD6 | 0xE6 | 0xF6 |
-            0xCE | 0xDE | 0xEE | 0xFE => {
-                //ADD A,r; ADD A,(HL)
-                //ADC A,r; ADC A,(HL)
-                //SUB r; SUB (HL); SBC A,r; SBC A,(HL)
-                //AND r; AND (HL)
-                //XOR r; XOR (HL)
-                //ADD A,n; ADC A,n; SUB n; SBC A,n; AND n; XOR n; OR n; CP n;
-                instruction = self.exec_bit_alu8(byte, memory);
-            },
-            0x04 | 0x14 | 0x24 | 0x34 |
-            0x0C | 0x1C | 0x2C | 0x3C |
-            0x05 | 0x15 | 0x25 | 0x35 |
-            0x0D | 0x1D | 0x2D | 0x3D => {
-                //INC r; INC (HL)
-                //DEC r; DEC (HL)
-                instruction = self.exec_inc_dec(byte, memory);
-            },
-            0x27 => {
-                //DAA
-                let reg_a_val: u8 = self.reg8(Reg::A);
-                let upper_nibble: u8 = reg_a_val >> 4;
-                let lower_nibble: u8 = reg_a_val & 0b0000_1111;
-                let c_flag: bool = self.flag_is_set(Flag::C);
-                let h_flag: bool = self.flag_is_set(Flag::H);
-                let n_flag: bool = self.flag_is_set(Flag::N);
-                let mut as_nop: bool = false;
-                //the N flag isn't strictly necessary here, so it can be removed in the future.
-                let (add_value, new_c_flag) =
-                    match (n_flag, c_flag, upper_nibble, h_flag, lower_nibble) {
-                    (false, false, 0x0 ... 0x9, false, 0x0 ... 0x9) |
-                    (true, false, 0x0 ... 0x9, false, 0x0 ... 0x9) => (0x00, false),
-                    (false, false, 0x0 ... 0x8, false, 0xA ... 0xF) |
-                    (false, false, 0x0 ... 0x9, true, 0x0 ... 0x3) => (0x06, false),
-                    (false, false, 0xA ... 0xF, false, 0x0 ... 0x9) |
-                    (false, true, 0x0 ... 0x2, false, 0x0 ... 0x9) => (0x60, true),
-                    (false, false, 0x9 ... 0xF, false, 0xA ... 0xF) |
-                    (false, false, 0xA ... 0xF, true, 0x0 ... 0x3) |
-                    (false, true, 0x0 ... 0x2, false, 0xA ... 0xF) |
-                    (false, true, 0x0 ... 0x3, true, 0x0 ... 0x3) => (0x66, true),
-                    (true, false, 0x0 ... 0x8, true, 0x6 ... 0xF) => (0xFA, false),
-                    (true, true, 0x7 ... 0xF, false, 0x0 ... 0x9) => (0xA0, true),
-                    (true, true, 0x6 ... 0xF, true, 0x6 ... 0xF) => (0x9A, true),
-                    _ => {
-                        as_nop = true;
-                        (0, false)
-                    },
-                };
-                if !as_nop {
-                    let res: u8 = reg_a_val.wrapping_add(add_value);
-                    self.reg_set8(Reg::A, res);
-                    self.flag_set(res == 0, Flag::Z);
-                    self.flag_set(false, Flag::H);
-                    self.flag_set(new_c_flag, Flag::C);
-                }
-
-                instruction.cycles = 4;
-            },
-            0x37 => {
-                //SCF
+            Op::Alu(alu, reg) => {
+                let value: u8 = self.read_operand8(reg, memory);
+                self.alu8(alu, value);
+            }
+            Op::AluImm(alu) => {
+                self.alu8(alu, instruction.imm8.unwrap());
+            }
+            Op::Inc(reg) => {
+                let value: u8 = self.read_operand8(reg, memory);
+                let result: u8 = value.wrapping_add(1);
+                self.flag_set(result == 0, Flag::Z);
+                self.flag_set(false, Flag::N);
+                self.flag_set(util::has_half_carry(value, 1), Flag::H);
+                self.write_operand8(reg, result, memory);
+            }
+            Op::Dec(reg) => {
+                let value: u8 = self.read_operand8(reg, memory);
+                let result: u8 = value.wrapping_sub(1);
+                self.flag_set(result == 0, Flag::Z);
+                self.flag_set(true, Flag::N);
+                self.flag_set(util::has_borrow(value, result), Flag::H);
+                self.write_operand8(reg, result, memory);
+            }
+            Op::Daa => {
+                self.daa();
+            }
+            Op::Scf => {
                 self.flag_set(false, Flag::N);
                 self.flag_set(false, Flag::H);
                 self.flag_set(true, Flag::C);
-                instruction.cycles = 4;
-            },
-            0x2F => {
-                //CPL
+            }
+            Op::Cpl => {
                 let val: u8 = self.reg8(Reg::A);
                 self.reg_set8(Reg::A, !val);
                 self.flag_set(true, Flag::N);
                 self.flag_set(true, Flag::H);
-                instruction.cycles = 4;
-            },
-            0x3F => {
-                //CCF
+            }
+            Op::Ccf => {
                 let c: bool = self.flag_is_set(Flag::C);
                 self.flag_set(false, Flag::N);
                 self.flag_set(false, Flag::H);
                 self.flag_set(!c, Flag::C);
-                instruction.cycles = 4;
-            },
+            }
             /******************************************/
             /* 16 bit arithmetic/logical instructions */
             /******************************************/
-            0x03 | 0x13 | 0x23 | 0x33 => {
-                //INC rr
-                let reg: Reg = Reg::pair_from_dd(byte >> 4);
+            Op::Inc16(reg) => {
                 self.increment_reg(reg);
-                instruction.cycles = 8;
-            },
-            0x0B | 0x1B | 0x2B | 0x3B => {
-                //DEC rr
-                let reg: Reg = Reg::pair_from_dd(byte >> 4);
+                self.internal_cycle(memory);
+            }
+            Op::Dec16(reg) => {
                 self.decrement_reg(reg);
-                instruction.cycles = 8;
-            },
-            0x09 | 0x19 | 0x29 | 0x39 => {
-                //ADD HL,rr
-                let reg: Reg = Reg::pair_from_dd(byte >> 4);
+                self.internal_cycle(memory);
+            }
+            Op::AddHl(reg) => {
                 let value: u16 = self.reg16(reg);
-
                 let hl: u16 = self.reg16(Reg::HL);
                 self.reg_set16(Reg::HL, hl.wrapping_add(value));
 
                 self.flag_set(false, Flag::N);
                 self.flag_set(util::has_half_carry16(hl, value), Flag::H);
                 self.flag_set(util::has_carry16(hl, value), Flag::C);
-
-                instruction.cycles = 8;
-            },
-            0xE8 => {
-                //ADD SP,n
-                let imm: u16 = util::sign_extend(self.mem_next8(memory));
-                let sp: u16 = self.reg16(Reg::SP);
-                if util::is_neg16(imm) {
-                    let res: u16 = sp.wrapping_sub(util::twos_complement(imm));
-                    self.reg_set16(Reg::SP, res);
-                    //self.flag_set((res & 0xff) <= (sp & 0xff), Flag::C);
-                    self.flag_set(sp < util::twos_complement(imm), Flag::C);
-                    self.flag_set((res & 0xf) <= (sp & 0xf), Flag::H);
-                } else {
-                    let res: u16 = sp.wrapping_add(imm);
-                    self.reg_set16(Reg::SP, res);
-                   // self.flag_set((sp & 0xff) as u32 + imm as u32 > 0xff, Flag::C);
-                    self.flag_set(util::has_carry16(sp, imm), Flag::C);
-                    self.flag_set((sp & 0xf) + (imm & 0xf) > 0xf, Flag::H);
-                }
+                self.internal_cycle(memory);
+            }
+            Op::AddSpImm => {
+                let res: u16 = self.add_sp_imm8(instruction.imm8.unwrap());
+                self.reg_set16(Reg::SP, res);
+                self.internal_cycle(memory);
+                self.internal_cycle(memory);
+            }
+            /**************************************/
+            /*      8 bit rotations/shifts        */
+            /**************************************/
+            Op::Rlca | Op::Rrca | Op::Rla | Op::Rra => {
+                let value: u8 = self.reg8(Reg::A);
+                let shift: Op = match op {
+                    Op::Rlca => Op::Rlc(Reg::A),
+                    Op::Rrca => Op::Rrc(Reg::A),
+                    Op::Rla => Op::Rl(Reg::A),
+                    _ => Op::Rr(Reg::A),
+                };
+                let result: u8 = self.rotate_shift(shift, value);
+                self.reg_set8(Reg::A, result);
+                // unlike the CB-prefixed ones, these always reset Z.
                 self.flag_set(false, Flag::Z);
+            }
+            Op::Rlc(reg) | Op::Rrc(reg) | Op::Rl(reg) | Op::Rr(reg) | Op::Sla(reg) |
+            Op::Sra(reg) | Op::Swap(reg) | Op::Srl(reg) => {
+                let value: u8 = self.read_operand8(reg, memory);
+                let result: u8 = self.rotate_shift(op, value);
+                self.write_operand8(reg, result, memory);
+            }
+            Op::Bit(bit, reg) => {
+                let value: u8 = self.read_operand8(reg, memory);
+                self.flag_set(((value >> bit) & 0b1) == 0b0, Flag::Z);
                 self.flag_set(false, Flag::N);
-                instruction.cycles = 16;
-                instruction.imm8 = Some(imm as u8);
-            },
+                self.flag_set(true, Flag::H);
+            }
+            Op::Res(bit, reg) => {
+                let value: u8 = self.read_operand8(reg, memory);
+                self.write_operand8(reg, value & !(1 << bit), memory);
+            }
+            Op::Set(bit, reg) => {
+                let value: u8 = self.read_operand8(reg, memory);
+                self.write_operand8(reg, value | (1 << bit), memory);
+            }
             /******************************************/
             /*            Jumps/Calls                 */
             /******************************************/
-            0x18 | 0x20 | 0x28 | 0x30 | 0x38 => {
-                //JR n; JR c,n
-                instruction = self.exec_jr(byte, memory);
-            },
-            0xC2 | 0xC3 | 0xCA |
-            0xD2 | 0xDA | 0xE9 => {
-                //JP nn; JP c,nn; JP (HL)
-                instruction = self.exec_jp(byte, memory);
-            },
-            0xC0 | 0xC8 | 0xC9 |
-            0xD0 | 0xD8 | 0xD9 => {
-                //RET; RET c; RETI
-                instruction = self.exec_ret(byte, memory);
-            },
-            0xC4 | 0xCC | 0xCD |
-            0xD4 | 0xDC => {
-                //CALL nn; CALL c,nn
-                instruction = self.exec_call(byte, memory);
-            },
-            0xC7 | 0xCF | 0xD7 | 0xDF |
-            0xE7 | 0xEF | 0xF7 | 0xFF => {
-                //RST
+            Op::Jr(cond) => {
+                if self.condition(cond) {
+                    let pc: u16 = self.reg16(Reg::PC);
+                    let offset: u16 = util::sign_extend(instruction.imm8.unwrap());
+                    self.reg_set16(Reg::PC, pc.wrapping_add(offset));
+                    self.internal_cycle(memory);
+                    branch_taken = true;
+                }
+            }
+            Op::Jp(cond) => {
+                if self.condition(cond) {
+                    self.reg_set16(Reg::PC, instruction.imm16.unwrap());
+                    self.internal_cycle(memory);
+                    branch_taken = true;
+                }
+            }
+            Op::JpHl => {
+                let hl: u16 = self.reg16(Reg::HL);
+                self.reg_set16(Reg::PC, hl);
+            }
+            Op::Ret(cond) => {
+                if cond != Cond::Always {
+                    // checking the condition takes one cycle.
+                    self.internal_cycle(memory);
+                }
+                if self.condition(cond) {
+                    let addr: u16 = self.pop_sp16(memory);
+                    self.reg_set16(Reg::PC, addr);
+                    self.internal_cycle(memory);
+                    branch_taken = true;
+                }
+            }
+            Op::Reti => {
+                let addr: u16 = self.pop_sp16(memory);
+                self.reg_set16(Reg::PC, addr);
+                self.internal_cycle(memory);
+                self.ime_flag = true;
+            }
+            Op::Call(cond) => {
+                if self.condition(cond) {
+                    let pc: u16 = self.reg16(Reg::PC);
+                    self.internal_cycle(memory);
+                    self.push_sp16(pc, memory);
+                    self.reg_set16(Reg::PC, instruction.imm16.unwrap());
+                    branch_taken = true;
+                }
+            }
+            Op::Rst(addr) => {
                 let pc: u16 = self.reg16(Reg::PC);
+                self.internal_cycle(memory);
                 self.push_sp16(pc, memory);
-                let addr: u16 = byte as u16 & 0b0011_1000;
                 self.reg_set16(Reg::PC, addr);
-                instruction.cycles = 16;
-            },
-            _ => panic!("Unknown instruction: {:#x}", byte),
+            }
         }
 
-        if instruction.prefix.is_none() {
-            instruction.opcode = byte;
-        }
-        instruction.address = addr;
-        self.last_instruction = Some(instruction);
-        (instruction, event)
+        branch_taken
     }
 
     // Instructions execution codes
 
-    fn exec_ret(&mut self, opcode: u8, memory: &mem::Memory) -> Instruction {
-        let should_return: bool;
-        let mut cycles: u32 = 20;
-        match opcode {
-            0xC0 => {
-                // RET NZ
-                should_return = !self.flag_is_set(Flag::Z);
-            }
-            0xC8 => {
-                // RET Z
-                should_return = self.flag_is_set(Flag::Z);
-            }
-            0xC9 => {
-                // RET
-                should_return = true;
-                cycles = 16;
-            }
-            0xD0 => {
-                // RET NC
-                should_return = !self.flag_is_set(Flag::C);
-            }
-            0xD8 => {
-                // RET C
-                should_return = self.flag_is_set(Flag::C);
-            }
-            0xD9 => {
-                // RETI
-                should_return = true;
-                cycles = 16;
-                self.ime_flag = true;
-            }
-            _ => unreachable!(),
+    #[inline]
+    fn condition(&self, cond: Cond) -> bool {
+        match cond {
+            Cond::Always => true,
+            Cond::NZ => !self.flag_is_set(Flag::Z),
+            Cond::Z => self.flag_is_set(Flag::Z),
+            Cond::NC => !self.flag_is_set(Flag::C),
+            Cond::C => self.flag_is_set(Flag::C),
         }
+    }
 
-        if should_return {
-            let addr: u16 = self.pop_sp16(memory);
-            self.reg_set16(Reg::PC, addr);
+    // Reg::HL means (HL).
+    #[inline]
+    fn read_operand8<B: Bus>(&mut self, reg: Reg, memory: &mut B) -> u8 {
+        if reg == Reg::HL {
+            self.mem_at_reg(Reg::HL, memory)
         } else {
-            cycles = 8;
+            self.reg8(reg)
         }
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = cycles;
-
-        instr
     }
 
-    fn exec_rotates_shifts(&mut self, opcode: u8) -> Instruction {
-        let mut value: u8 = self.reg8(Reg::A);
-
-        let bit_7: u8 = (value >> 7) & 0b1;
-        let bit_0: u8 = value & 0b1;
-        let bit: u8;
-        match opcode {
-            0x07 => {
-                // RLCA
-                value = (value << 1) | bit_7;
-                bit = bit_7;
-            }
-            0x0F => {
-                // RRCA
-                value = (value >> 1) | (bit_0 << 7);
-                bit = bit_0;
-            }
-            0x17 => {
-                // RLA
-                value = (value << 1) | self.flag_bit(Flag::C);
-                bit = bit_7;
-            }
-            0x1F => {
-                // RRA
-                value = (value >> 1) | (self.flag_bit(Flag::C) << 7);
-                bit = bit_0
-            }
-            _ => unreachable!(),
+    #[inline]
+    fn write_operand8<B: Bus>(&mut self, reg: Reg, value: u8, memory: &mut B) {
+        if reg == Reg::HL {
+            let addr: u16 = self.reg16(Reg::HL);
+            self.mem_write(addr, value, memory);
+        } else {
+            self.reg_set8(reg, value);
         }
+    }
 
-        self.reg_set8(Reg::A, value);
-
-        self.flag_set(bit == 1, Flag::C);
-        // TODO: what to believe?
-        // Z80 manual says Z flag is not affected;
-        // Gameboy manual says it is.
-        // self.flag_set(value == 0, Flag::Z);
-
+    // SP + signed immediate, with the flags set as ADD SP,n and LD HL,SP+n do.
+    fn add_sp_imm8(&mut self, imm8: u8) -> u16 {
+        let imm: u16 = util::sign_extend(imm8);
+        let sp: u16 = self.reg16(Reg::SP);
+        // the flags come from the unsigned addition of the lower byte.
+        self.flag_set((sp & 0xff) + (imm & 0xff) > 0xff, Flag::C);
+        self.flag_set((sp & 0xf) + (imm & 0xf) > 0xf, Flag::H);
         self.flag_set(false, Flag::Z);
         self.flag_set(false, Flag::N);
-        self.flag_set(false, Flag::H);
-
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = 4;
-
-        instr
+        sp.wrapping_add(imm)
     }
 
-    fn exec_call(&mut self, opcode: u8, memory: &mut mem::Memory) -> Instruction {
-        // push next instruction onto stack
-        let immediate: u16 = self.mem_next16(memory);
-        let should_jump: bool;
-        match opcode {
-            0xC4 => {
-                // CALL NZ,a16
-                should_jump = !self.flag_is_set(Flag::Z);
-            }
-            0xCC => {
-                // CALL Z,a16
-                should_jump = self.flag_is_set(Flag::Z);
-            }
-            0xCD => {
-                // CALL a16
-                should_jump = true;
-            }
-            0xD4 => {
-                // CALL NC,a16
-                should_jump = !self.flag_is_set(Flag::C);
-            }
-            0xDC => {
-                // CALL C,a16
-                should_jump = self.flag_is_set(Flag::C);
-            }
-            _ => unreachable!(),
-        }
-
-        let mut cycles: u32 = 12;
-        if should_jump {
-            let pc: u16 = self.reg16(Reg::PC);
-            self.push_sp16(pc, memory);
-            self.reg_set16(Reg::PC, immediate);
-            cycles = 24;
+    fn daa(&mut self) {
+        let reg_a_val: u8 = self.reg8(Reg::A);
+        let upper_nibble: u8 = reg_a_val >> 4;
+        let lower_nibble: u8 = reg_a_val & 0b0000_1111;
+        let c_flag: bool = self.flag_is_set(Flag::C);
+        let h_flag: bool = self.flag_is_set(Flag::H);
+        let n_flag: bool = self.flag_is_set(Flag::N);
+        let mut as_nop: bool = false;
+        //the N flag isn't strictly necessary here, so it can be removed in the future.
+        let (add_value, new_c_flag) =
+            match (n_flag, c_flag, upper_nibble, h_flag, lower_nibble) {
+            (false, false, 0x0 ... 0x9, false, 0x0 ... 0x9) |
+            (true, false, 0x0 ... 0x9, false, 0x0 ... 0x9) => (0x00, false),
+            (false, false, 0x0 ... 0x8, false, 0xA ... 0xF) |
+            (false, false, 0x0 ... 0x9, true, 0x0 ... 0x3) => (0x06, false),
+            (false, false, 0xA ... 0xF, false, 0x0 ... 0x9) |
+            (false, true, 0x0 ... 0x2, false, 0x0 ... 0x9) => (0x60, true),
+            (false, false, 0x9 ... 0xF, false, 0xA ... 0xF) |
+            (false, false, 0xA ... 0xF, true, 0x0 ... 0x3) |
+            (false, true, 0x0 ... 0x2, false, 0xA ... 0xF) |
+            (false, true, 0x0 ... 0x3, true, 0x0 ... 0x3) => (0x66, true),
+            (true, false, 0x0 ... 0x8, true, 0x6 ... 0xF) => (0xFA, false),
+            (true, true, 0x7 ... 0xF, false, 0x0 ... 0x9) => (0xA0, true),
+            (true, true, 0x6 ... 0xF, true, 0x6 ... 0xF) => (0x9A, true),
+            _ => {
+                as_nop = true;
+                (0, false)
+            },
+        };
+        if !as_nop {
+            let res: u8 = reg_a_val.wrapping_add(add_value);
+            self.reg_set8(Reg::A, res);
+            self.flag_set(res == 0, Flag::Z);
+            self.flag_set(false, Flag::H);
+            self.flag_set(new_c_flag, Flag::C);
         }
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = cycles;
-        instr.imm16 = Some(immediate);
-
-        instr
     }
 
-    fn exec_cb_prefixed(&mut self, memory: &mut mem::Memory) -> Instruction {
-        let opcode = self.mem_next8(memory);
-        let reg: Reg = Reg::pair_from_ddd(opcode);
-        let mut value: u8;
-        if reg == Reg::HL {
-            value = memory.read_byte(self.reg16(Reg::HL));
-        } else {
-            value = self.reg8(reg);
-        }
-        let bit: u8 = (opcode >> 3) & 0b111;
-        let mut should_change_reg: bool = true;
-
-        let cycles: u32 = if reg == Reg::HL {
-            16
-        } else {
-            8
-        };
-        match opcode {
-            0x00...0x07 => {
-                // RLC b
-                let bit_7: u8 = (value >> 7) & 0b1;
-
+    // RLC, RRC, RL, RR, SLA, SRA, SWAP and SRL.
+    fn rotate_shift(&mut self, op: Op, mut value: u8) -> u8 {
+        let bit_7: u8 = (value >> 7) & 0b1;
+        let bit_0: u8 = value & 0b1;
+        let carry: u8;
+        match op {
+            Op::Rlc(_) => {
                 value = (value << 1) | bit_7;
-                self.flag_set(bit_7 == 1, Flag::C);
+                carry = bit_7;
             }
-            0x08...0x0F => {
-                // RRC m
-                let bit_0: u8 = value & 0b1;
-
+            Op::Rrc(_) => {
                 value = (value >> 1) | (bit_0 << 7);
-                self.flag_set(bit_0 == 1, Flag::C);
+                carry = bit_0;
             }
-            0x10...0x17 => {
-                // RL m
-                let bit_7: u8 = (value >> 7) & 0b1;
-
+            Op::Rl(_) => {
                 value = (value << 1) | self.flag_bit(Flag::C);
-                self.flag_set(bit_7 == 1, Flag::C);
+                carry = bit_7;
             }
-            0x18...0x1F => {
-                // RR m
-                let bit_c: u8 = self.flag_bit(Flag::C);
-                let bit_0: u8 = value & 0b1;
-
-                value = (value >> 1) | (bit_c << 7);
-                self.flag_set(bit_0 == 1, Flag::C);
+            Op::Rr(_) => {
+                value = (value >> 1) | (self.flag_bit(Flag::C) << 7);
+                carry = bit_0;
             }
-            0x20...0x27 => {
-                // SLA n
-                let bit_7: u8 = (value >> 7) & 0b1;
+            Op::Sla(_) => {
                 value = value << 1;
-
-                self.flag_set(bit_7 == 1, Flag::C);
+                carry = bit_7;
             }
-            0x28...0x2F => {
-                // SRA n
-                let bit_7: u8 = value & 0b1000_0000;
-                let bit_0: u8 = value & 0b1;
-                value = (value >> 1) | bit_7;
-
-                self.flag_set(bit_0 == 1, Flag::C);
+            Op::Sra(_) => {
+                value = (value >> 1) | (bit_7 << 7);
+                carry = bit_0;
             }
-            0x30...0x37 => {
-                // SWAP n
+            Op::Swap(_) => {
                 value = (value << 4) | (value >> 4);
-                self.flag_set(false, Flag::C);
+                carry = 0;
             }
-            0x38...0x3F => {
-                // SRL n
-                let bit_0: u8 = value & 0b1;
+            Op::Srl(_) => {
                 value = value >> 1;
-
-                self.flag_set(bit_0 == 1, Flag::C);
-            }
-            0x40...0x7F => {
-                // BIT b,r; BIT b,(HL)
-                self.flag_set(((value >> bit) & 0b1) == 0b0, Flag::Z);
-                self.flag_set(false, Flag::N);
-                self.flag_set(true, Flag::H);
-
-                should_change_reg = false;
-            }
-            0x80...0xBF => {
-                // RES b,r; RES b,(HL)
-                value = value & !(1 << bit);
-            }
-            0xC0...0xFF => {
-                // SET b,r; SET b,(HL)
-                value = value | (1 << bit);
-            }
-            _ => {
-                panic!("CB-prefixed opcode not yet implemented: {:#01$x}",
-                       opcode,
-                       2)
-            }
-        }
-
-        if should_change_reg {
-            if reg == Reg::HL {
-                self.mem_write(self.reg16(Reg::HL), value, memory)
-            } else {
-                self.reg_set8(reg, value);
-            }
-        }
-
-        if opcode <= 0x3F {
-            self.flag_set(value == 0, Flag::Z);
-            self.flag_set(false, Flag::N);
-            self.flag_set(false, Flag::H);
-        }
-
-        let mut instr: Instruction = Instruction::default();
-        instr.prefix = Some(0xCB);
-        instr.opcode = opcode;
-        instr.cycles = cycles;
-
-        instr
-    }
-
-    fn exec_jp(&mut self, opcode: u8, memory: &mut mem::Memory) -> Instruction {
-        let should_jump: bool;
-        let mut jump_to_hl: bool = false;
-        match opcode {
-            0xC3 => {
-                // JP nn
-                should_jump = true;
-            }
-            0xC2 => {
-                // JP NZ,nn
-                should_jump = !self.flag_is_set(Flag::Z);
-            }
-            0xCA => {
-                // JP Z,nn
-                should_jump = self.flag_is_set(Flag::Z);
-            }
-            0xD2 => {
-                // JP NC,nn
-                should_jump = !self.flag_is_set(Flag::C);
-            }
-            0xDA => {
-                // JP C,nn
-                should_jump = self.flag_is_set(Flag::C);
-            }
-            0xE9 => {
-                // JP (HL)
-                should_jump = true;
-                jump_to_hl = true;
-            }
-            _ => unreachable!(),
-        }
-
-        let cycles: u32;
-        let mut imm16: Option<u16> = None;
-        if should_jump {
-            let val: u16 = if jump_to_hl {
-                cycles = 4;
-                self.reg16(Reg::HL)
-            } else {
-                cycles = 16;
-                let imm: u16 = self.mem_next16(memory);
-                imm16 = Some(imm);
-                imm
-            };
-            self.reg_set16(Reg::PC, val);
-        } else if jump_to_hl {
-            cycles = 4;
-        } else {
-            imm16 = Some(self.mem_next16(memory)); //mem_next increments PC twice.
-            cycles = 12;
-        }
-
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = cycles;
-        instr.imm16 = imm16;
-
-        instr
-    }
-
-    fn exec_jr(&mut self, opcode: u8, memory: &mut mem::Memory) -> Instruction {
-        let should_jump: bool;
-        let mut cycles: u32 = 8;
-        match opcode {
-            0x18 => {
-                // JR n
-                should_jump = true;
-                cycles = 12;
-            }
-            0x20 => {
-                // JR NZ,r8
-                should_jump = !self.flag_is_set(Flag::Z);
-            }
-            0x28 => {
-                // JR Z,r8
-                should_jump = self.flag_is_set(Flag::Z);
-            }
-            0x30 => {
-                // JR NC,r8
-                should_jump = !self.flag_is_set(Flag::C);
-            }
-            0x38 => {
-                // JR C,r8
-                should_jump = self.flag_is_set(Flag::C);
-            }
-            _ => unreachable!(),
-        }
-
-        let imm8: u8 = self.mem_next8(memory);
-        if should_jump {
-            let imm: u16 = util::sign_extend(imm8);
-            cycles = 12;
-
-            let mut addr: u16 = self.reg16(Reg::PC);
-            if util::is_neg16(imm) {
-                addr = addr - util::twos_complement(imm);
-            } else {
-                addr = addr + imm;
-            }
-
-            self.reg_set16(Reg::PC, addr);
-        }
-
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = cycles;
-        instr.imm8 = Some(imm8);
-
-        instr
-    }
-
-    fn exec_inc_dec(&mut self, opcode: u8, memory: &mut mem::Memory) -> Instruction {
-        let reg: Reg = Reg::pair_from_ddd(opcode >> 3);
-        let result: u8;
-        let mut cycles: u32 = 4;
-        let reg_val: u8;
-        if reg == Reg::HL {
-            cycles = 12;
-            reg_val = self.mem_at_reg(Reg::HL, memory);
-        } else {
-            reg_val = self.reg8(reg);
-        }
-        match opcode {
-            0x04 |
-            0x14 |
-            0x24 |
-            0x34 |
-            0x0C |
-            0x1C |
-            0x2C |
-            0x3C => {
-                // INC
-                result = reg_val.wrapping_add(1);
-                self.flag_set(false, Flag::N);
-                self.flag_set(util::has_half_carry(reg_val, 1), Flag::H);
-            }
-            0x05 |
-            0x15 |
-            0x25 |
-            0x35 |
-            0x0D |
-            0x1D |
-            0x2D |
-            0x3D => {
-                // DEC
-                result = reg_val.wrapping_sub(1);
-                self.flag_set(true, Flag::N);
-                self.flag_set(util::has_borrow(reg_val, result), Flag::H);
+                carry = bit_0;
             }
             _ => unreachable!(),
         }
-        self.flag_set(result == 0, Flag::Z);
-
-        if reg == Reg::HL {
-            self.mem_write(self.reg16(Reg::HL), result, memory);
-        } else {
-            self.reg_set8(reg, result);
-        }
 
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = cycles;
+        self.flag_set(value == 0, Flag::Z);
+        self.flag_set(false, Flag::N);
+        self.flag_set(false, Flag::H);
+        self.flag_set(carry == 1, Flag::C);
 
-        instr
+        value
     }
 
-    fn exec_bit_alu8(&mut self, opcode: u8, memory: &mem::Memory) -> Instruction {
+    fn alu8(&mut self, alu: Alu, value: u8) {
         let reg_a_val: u8 = self.reg8(Reg::A);
-        let reg: Reg = Reg::pair_from_ddd(opcode);
-        let value: u8;
-
-        let mut cycles: u32 = 8;
-        let mut imm8: Option<u8> = None;
-        if opcode > 0xBF {
-            value = self.mem_next8(memory);
-            imm8 = Some(value);
-        } else if reg == Reg::HL {
-            value = self.mem_at_reg(reg, memory);
-        } else {
-            value = self.reg8(reg);
-            cycles = 4;
-        }
         let result: u8;
-        let mut unchange_a: bool = false;
 
-        match opcode {
-            0x80...0x87 | 0xC6 => {
-                // ADD
+        match alu {
+            Alu::Add => {
                 result = reg_a_val.wrapping_add(value);
                 self.flag_set(false, Flag::N);
                 self.flag_set(util::has_half_carry(reg_a_val, value), Flag::H);
                 self.flag_set(util::has_carry(reg_a_val, value), Flag::C);
             }
-            0x88...0x8F | 0xCE => {
-                // ADC
-                let value: u8 = value.wrapping_add(self.flag_bit(Flag::C));
-                result = reg_a_val.wrapping_add(value);
+            Alu::Adc => {
+                let carry: u8 = self.flag_bit(Flag::C);
+                result = reg_a_val.wrapping_add(value).wrapping_add(carry);
                 self.flag_set(false, Flag::N);
-                self.flag_set(util::has_half_carry(reg_a_val, value), Flag::H);
-                self.flag_set(util::has_carry(reg_a_val, value), Flag::C);
+                self.flag_set((reg_a_val & 0xF) + (value & 0xF) + carry > 0xF, Flag::H);
+                self.flag_set(reg_a_val as u16 + value as u16 + carry as u16 > 0xFF, Flag::C);
             }
-            0x90...0x97 | 0xD6 => {
-                // SUB
+            Alu::Sub | Alu::Cp => {
                 result = reg_a_val.wrapping_sub(value);
                 self.flag_set(true, Flag::N);
                 self.flag_set(util::has_borrow(reg_a_val, value), Flag::H);
                 self.flag_set(value > reg_a_val, Flag::C);
             }
-            0x98...0x9F | 0xDE => {
-                // SBC
-                let value: u8 = value.wrapping_add(self.flag_bit(Flag::C));
-                result = reg_a_val.wrapping_sub(value);
+            Alu::Sbc => {
+                let carry: u8 = self.flag_bit(Flag::C);
+                result = reg_a_val.wrapping_sub(value).wrapping_sub(carry);
                 self.flag_set(true, Flag::N);
-                self.flag_set(util::has_borrow(reg_a_val, value), Flag::H);
-                self.flag_set(value > reg_a_val, Flag::C);
+                self.flag_set((reg_a_val & 0xF) < (value & 0xF) + carry, Flag::H);
+                self.flag_set((reg_a_val as u16) < value as u16 + carry as u16, Flag::C);
             }
-            0xA0...0xA7 | 0xE6 => {
-                // AND
+            Alu::And => {
                 result = reg_a_val & value;
                 self.flag_set(false, Flag::N);
                 self.flag_set(true, Flag::H);
                 self.flag_set(false, Flag::C);
             }
-            0xA8...0xAF | 0xEE => {
-                // XOR
+            Alu::Xor => {
                 result = reg_a_val ^ value;
                 self.flag_set(false, Flag::N);
                 self.flag_set(false, Flag::H);
                 self.flag_set(false, Flag::C);
             }
-            0xB0...0xB7 | 0xF6 => {
-                // OR
+            Alu::Or => {
                 result = reg_a_val | value;
                 self.flag_set(false, Flag::N);
                 self.flag_set(false, Flag::H);
                 self.flag_set(false, Flag::C);
             }
-            0xB8...0xBF | 0xFE => {
-                // CP
-                result = if reg_a_val == value {
-                    0x0
-                } else {
-                    0x1
-                };
-                self.flag_set(true, Flag::N);
-                self.flag_set(util::has_borrow(reg_a_val, value), Flag::H);
-                self.flag_set(reg_a_val < value, Flag::C);
-                unchange_a = true;
-            }
-
-            _ => unreachable!(),
         }
         self.flag_set(result == 0, Flag::Z);
-        if !unchange_a {
+        if alu != Alu::Cp {
             self.reg_set8(Reg::A, result);
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cpu, Reg, Instruction};
+    use cpu::consts;
+    use mem::bus::Bus;
+    use sm83::FlatRam;
+
+    const VBLANK: u8 = 0b1;
+    const EI: u8 = 0xFB;
+    const DI: u8 = 0xF3;
+    const HALT: u8 = 0x76;
+    const NOP: u8 = 0x00;
+    const INC_A: u8 = 0x3C;
+    const LD_A_D8: u8 = 0x3E;
+    const STOP: u8 = 0x10;
+
+    // 'program' at 0x0100, with IME off and the stack in WRAM.
+    fn setup(program: &[u8]) -> (Cpu, FlatRam) {
+        let mut cpu: Cpu = Cpu::default();
+        let mut ram: FlatRam = FlatRam::default();
+        cpu.reg_set16(Reg::PC, 0x0100);
+        cpu.reg_set16(Reg::SP, 0xD000);
+        cpu.set_ime(false);
+        for (i, byte) in program.iter().enumerate() {
+            ram.write_byte(0x0100 + i as u16, *byte);
+        }
+        (cpu, ram)
+    }
+
+    fn request_vblank(ram: &mut FlatRam) {
+        ram.write_byte(consts::IE_REGISTER_ADDR, VBLANK);
+        ram.write_byte(consts::IF_REGISTER_ADDR, VBLANK);
+    }
+
+    fn pushed_pc(cpu: &Cpu, ram: &mut FlatRam) -> u16 {
+        let sp: u16 = cpu.reg16(Reg::SP);
+        (ram.read_byte(sp + 1) as u16) << 8 | ram.read_byte(sp) as u16
+    }
+
+    #[test]
+    fn dispatch_takes_5_m_cycles() {
+        let (mut cpu, mut ram) = setup(&[]);
+        cpu.set_ime(true);
+        request_vblank(&mut ram);
+
+        assert_eq!(cpu.handle_interrupts(&mut ram), 20);
+        assert_eq!(ram.cycles(), 20);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0040);
+        assert_eq!(cpu.reg16(Reg::SP), 0xCFFE);
+        assert_eq!(pushed_pc(&cpu, &mut ram), 0x0100);
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR) & VBLANK, 0);
+        assert!(!cpu.ime());
+    }
 
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = cycles;
-        instr.imm8 = imm8;
+    #[test]
+    fn nothing_to_dispatch_takes_no_cycles() {
+        let (mut cpu, mut ram) = setup(&[]);
+        cpu.set_ime(true);
+        ram.write_byte(consts::IF_REGISTER_ADDR, VBLANK);
 
-        instr
+        assert_eq!(cpu.handle_interrupts(&mut ram), 0);
+        assert_eq!(ram.cycles(), 0);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0100);
     }
 
-    fn exec_ld_a_nn(&mut self, opcode: u8, memory: &mut mem::Memory) -> Instruction {
-        let mut reg: Reg = Reg::pair_from_dd(opcode >> 4);
-        if reg == Reg::SP {
-            reg = Reg::HL;
+    #[test]
+    fn pushing_pc_over_ie_cancels_the_dispatch() {
+        let (mut cpu, mut ram) = setup(&[]);
+        cpu.set_ime(true);
+        request_vblank(&mut ram);
+        // the upper byte of PC (0x02) lands on IE and disables VBlank.
+        cpu.reg_set16(Reg::PC, 0x0200);
+        cpu.reg_set16(Reg::SP, 0x0000);
+
+        assert_eq!(cpu.handle_interrupts(&mut ram), 20);
+        assert_eq!(ram.read_byte(consts::IE_REGISTER_ADDR), 0x02);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0000);
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR) & VBLANK, VBLANK);
+        assert!(!cpu.ime());
+    }
+
+    #[test]
+    fn pushing_pc_over_ie_can_keep_the_interrupt() {
+        let (mut cpu, mut ram) = setup(&[]);
+        cpu.set_ime(true);
+        request_vblank(&mut ram);
+        cpu.reg_set16(Reg::PC, 0x0100);
+        cpu.reg_set16(Reg::SP, 0x0000);
+
+        cpu.handle_interrupts(&mut ram);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0040);
+    }
+
+    #[test]
+    fn ei_takes_effect_after_the_next_instruction() {
+        let (mut cpu, mut ram) = setup(&[EI, NOP, NOP]);
+        request_vblank(&mut ram);
+
+        cpu.run_instruction(&mut ram);
+        assert!(!cpu.ime());
+        assert_eq!(cpu.handle_interrupts(&mut ram), 0);
+
+        cpu.run_instruction(&mut ram);
+        assert!(cpu.ime());
+        assert_eq!(cpu.handle_interrupts(&mut ram), 20);
+        assert_eq!(pushed_pc(&cpu, &mut ram), 0x0102);
+    }
+
+    #[test]
+    fn di_right_after_ei_keeps_interrupts_disabled() {
+        let (mut cpu, mut ram) = setup(&[EI, DI, NOP]);
+        request_vblank(&mut ram);
+
+        for _ in 0..3 {
+            cpu.run_instruction(&mut ram);
+            assert!(!cpu.ime());
+            assert_eq!(cpu.handle_interrupts(&mut ram), 0);
         }
-        let val: u8 = self.mem_at_reg(reg, memory);
-        self.reg_set8(Reg::A, val);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0103);
+    }
 
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = 8;
+    #[test]
+    fn ei_then_halt_services_the_interrupt_without_the_halt_bug() {
+        let (mut cpu, mut ram) = setup(&[EI, HALT, NOP]);
+        ram.write_byte(0x0040, INC_A);
+        request_vblank(&mut ram);
+
+        cpu.run_instruction(&mut ram);
+        let halt: Instruction = cpu.run_instruction(&mut ram);
+        assert_eq!(halt.opcode, HALT);
+        assert!(cpu.ime());
+        assert_eq!(cpu.handle_interrupts(&mut ram), 20);
+        assert_eq!(pushed_pc(&cpu, &mut ram), 0x0102);
+
+        // the first byte of the handler is only fetched once.
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0041);
+    }
 
-        instr
+    #[test]
+    fn halt_bug_runs_the_next_byte_twice() {
+        let (mut cpu, mut ram) = setup(&[HALT, INC_A, NOP]);
+        request_vblank(&mut ram);
+
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0101);
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0101);
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0102);
+        assert_eq!(cpu.reg8(Reg::A), 2);
     }
 
-    fn exec_ld_nn_a(&mut self, opcode: u8, memory: &mut mem::Memory) -> Instruction {
-        let mut reg: Reg = Reg::pair_from_dd(opcode >> 4);
-        if reg == Reg::SP {
-            reg = Reg::HL;
+    #[test]
+    fn halt_bug_makes_a_2_byte_instruction_read_its_opcode_as_operand() {
+        let (mut cpu, mut ram) = setup(&[HALT, LD_A_D8, INC_A]);
+        request_vblank(&mut ram);
+
+        cpu.run_instruction(&mut ram);
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg8(Reg::A), LD_A_D8);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0102);
+        // what was meant as the operand runs as an instruction.
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg8(Reg::A), LD_A_D8 + 1);
+    }
+
+    #[test]
+    fn halt_wakes_up_with_ime_off_without_dispatching() {
+        let (mut cpu, mut ram) = setup(&[HALT, INC_A]);
+        ram.write_byte(consts::IE_REGISTER_ADDR, VBLANK);
+
+        cpu.run_instruction(&mut ram);
+        for _ in 0..10 {
+            cpu.run_instruction(&mut ram);
         }
-        let addr: u16 = self.reg16(reg);
-        let val: u8 = self.reg8(Reg::A);
-        self.mem_write(addr, val, memory);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0101);
+        assert_eq!(cpu.reg8(Reg::A), 0);
+
+        ram.write_byte(consts::IF_REGISTER_ADDR, VBLANK);
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.handle_interrupts(&mut ram), 0);
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg8(Reg::A), 1);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0102);
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR) & VBLANK, VBLANK);
+    }
 
-        let mut instr: Instruction = Instruction::default();
-        instr.cycles = 8;
+    #[test]
+    fn halt_idles_4_cycles_at_a_time_while_no_interrupt_is_enabled() {
+        let (mut cpu, mut ram) = setup(&[HALT, INC_A]);
+        // requested but not enabled: no wake up and no HALT bug.
+        ram.write_byte(consts::IF_REGISTER_ADDR, VBLANK);
+
+        cpu.run_instruction(&mut ram);
+        let halted_at: u32 = ram.cycles();
+        for _ in 0..10 {
+            cpu.run_instruction(&mut ram);
+            assert_eq!(cpu.handle_interrupts(&mut ram), 0);
+        }
+        assert_eq!(ram.cycles() - halted_at, 10 * 4);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0101);
+        assert_eq!(cpu.reg8(Reg::A), 0);
+    }
+
+    #[test]
+    fn stop_skips_a_byte_resets_div_and_stops_the_clock() {
+        let (mut cpu, mut ram) = setup(&[STOP, INC_A, INC_A]);
+        ram.write_byte(consts::DIV_REGISTER_ADDR, 0xAB);
+
+        cpu.run_instruction(&mut ram);
+        assert!(cpu.is_stopped());
+        assert_eq!(cpu.reg16(Reg::PC), 0x0102);
+        assert_eq!(ram.read_byte(consts::DIV_REGISTER_ADDR), 0);
+
+        let stopped_at: u32 = ram.cycles();
+        for _ in 0..10 {
+            assert_eq!(cpu.run_instruction(&mut ram).cycles, 0);
+        }
+        assert_eq!(ram.cycles(), stopped_at);
 
-        instr
+        cpu.resume_from_stop();
+        cpu.run_instruction(&mut ram);
+        assert_eq!(cpu.reg8(Reg::A), 1);
+        assert_eq!(cpu.reg16(Reg::PC), 0x0103);
     }
 }
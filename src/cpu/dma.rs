@@ -0,0 +1,196 @@
+use super::super::mem::mem::Memory;
+use super::super::mem::bus::Bus;
+
+const OAM_SIZE: u16 = 0xA0;
+
+// OAM DMA: copies 0xA0 bytes from (DMA << 8) to OAM, one byte per M-cycle.
+// While it runs the cpu can't use the bus the transfer reads from (it gets
+// whatever byte the DMA is moving instead) and OAM reads return 0xFF, so
+// games run the wait loop from HRAM.
+pub struct Dma {
+    register: u8, // last value written to DMA.
+    active: bool,
+    source: u16,
+    index: u16, // next byte to be copied.
+    current_byte: u8, // byte on the bus the transfer reads from.
+    // a write to DMA starts a new transfer one M-cycle later; until then the
+    // previous one, if any, keeps going.
+    pending: Option<u16>,
+}
+
+impl Default for Dma {
+    fn default() -> Dma {
+        Dma {
+            register: 0,
+            active: false,
+            source: 0,
+            index: 0,
+            current_byte: 0xFF,
+            pending: None,
+        }
+    }
+}
+
+// the cpu, vram and OAM/HRAM/IO buses.
+#[derive(PartialEq)]
+enum BusKind {
+    External,
+    Vram,
+    Internal,
+}
+
+fn bus_kind(address: u16) -> BusKind {
+    match address {
+        0x8000...0x9FFF => BusKind::Vram,
+        0xFE00...0xFFFF => BusKind::Internal,
+        _ => BusKind::External,
+    }
+}
+
+impl Dma {
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+        // sources above 0xDF00 read from the echo of WRAM.
+        let source: u16 = (value as u16) << 8;
+        self.pending = Some(if source >= 0xE000 {
+            source - 0x2000
+        } else {
+            source
+        });
+    }
+
+    #[cfg(test)]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // true if the cpu can't use 'address' right now.
+    pub fn is_blocked(&self, address: u16) -> bool {
+        self.active &&
+        match address {
+            0xFE00...0xFE9F => true,
+            // the source is never on the internal bus, so HRAM and IO always work.
+            _ => bus_kind(address) == bus_kind(self.source),
+        }
+    }
+
+    // what the cpu reads from a blocked address.
+    pub fn conflict_read(&self, address: u16) -> u8 {
+        match address {
+            0xFE00...0xFE9F => 0xFF,
+            _ => self.current_byte,
+        }
+    }
+
+    pub fn update(&mut self, cycles: u32, memory: &mut Memory) {
+        for _ in 0..cycles / 4 {
+            if self.active {
+                self.current_byte = memory.read_byte(self.source + self.index);
+                memory.write_oam(self.index, self.current_byte);
+                self.index += 1;
+                if self.index == OAM_SIZE {
+                    self.active = false;
+                }
+            }
+            if let Some(source) = self.pending {
+                self.pending = None;
+                self.active = true;
+                self.source = source;
+                self.index = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dma, OAM_SIZE};
+    use mem::bus::Bus;
+    use mem::mem::Memory;
+
+    fn source_byte(index: u16) -> u8 {
+        (index as u8) ^ 0x5A
+    }
+
+    fn fill_wram(memory: &mut Memory) {
+        for i in 0..OAM_SIZE {
+            memory.write_byte(0xC000 + i, source_byte(i));
+        }
+    }
+
+    #[test]
+    fn copies_one_byte_per_m_cycle_after_a_one_cycle_delay() {
+        let mut memory: Memory = Memory::default();
+        fill_wram(&mut memory);
+        let mut dma: Dma = Dma::default();
+        dma.write(0xC0);
+        assert_eq!(dma.read(), 0xC0);
+        assert!(!dma.is_active());
+
+        dma.update(4, &mut memory);
+        assert!(dma.is_active());
+        assert_eq!(memory.read_byte(0xFE00), 0);
+
+        dma.update(4, &mut memory);
+        assert_eq!(memory.read_byte(0xFE00), source_byte(0));
+        assert_eq!(memory.read_byte(0xFE01), 0);
+
+        dma.update(4 * (OAM_SIZE as u32 - 1), &mut memory);
+        assert!(!dma.is_active());
+        for i in 0..OAM_SIZE {
+            assert_eq!(memory.read_byte(0xFE00 + i), source_byte(i));
+        }
+    }
+
+    #[test]
+    fn echo_sources_read_from_wram() {
+        let mut memory: Memory = Memory::default();
+        fill_wram(&mut memory);
+        let mut dma: Dma = Dma::default();
+        dma.write(0xE0);
+        dma.update(4 * (OAM_SIZE as u32 + 1), &mut memory);
+        for i in 0..OAM_SIZE {
+            assert_eq!(memory.read_byte(0xFE00 + i), source_byte(i));
+        }
+    }
+
+    #[test]
+    fn blocks_the_source_bus_and_oam() {
+        let mut memory: Memory = Memory::default();
+        fill_wram(&mut memory);
+        let mut dma: Dma = Dma::default();
+        dma.write(0xC0);
+        assert!(!dma.is_blocked(0xC000));
+
+        dma.update(4 * 3, &mut memory);
+        // WRAM and the cartridge share the external bus.
+        assert!(dma.is_blocked(0xD000));
+        assert!(dma.is_blocked(0x0150));
+        assert!(dma.is_blocked(0xFE00));
+        assert!(!dma.is_blocked(0x8000));
+        assert!(!dma.is_blocked(0xFF80));
+        // the cpu gets the byte being moved, and 0xFF from OAM.
+        assert_eq!(dma.conflict_read(0xD000), source_byte(1));
+        assert_eq!(dma.conflict_read(0xFE10), 0xFF);
+    }
+
+    #[test]
+    fn restarting_keeps_the_old_transfer_for_one_m_cycle() {
+        let mut memory: Memory = Memory::default();
+        fill_wram(&mut memory);
+        let mut dma: Dma = Dma::default();
+        dma.write(0xC0);
+        dma.update(4 * 3, &mut memory);
+
+        dma.write(0x80);
+        dma.update(4, &mut memory);
+        assert_eq!(memory.read_byte(0xFE02), source_byte(2));
+        // the new transfer starts over from its first byte, from VRAM.
+        assert!(dma.is_blocked(0x8000));
+        assert!(!dma.is_blocked(0xC000));
+    }
+}
@@ -1,4 +1,4 @@
-use super::super::mem::mem;
+use super::super::mem::bus::Bus;
 use cpu::consts;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -45,42 +45,42 @@ pub fn address(interrupt: Interrupt) -> u16 {
 }
 
 #[inline]
-fn is_set_bit(bit: u8, addr: u16, memory: &mem::Memory) -> bool {
+fn is_set_bit<B: Bus>(bit: u8, addr: u16, memory: &mut B) -> bool {
     let reg: u8 = memory.read_byte(addr);
     ((reg >> bit) & 0b1) == 0b1
 }
 
 #[inline]
-fn set_bit(bit: u8, addr: u16, memory: &mut mem::Memory) {
+fn set_bit<B: Bus>(bit: u8, addr: u16, memory: &mut B) {
     let reg: u8 = memory.read_byte(addr);
     let new: u8 = reg | (1 << bit);
     memory.write_byte(addr, new);
 }
 
 #[inline]
-fn unset_bit(bit: u8, addr: u16, memory: &mut mem::Memory) {
+fn unset_bit<B: Bus>(bit: u8, addr: u16, memory: &mut B) {
     let reg: u8 = memory.read_byte(addr);
     let new: u8 = reg & !(1 << bit);
     memory.write_byte(addr, new);
 }
 
 #[inline]
-pub fn is_requested(interrupt: Interrupt, memory: &mem::Memory) -> bool {
+pub fn is_requested<B: Bus>(interrupt: Interrupt, memory: &mut B) -> bool {
     is_set_bit(bit(interrupt), consts::IF_REGISTER_ADDR, memory)
 }
 
 #[inline]
-pub fn request(interrupt: Interrupt, memory: &mut mem::Memory) {
+pub fn request<B: Bus>(interrupt: Interrupt, memory: &mut B) {
     set_bit(bit(interrupt), consts::IF_REGISTER_ADDR, memory);
 }
 
 #[inline]
-pub fn remove_request(interrupt: Interrupt, memory: &mut mem::Memory) {
+pub fn remove_request<B: Bus>(interrupt: Interrupt, memory: &mut B) {
     unset_bit(bit(interrupt), consts::IF_REGISTER_ADDR, memory);
 }
 
 #[inline]
-pub fn next_request(memory: &mem::Memory) -> Option<Interrupt> {
+pub fn next_request<B: Bus>(memory: &mut B) -> Option<Interrupt> {
     // order of priority
     for bit in 0..5 {
         let interrupt: Interrupt = from_bit(bit);
@@ -92,6 +92,6 @@ pub fn next_request(memory: &mem::Memory) -> Option<Interrupt> {
 }
 
 #[inline]
-pub fn is_enabled(interrupt: Interrupt, memory: &mem::Memory) -> bool {
+pub fn is_enabled<B: Bus>(interrupt: Interrupt, memory: &mut B) -> bool {
     is_set_bit(bit(interrupt), consts::IE_REGISTER_ADDR, memory)
 }
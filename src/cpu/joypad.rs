@@ -0,0 +1,112 @@
+use super::super::mem::bus::Bus;
+use cpu::interrupt;
+
+// P1 register. The game selects the direction keys (bit 4 low) and/or the
+// button keys (bit 5 low) and reads the state of the selected ones in the lower
+// nibble, where 0 means pressed.
+pub struct Joypad {
+    select: u8,
+    keys: u8, // nibble to the left are direction keys and to the right button keys.
+}
+
+impl Default for Joypad {
+    fn default() -> Joypad {
+        Joypad {
+            select: 0b0011_0000,
+            keys: 0b1111_1111,
+        }
+    }
+}
+
+impl Joypad {
+    // state of the four input lines, as seen in the lower nibble of P1.
+    pub fn lines(&self) -> u8 {
+        let mut lines: u8 = 0b1111;
+        if self.select & 0b0001_0000 == 0b0 {
+            lines &= self.keys >> 4;
+        }
+        if self.select & 0b0010_0000 == 0b0 {
+            lines &= self.keys & 0b0000_1111;
+        }
+        lines
+    }
+
+    pub fn read(&self) -> u8 {
+        0b1100_0000 | self.select | self.lines()
+    }
+
+    pub fn write<B: Bus>(&mut self, value: u8, memory: &mut B) {
+        let lines: u8 = self.lines();
+        self.select = value & 0b0011_0000;
+        self.request_interrupt(lines, memory);
+    }
+
+    // returns true if a line went low, which is also what ends STOP.
+    pub fn set_keys<B: Bus>(&mut self, keys: u8, memory: &mut B) -> bool {
+        let lines: u8 = self.lines();
+        self.keys = keys;
+        self.request_interrupt(lines, memory)
+    }
+
+    // the interrupt is requested when any of the lines goes from high to low.
+    fn request_interrupt<B: Bus>(&self, old_lines: u8, memory: &mut B) -> bool {
+        let fell: bool = old_lines & !self.lines() != 0b0;
+        if fell {
+            interrupt::request(interrupt::Interrupt::Joypad, memory);
+        }
+        fell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Joypad;
+    use cpu::consts;
+    use mem::bus::Bus;
+    use sm83::FlatRam;
+
+    const JOYPAD_INTERRUPT: u8 = 0b1_0000;
+    const NO_KEYS: u8 = 0b1111_1111;
+    const A: u8 = 0b1111_1110;
+    const A_AND_B: u8 = 0b1111_1100;
+    const RIGHT: u8 = 0b1110_1111;
+
+    fn buttons_selected(ram: &mut FlatRam) -> Joypad {
+        let mut joypad: Joypad = Joypad::default();
+        joypad.write(0b0001_0000, ram);
+        joypad
+    }
+
+    #[test]
+    fn a_line_going_low_requests_the_interrupt() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut joypad: Joypad = buttons_selected(&mut ram);
+
+        assert!(joypad.set_keys(A, &mut ram));
+        assert_eq!(joypad.read() & 0b1111, 0b1110);
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR), JOYPAD_INTERRUPT);
+    }
+
+    #[test]
+    fn a_key_held_down_is_not_a_new_edge() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut joypad: Joypad = buttons_selected(&mut ram);
+        joypad.set_keys(A, &mut ram);
+        ram.write_byte(consts::IF_REGISTER_ADDR, 0);
+
+        assert!(!joypad.set_keys(A, &mut ram));
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR), 0);
+        // another line going low while A is still held is.
+        assert!(joypad.set_keys(A_AND_B, &mut ram));
+    }
+
+    #[test]
+    fn keys_that_are_not_selected_do_nothing() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut joypad: Joypad = buttons_selected(&mut ram);
+
+        assert!(!joypad.set_keys(RIGHT, &mut ram));
+        assert!(!joypad.set_keys(NO_KEYS, &mut ram));
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR), 0);
+    }
+}
@@ -1,5 +1,8 @@
 pub mod cpu;
+pub mod opcodes;
 pub mod consts;
 pub mod interrupt;
 pub mod timer;
-pub mod ioregister;
+pub mod joypad;
+pub mod serial;
+pub mod dma;
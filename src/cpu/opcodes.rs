@@ -0,0 +1,642 @@
+// Decode table for the whole instruction set. Both the cpu and the debugger
+// use it, so what is executed and what is disassembled can't disagree.
+use cpu::cpu::Reg;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Operand {
+    None,
+    Imm8,
+    Imm16,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Cond {
+    Always,
+    NZ,
+    Z,
+    NC,
+    C,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Alu {
+    Add,
+    Adc,
+    Sub,
+    Sbc,
+    And,
+    Xor,
+    Or,
+    Cp,
+}
+
+// Reg::HL as an 8 bit operand means (HL).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Op {
+    Nop,
+    Stop,
+    Halt,
+    Di,
+    Ei,
+    Prefix,
+    Illegal,
+    Ld(Reg, Reg),
+    LdImm(Reg),
+    LdIndirectA(Reg),
+    LdAIndirect(Reg),
+    LdHliA,
+    LdHldA,
+    LdAHli,
+    LdAHld,
+    LdhImmA,
+    LdhAImm,
+    LdhCA,
+    LdhAC,
+    LdAbsA,
+    LdAAbs,
+    LdImm16(Reg),
+    LdAbsSp,
+    Pop(Reg),
+    Push(Reg),
+    LdHlSpImm,
+    LdSpHl,
+    Alu(Alu, Reg),
+    AluImm(Alu),
+    Inc(Reg),
+    Dec(Reg),
+    Daa,
+    Scf,
+    Cpl,
+    Ccf,
+    Inc16(Reg),
+    Dec16(Reg),
+    AddHl(Reg),
+    AddSpImm,
+    Rlca,
+    Rrca,
+    Rla,
+    Rra,
+    Jr(Cond),
+    Jp(Cond),
+    JpHl,
+    Ret(Cond),
+    Reti,
+    Call(Cond),
+    Rst(u16),
+    // CB-prefixed
+    Rlc(Reg),
+    Rrc(Reg),
+    Rl(Reg),
+    Rr(Reg),
+    Sla(Reg),
+    Sra(Reg),
+    Swap(Reg),
+    Srl(Reg),
+    Bit(u8, Reg),
+    Res(u8, Reg),
+    Set(u8, Reg),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Opcode {
+    pub op: Op,
+    // d8/a8/r8 and d16/a16 stand for the immediate value.
+    pub mnemonic: &'static str,
+    pub operand: Operand,
+    pub length: u8, // in bytes, including the opcode (and the 0xCB prefix).
+    pub cycles: u32,
+    pub branch_cycles: u32, // cycles when a conditional branch is taken.
+}
+
+macro_rules! opcode {
+    ($op:expr, $mnemonic:expr, $operand:ident, $length:expr, $cycles:expr,
+     $branch_cycles:expr) => {
+        Opcode {
+            op: $op,
+            mnemonic: $mnemonic,
+            operand: Operand::$operand,
+            length: $length,
+            cycles: $cycles,
+            branch_cycles: $branch_cycles,
+        }
+    }
+}
+
+pub static OPCODES: [Opcode; 256] = [
+    opcode!(Op::Nop, "nop", None, 1, 4, 4), // 0x00
+    opcode!(Op::LdImm16(Reg::BC), "ld BC,d16", Imm16, 3, 12, 12), // 0x01
+    opcode!(Op::LdIndirectA(Reg::BC), "ld (BC),A", None, 1, 8, 8), // 0x02
+    opcode!(Op::Inc16(Reg::BC), "inc BC", None, 1, 8, 8), // 0x03
+    opcode!(Op::Inc(Reg::B), "inc B", None, 1, 4, 4), // 0x04
+    opcode!(Op::Dec(Reg::B), "dec B", None, 1, 4, 4), // 0x05
+    opcode!(Op::LdImm(Reg::B), "ld B,d8", Imm8, 2, 8, 8), // 0x06
+    opcode!(Op::Rlca, "rlca", None, 1, 4, 4), // 0x07
+    opcode!(Op::LdAbsSp, "ld (a16),SP", Imm16, 3, 20, 20), // 0x08
+    opcode!(Op::AddHl(Reg::BC), "add HL,BC", None, 1, 8, 8), // 0x09
+    opcode!(Op::LdAIndirect(Reg::BC), "ld A,(BC)", None, 1, 8, 8), // 0x0A
+    opcode!(Op::Dec16(Reg::BC), "dec BC", None, 1, 8, 8), // 0x0B
+    opcode!(Op::Inc(Reg::C), "inc C", None, 1, 4, 4), // 0x0C
+    opcode!(Op::Dec(Reg::C), "dec C", None, 1, 4, 4), // 0x0D
+    opcode!(Op::LdImm(Reg::C), "ld C,d8", Imm8, 2, 8, 8), // 0x0E
+    opcode!(Op::Rrca, "rrca", None, 1, 4, 4), // 0x0F
+    opcode!(Op::Stop, "stop", Imm8, 2, 8, 8), // 0x10
+    opcode!(Op::LdImm16(Reg::DE), "ld DE,d16", Imm16, 3, 12, 12), // 0x11
+    opcode!(Op::LdIndirectA(Reg::DE), "ld (DE),A", None, 1, 8, 8), // 0x12
+    opcode!(Op::Inc16(Reg::DE), "inc DE", None, 1, 8, 8), // 0x13
+    opcode!(Op::Inc(Reg::D), "inc D", None, 1, 4, 4), // 0x14
+    opcode!(Op::Dec(Reg::D), "dec D", None, 1, 4, 4), // 0x15
+    opcode!(Op::LdImm(Reg::D), "ld D,d8", Imm8, 2, 8, 8), // 0x16
+    opcode!(Op::Rla, "rla", None, 1, 4, 4), // 0x17
+    opcode!(Op::Jr(Cond::Always), "jr r8", Imm8, 2, 12, 12), // 0x18
+    opcode!(Op::AddHl(Reg::DE), "add HL,DE", None, 1, 8, 8), // 0x19
+    opcode!(Op::LdAIndirect(Reg::DE), "ld A,(DE)", None, 1, 8, 8), // 0x1A
+    opcode!(Op::Dec16(Reg::DE), "dec DE", None, 1, 8, 8), // 0x1B
+    opcode!(Op::Inc(Reg::E), "inc E", None, 1, 4, 4), // 0x1C
+    opcode!(Op::Dec(Reg::E), "dec E", None, 1, 4, 4), // 0x1D
+    opcode!(Op::LdImm(Reg::E), "ld E,d8", Imm8, 2, 8, 8), // 0x1E
+    opcode!(Op::Rra, "rra", None, 1, 4, 4), // 0x1F
+    opcode!(Op::Jr(Cond::NZ), "jr nz,r8", Imm8, 2, 8, 12), // 0x20
+    opcode!(Op::LdImm16(Reg::HL), "ld HL,d16", Imm16, 3, 12, 12), // 0x21
+    opcode!(Op::LdHliA, "ld (HL+),A", None, 1, 8, 8), // 0x22
+    opcode!(Op::Inc16(Reg::HL), "inc HL", None, 1, 8, 8), // 0x23
+    opcode!(Op::Inc(Reg::H), "inc H", None, 1, 4, 4), // 0x24
+    opcode!(Op::Dec(Reg::H), "dec H", None, 1, 4, 4), // 0x25
+    opcode!(Op::LdImm(Reg::H), "ld H,d8", Imm8, 2, 8, 8), // 0x26
+    opcode!(Op::Daa, "daa", None, 1, 4, 4), // 0x27
+    opcode!(Op::Jr(Cond::Z), "jr z,r8", Imm8, 2, 8, 12), // 0x28
+    opcode!(Op::AddHl(Reg::HL), "add HL,HL", None, 1, 8, 8), // 0x29
+    opcode!(Op::LdAHli, "ld A,(HL+)", None, 1, 8, 8), // 0x2A
+    opcode!(Op::Dec16(Reg::HL), "dec HL", None, 1, 8, 8), // 0x2B
+    opcode!(Op::Inc(Reg::L), "inc L", None, 1, 4, 4), // 0x2C
+    opcode!(Op::Dec(Reg::L), "dec L", None, 1, 4, 4), // 0x2D
+    opcode!(Op::LdImm(Reg::L), "ld L,d8", Imm8, 2, 8, 8), // 0x2E
+    opcode!(Op::Cpl, "cpl", None, 1, 4, 4), // 0x2F
+    opcode!(Op::Jr(Cond::NC), "jr nc,r8", Imm8, 2, 8, 12), // 0x30
+    opcode!(Op::LdImm16(Reg::SP), "ld SP,d16", Imm16, 3, 12, 12), // 0x31
+    opcode!(Op::LdHldA, "ld (HL-),A", None, 1, 8, 8), // 0x32
+    opcode!(Op::Inc16(Reg::SP), "inc SP", None, 1, 8, 8), // 0x33
+    opcode!(Op::Inc(Reg::HL), "inc (HL)", None, 1, 12, 12), // 0x34
+    opcode!(Op::Dec(Reg::HL), "dec (HL)", None, 1, 12, 12), // 0x35
+    opcode!(Op::LdImm(Reg::HL), "ld (HL),d8", Imm8, 2, 12, 12), // 0x36
+    opcode!(Op::Scf, "scf", None, 1, 4, 4), // 0x37
+    opcode!(Op::Jr(Cond::C), "jr c,r8", Imm8, 2, 8, 12), // 0x38
+    opcode!(Op::AddHl(Reg::SP), "add HL,SP", None, 1, 8, 8), // 0x39
+    opcode!(Op::LdAHld, "ld A,(HL-)", None, 1, 8, 8), // 0x3A
+    opcode!(Op::Dec16(Reg::SP), "dec SP", None, 1, 8, 8), // 0x3B
+    opcode!(Op::Inc(Reg::A), "inc A", None, 1, 4, 4), // 0x3C
+    opcode!(Op::Dec(Reg::A), "dec A", None, 1, 4, 4), // 0x3D
+    opcode!(Op::LdImm(Reg::A), "ld A,d8", Imm8, 2, 8, 8), // 0x3E
+    opcode!(Op::Ccf, "ccf", None, 1, 4, 4), // 0x3F
+    opcode!(Op::Ld(Reg::B, Reg::B), "ld B,B", None, 1, 4, 4), // 0x40
+    opcode!(Op::Ld(Reg::B, Reg::C), "ld B,C", None, 1, 4, 4), // 0x41
+    opcode!(Op::Ld(Reg::B, Reg::D), "ld B,D", None, 1, 4, 4), // 0x42
+    opcode!(Op::Ld(Reg::B, Reg::E), "ld B,E", None, 1, 4, 4), // 0x43
+    opcode!(Op::Ld(Reg::B, Reg::H), "ld B,H", None, 1, 4, 4), // 0x44
+    opcode!(Op::Ld(Reg::B, Reg::L), "ld B,L", None, 1, 4, 4), // 0x45
+    opcode!(Op::Ld(Reg::B, Reg::HL), "ld B,(HL)", None, 1, 8, 8), // 0x46
+    opcode!(Op::Ld(Reg::B, Reg::A), "ld B,A", None, 1, 4, 4), // 0x47
+    opcode!(Op::Ld(Reg::C, Reg::B), "ld C,B", None, 1, 4, 4), // 0x48
+    opcode!(Op::Ld(Reg::C, Reg::C), "ld C,C", None, 1, 4, 4), // 0x49
+    opcode!(Op::Ld(Reg::C, Reg::D), "ld C,D", None, 1, 4, 4), // 0x4A
+    opcode!(Op::Ld(Reg::C, Reg::E), "ld C,E", None, 1, 4, 4), // 0x4B
+    opcode!(Op::Ld(Reg::C, Reg::H), "ld C,H", None, 1, 4, 4), // 0x4C
+    opcode!(Op::Ld(Reg::C, Reg::L), "ld C,L", None, 1, 4, 4), // 0x4D
+    opcode!(Op::Ld(Reg::C, Reg::HL), "ld C,(HL)", None, 1, 8, 8), // 0x4E
+    opcode!(Op::Ld(Reg::C, Reg::A), "ld C,A", None, 1, 4, 4), // 0x4F
+    opcode!(Op::Ld(Reg::D, Reg::B), "ld D,B", None, 1, 4, 4), // 0x50
+    opcode!(Op::Ld(Reg::D, Reg::C), "ld D,C", None, 1, 4, 4), // 0x51
+    opcode!(Op::Ld(Reg::D, Reg::D), "ld D,D", None, 1, 4, 4), // 0x52
+    opcode!(Op::Ld(Reg::D, Reg::E), "ld D,E", None, 1, 4, 4), // 0x53
+    opcode!(Op::Ld(Reg::D, Reg::H), "ld D,H", None, 1, 4, 4), // 0x54
+    opcode!(Op::Ld(Reg::D, Reg::L), "ld D,L", None, 1, 4, 4), // 0x55
+    opcode!(Op::Ld(Reg::D, Reg::HL), "ld D,(HL)", None, 1, 8, 8), // 0x56
+    opcode!(Op::Ld(Reg::D, Reg::A), "ld D,A", None, 1, 4, 4), // 0x57
+    opcode!(Op::Ld(Reg::E, Reg::B), "ld E,B", None, 1, 4, 4), // 0x58
+    opcode!(Op::Ld(Reg::E, Reg::C), "ld E,C", None, 1, 4, 4), // 0x59
+    opcode!(Op::Ld(Reg::E, Reg::D), "ld E,D", None, 1, 4, 4), // 0x5A
+    opcode!(Op::Ld(Reg::E, Reg::E), "ld E,E", None, 1, 4, 4), // 0x5B
+    opcode!(Op::Ld(Reg::E, Reg::H), "ld E,H", None, 1, 4, 4), // 0x5C
+    opcode!(Op::Ld(Reg::E, Reg::L), "ld E,L", None, 1, 4, 4), // 0x5D
+    opcode!(Op::Ld(Reg::E, Reg::HL), "ld E,(HL)", None, 1, 8, 8), // 0x5E
+    opcode!(Op::Ld(Reg::E, Reg::A), "ld E,A", None, 1, 4, 4), // 0x5F
+    opcode!(Op::Ld(Reg::H, Reg::B), "ld H,B", None, 1, 4, 4), // 0x60
+    opcode!(Op::Ld(Reg::H, Reg::C), "ld H,C", None, 1, 4, 4), // 0x61
+    opcode!(Op::Ld(Reg::H, Reg::D), "ld H,D", None, 1, 4, 4), // 0x62
+    opcode!(Op::Ld(Reg::H, Reg::E), "ld H,E", None, 1, 4, 4), // 0x63
+    opcode!(Op::Ld(Reg::H, Reg::H), "ld H,H", None, 1, 4, 4), // 0x64
+    opcode!(Op::Ld(Reg::H, Reg::L), "ld H,L", None, 1, 4, 4), // 0x65
+    opcode!(Op::Ld(Reg::H, Reg::HL), "ld H,(HL)", None, 1, 8, 8), // 0x66
+    opcode!(Op::Ld(Reg::H, Reg::A), "ld H,A", None, 1, 4, 4), // 0x67
+    opcode!(Op::Ld(Reg::L, Reg::B), "ld L,B", None, 1, 4, 4), // 0x68
+    opcode!(Op::Ld(Reg::L, Reg::C), "ld L,C", None, 1, 4, 4), // 0x69
+    opcode!(Op::Ld(Reg::L, Reg::D), "ld L,D", None, 1, 4, 4), // 0x6A
+    opcode!(Op::Ld(Reg::L, Reg::E), "ld L,E", None, 1, 4, 4), // 0x6B
+    opcode!(Op::Ld(Reg::L, Reg::H), "ld L,H", None, 1, 4, 4), // 0x6C
+    opcode!(Op::Ld(Reg::L, Reg::L), "ld L,L", None, 1, 4, 4), // 0x6D
+    opcode!(Op::Ld(Reg::L, Reg::HL), "ld L,(HL)", None, 1, 8, 8), // 0x6E
+    opcode!(Op::Ld(Reg::L, Reg::A), "ld L,A", None, 1, 4, 4), // 0x6F
+    opcode!(Op::Ld(Reg::HL, Reg::B), "ld (HL),B", None, 1, 8, 8), // 0x70
+    opcode!(Op::Ld(Reg::HL, Reg::C), "ld (HL),C", None, 1, 8, 8), // 0x71
+    opcode!(Op::Ld(Reg::HL, Reg::D), "ld (HL),D", None, 1, 8, 8), // 0x72
+    opcode!(Op::Ld(Reg::HL, Reg::E), "ld (HL),E", None, 1, 8, 8), // 0x73
+    opcode!(Op::Ld(Reg::HL, Reg::H), "ld (HL),H", None, 1, 8, 8), // 0x74
+    opcode!(Op::Ld(Reg::HL, Reg::L), "ld (HL),L", None, 1, 8, 8), // 0x75
+    opcode!(Op::Halt, "halt", None, 1, 4, 4), // 0x76
+    opcode!(Op::Ld(Reg::HL, Reg::A), "ld (HL),A", None, 1, 8, 8), // 0x77
+    opcode!(Op::Ld(Reg::A, Reg::B), "ld A,B", None, 1, 4, 4), // 0x78
+    opcode!(Op::Ld(Reg::A, Reg::C), "ld A,C", None, 1, 4, 4), // 0x79
+    opcode!(Op::Ld(Reg::A, Reg::D), "ld A,D", None, 1, 4, 4), // 0x7A
+    opcode!(Op::Ld(Reg::A, Reg::E), "ld A,E", None, 1, 4, 4), // 0x7B
+    opcode!(Op::Ld(Reg::A, Reg::H), "ld A,H", None, 1, 4, 4), // 0x7C
+    opcode!(Op::Ld(Reg::A, Reg::L), "ld A,L", None, 1, 4, 4), // 0x7D
+    opcode!(Op::Ld(Reg::A, Reg::HL), "ld A,(HL)", None, 1, 8, 8), // 0x7E
+    opcode!(Op::Ld(Reg::A, Reg::A), "ld A,A", None, 1, 4, 4), // 0x7F
+    opcode!(Op::Alu(Alu::Add, Reg::B), "add A,B", None, 1, 4, 4), // 0x80
+    opcode!(Op::Alu(Alu::Add, Reg::C), "add A,C", None, 1, 4, 4), // 0x81
+    opcode!(Op::Alu(Alu::Add, Reg::D), "add A,D", None, 1, 4, 4), // 0x82
+    opcode!(Op::Alu(Alu::Add, Reg::E), "add A,E", None, 1, 4, 4), // 0x83
+    opcode!(Op::Alu(Alu::Add, Reg::H), "add A,H", None, 1, 4, 4), // 0x84
+    opcode!(Op::Alu(Alu::Add, Reg::L), "add A,L", None, 1, 4, 4), // 0x85
+    opcode!(Op::Alu(Alu::Add, Reg::HL), "add A,(HL)", None, 1, 8, 8), // 0x86
+    opcode!(Op::Alu(Alu::Add, Reg::A), "add A,A", None, 1, 4, 4), // 0x87
+    opcode!(Op::Alu(Alu::Adc, Reg::B), "adc A,B", None, 1, 4, 4), // 0x88
+    opcode!(Op::Alu(Alu::Adc, Reg::C), "adc A,C", None, 1, 4, 4), // 0x89
+    opcode!(Op::Alu(Alu::Adc, Reg::D), "adc A,D", None, 1, 4, 4), // 0x8A
+    opcode!(Op::Alu(Alu::Adc, Reg::E), "adc A,E", None, 1, 4, 4), // 0x8B
+    opcode!(Op::Alu(Alu::Adc, Reg::H), "adc A,H", None, 1, 4, 4), // 0x8C
+    opcode!(Op::Alu(Alu::Adc, Reg::L), "adc A,L", None, 1, 4, 4), // 0x8D
+    opcode!(Op::Alu(Alu::Adc, Reg::HL), "adc A,(HL)", None, 1, 8, 8), // 0x8E
+    opcode!(Op::Alu(Alu::Adc, Reg::A), "adc A,A", None, 1, 4, 4), // 0x8F
+    opcode!(Op::Alu(Alu::Sub, Reg::B), "sub B", None, 1, 4, 4), // 0x90
+    opcode!(Op::Alu(Alu::Sub, Reg::C), "sub C", None, 1, 4, 4), // 0x91
+    opcode!(Op::Alu(Alu::Sub, Reg::D), "sub D", None, 1, 4, 4), // 0x92
+    opcode!(Op::Alu(Alu::Sub, Reg::E), "sub E", None, 1, 4, 4), // 0x93
+    opcode!(Op::Alu(Alu::Sub, Reg::H), "sub H", None, 1, 4, 4), // 0x94
+    opcode!(Op::Alu(Alu::Sub, Reg::L), "sub L", None, 1, 4, 4), // 0x95
+    opcode!(Op::Alu(Alu::Sub, Reg::HL), "sub (HL)", None, 1, 8, 8), // 0x96
+    opcode!(Op::Alu(Alu::Sub, Reg::A), "sub A", None, 1, 4, 4), // 0x97
+    opcode!(Op::Alu(Alu::Sbc, Reg::B), "sbc A,B", None, 1, 4, 4), // 0x98
+    opcode!(Op::Alu(Alu::Sbc, Reg::C), "sbc A,C", None, 1, 4, 4), // 0x99
+    opcode!(Op::Alu(Alu::Sbc, Reg::D), "sbc A,D", None, 1, 4, 4), // 0x9A
+    opcode!(Op::Alu(Alu::Sbc, Reg::E), "sbc A,E", None, 1, 4, 4), // 0x9B
+    opcode!(Op::Alu(Alu::Sbc, Reg::H), "sbc A,H", None, 1, 4, 4), // 0x9C
+    opcode!(Op::Alu(Alu::Sbc, Reg::L), "sbc A,L", None, 1, 4, 4), // 0x9D
+    opcode!(Op::Alu(Alu::Sbc, Reg::HL), "sbc A,(HL)", None, 1, 8, 8), // 0x9E
+    opcode!(Op::Alu(Alu::Sbc, Reg::A), "sbc A,A", None, 1, 4, 4), // 0x9F
+    opcode!(Op::Alu(Alu::And, Reg::B), "and B", None, 1, 4, 4), // 0xA0
+    opcode!(Op::Alu(Alu::And, Reg::C), "and C", None, 1, 4, 4), // 0xA1
+    opcode!(Op::Alu(Alu::And, Reg::D), "and D", None, 1, 4, 4), // 0xA2
+    opcode!(Op::Alu(Alu::And, Reg::E), "and E", None, 1, 4, 4), // 0xA3
+    opcode!(Op::Alu(Alu::And, Reg::H), "and H", None, 1, 4, 4), // 0xA4
+    opcode!(Op::Alu(Alu::And, Reg::L), "and L", None, 1, 4, 4), // 0xA5
+    opcode!(Op::Alu(Alu::And, Reg::HL), "and (HL)", None, 1, 8, 8), // 0xA6
+    opcode!(Op::Alu(Alu::And, Reg::A), "and A", None, 1, 4, 4), // 0xA7
+    opcode!(Op::Alu(Alu::Xor, Reg::B), "xor B", None, 1, 4, 4), // 0xA8
+    opcode!(Op::Alu(Alu::Xor, Reg::C), "xor C", None, 1, 4, 4), // 0xA9
+    opcode!(Op::Alu(Alu::Xor, Reg::D), "xor D", None, 1, 4, 4), // 0xAA
+    opcode!(Op::Alu(Alu::Xor, Reg::E), "xor E", None, 1, 4, 4), // 0xAB
+    opcode!(Op::Alu(Alu::Xor, Reg::H), "xor H", None, 1, 4, 4), // 0xAC
+    opcode!(Op::Alu(Alu::Xor, Reg::L), "xor L", None, 1, 4, 4), // 0xAD
+    opcode!(Op::Alu(Alu::Xor, Reg::HL), "xor (HL)", None, 1, 8, 8), // 0xAE
+    opcode!(Op::Alu(Alu::Xor, Reg::A), "xor A", None, 1, 4, 4), // 0xAF
+    opcode!(Op::Alu(Alu::Or, Reg::B), "or B", None, 1, 4, 4), // 0xB0
+    opcode!(Op::Alu(Alu::Or, Reg::C), "or C", None, 1, 4, 4), // 0xB1
+    opcode!(Op::Alu(Alu::Or, Reg::D), "or D", None, 1, 4, 4), // 0xB2
+    opcode!(Op::Alu(Alu::Or, Reg::E), "or E", None, 1, 4, 4), // 0xB3
+    opcode!(Op::Alu(Alu::Or, Reg::H), "or H", None, 1, 4, 4), // 0xB4
+    opcode!(Op::Alu(Alu::Or, Reg::L), "or L", None, 1, 4, 4), // 0xB5
+    opcode!(Op::Alu(Alu::Or, Reg::HL), "or (HL)", None, 1, 8, 8), // 0xB6
+    opcode!(Op::Alu(Alu::Or, Reg::A), "or A", None, 1, 4, 4), // 0xB7
+    opcode!(Op::Alu(Alu::Cp, Reg::B), "cp B", None, 1, 4, 4), // 0xB8
+    opcode!(Op::Alu(Alu::Cp, Reg::C), "cp C", None, 1, 4, 4), // 0xB9
+    opcode!(Op::Alu(Alu::Cp, Reg::D), "cp D", None, 1, 4, 4), // 0xBA
+    opcode!(Op::Alu(Alu::Cp, Reg::E), "cp E", None, 1, 4, 4), // 0xBB
+    opcode!(Op::Alu(Alu::Cp, Reg::H), "cp H", None, 1, 4, 4), // 0xBC
+    opcode!(Op::Alu(Alu::Cp, Reg::L), "cp L", None, 1, 4, 4), // 0xBD
+    opcode!(Op::Alu(Alu::Cp, Reg::HL), "cp (HL)", None, 1, 8, 8), // 0xBE
+    opcode!(Op::Alu(Alu::Cp, Reg::A), "cp A", None, 1, 4, 4), // 0xBF
+    opcode!(Op::Ret(Cond::NZ), "ret nz", None, 1, 8, 20), // 0xC0
+    opcode!(Op::Pop(Reg::BC), "pop BC", None, 1, 12, 12), // 0xC1
+    opcode!(Op::Jp(Cond::NZ), "jp nz,a16", Imm16, 3, 12, 16), // 0xC2
+    opcode!(Op::Jp(Cond::Always), "jp a16", Imm16, 3, 16, 16), // 0xC3
+    opcode!(Op::Call(Cond::NZ), "call nz,a16", Imm16, 3, 12, 24), // 0xC4
+    opcode!(Op::Push(Reg::BC), "push BC", None, 1, 16, 16), // 0xC5
+    opcode!(Op::AluImm(Alu::Add), "add A,d8", Imm8, 2, 8, 8), // 0xC6
+    opcode!(Op::Rst(0x0), "rst 0x0", None, 1, 16, 16), // 0xC7
+    opcode!(Op::Ret(Cond::Z), "ret z", None, 1, 8, 20), // 0xC8
+    opcode!(Op::Ret(Cond::Always), "ret", None, 1, 16, 16), // 0xC9
+    opcode!(Op::Jp(Cond::Z), "jp z,a16", Imm16, 3, 12, 16), // 0xCA
+    opcode!(Op::Prefix, "prefix cb", None, 1, 4, 4), // 0xCB
+    opcode!(Op::Call(Cond::Z), "call z,a16", Imm16, 3, 12, 24), // 0xCC
+    opcode!(Op::Call(Cond::Always), "call a16", Imm16, 3, 24, 24), // 0xCD
+    opcode!(Op::AluImm(Alu::Adc), "adc A,d8", Imm8, 2, 8, 8), // 0xCE
+    opcode!(Op::Rst(0x8), "rst 0x8", None, 1, 16, 16), // 0xCF
+    opcode!(Op::Ret(Cond::NC), "ret nc", None, 1, 8, 20), // 0xD0
+    opcode!(Op::Pop(Reg::DE), "pop DE", None, 1, 12, 12), // 0xD1
+    opcode!(Op::Jp(Cond::NC), "jp nc,a16", Imm16, 3, 12, 16), // 0xD2
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xD3
+    opcode!(Op::Call(Cond::NC), "call nc,a16", Imm16, 3, 12, 24), // 0xD4
+    opcode!(Op::Push(Reg::DE), "push DE", None, 1, 16, 16), // 0xD5
+    opcode!(Op::AluImm(Alu::Sub), "sub d8", Imm8, 2, 8, 8), // 0xD6
+    opcode!(Op::Rst(0x10), "rst 0x10", None, 1, 16, 16), // 0xD7
+    opcode!(Op::Ret(Cond::C), "ret c", None, 1, 8, 20), // 0xD8
+    opcode!(Op::Reti, "reti", None, 1, 16, 16), // 0xD9
+    opcode!(Op::Jp(Cond::C), "jp c,a16", Imm16, 3, 12, 16), // 0xDA
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xDB
+    opcode!(Op::Call(Cond::C), "call c,a16", Imm16, 3, 12, 24), // 0xDC
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xDD
+    opcode!(Op::AluImm(Alu::Sbc), "sbc A,d8", Imm8, 2, 8, 8), // 0xDE
+    opcode!(Op::Rst(0x18), "rst 0x18", None, 1, 16, 16), // 0xDF
+    opcode!(Op::LdhImmA, "ldh (a8),A", Imm8, 2, 12, 12), // 0xE0
+    opcode!(Op::Pop(Reg::HL), "pop HL", None, 1, 12, 12), // 0xE1
+    opcode!(Op::LdhCA, "ld (0xff00+C),A", None, 1, 8, 8), // 0xE2
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xE3
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xE4
+    opcode!(Op::Push(Reg::HL), "push HL", None, 1, 16, 16), // 0xE5
+    opcode!(Op::AluImm(Alu::And), "and d8", Imm8, 2, 8, 8), // 0xE6
+    opcode!(Op::Rst(0x20), "rst 0x20", None, 1, 16, 16), // 0xE7
+    opcode!(Op::AddSpImm, "add SP,r8", Imm8, 2, 16, 16), // 0xE8
+    opcode!(Op::JpHl, "jp (HL)", None, 1, 4, 4), // 0xE9
+    opcode!(Op::LdAbsA, "ld (a16),A", Imm16, 3, 16, 16), // 0xEA
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xEB
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xEC
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xED
+    opcode!(Op::AluImm(Alu::Xor), "xor d8", Imm8, 2, 8, 8), // 0xEE
+    opcode!(Op::Rst(0x28), "rst 0x28", None, 1, 16, 16), // 0xEF
+    opcode!(Op::LdhAImm, "ldh A,(a8)", Imm8, 2, 12, 12), // 0xF0
+    opcode!(Op::Pop(Reg::AF), "pop AF", None, 1, 12, 12), // 0xF1
+    opcode!(Op::LdhAC, "ld A,(0xff00+C)", None, 1, 8, 8), // 0xF2
+    opcode!(Op::Di, "di", None, 1, 4, 4), // 0xF3
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xF4
+    opcode!(Op::Push(Reg::AF), "push AF", None, 1, 16, 16), // 0xF5
+    opcode!(Op::AluImm(Alu::Or), "or d8", Imm8, 2, 8, 8), // 0xF6
+    opcode!(Op::Rst(0x30), "rst 0x30", None, 1, 16, 16), // 0xF7
+    opcode!(Op::LdHlSpImm, "ld HL,SP+r8", Imm8, 2, 12, 12), // 0xF8
+    opcode!(Op::LdSpHl, "ld SP,HL", None, 1, 8, 8), // 0xF9
+    opcode!(Op::LdAAbs, "ld A,(a16)", Imm16, 3, 16, 16), // 0xFA
+    opcode!(Op::Ei, "ei", None, 1, 4, 4), // 0xFB
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xFC
+    opcode!(Op::Illegal, "illegal", None, 1, 4, 4), // 0xFD
+    opcode!(Op::AluImm(Alu::Cp), "cp d8", Imm8, 2, 8, 8), // 0xFE
+    opcode!(Op::Rst(0x38), "rst 0x38", None, 1, 16, 16), // 0xFF
+];
+
+// cycles include the ones spent fetching the 0xCB prefix.
+pub static CB_OPCODES: [Opcode; 256] = [
+    opcode!(Op::Rlc(Reg::B), "rlc B", None, 2, 8, 8), // 0x00
+    opcode!(Op::Rlc(Reg::C), "rlc C", None, 2, 8, 8), // 0x01
+    opcode!(Op::Rlc(Reg::D), "rlc D", None, 2, 8, 8), // 0x02
+    opcode!(Op::Rlc(Reg::E), "rlc E", None, 2, 8, 8), // 0x03
+    opcode!(Op::Rlc(Reg::H), "rlc H", None, 2, 8, 8), // 0x04
+    opcode!(Op::Rlc(Reg::L), "rlc L", None, 2, 8, 8), // 0x05
+    opcode!(Op::Rlc(Reg::HL), "rlc (HL)", None, 2, 16, 16), // 0x06
+    opcode!(Op::Rlc(Reg::A), "rlc A", None, 2, 8, 8), // 0x07
+    opcode!(Op::Rrc(Reg::B), "rrc B", None, 2, 8, 8), // 0x08
+    opcode!(Op::Rrc(Reg::C), "rrc C", None, 2, 8, 8), // 0x09
+    opcode!(Op::Rrc(Reg::D), "rrc D", None, 2, 8, 8), // 0x0A
+    opcode!(Op::Rrc(Reg::E), "rrc E", None, 2, 8, 8), // 0x0B
+    opcode!(Op::Rrc(Reg::H), "rrc H", None, 2, 8, 8), // 0x0C
+    opcode!(Op::Rrc(Reg::L), "rrc L", None, 2, 8, 8), // 0x0D
+    opcode!(Op::Rrc(Reg::HL), "rrc (HL)", None, 2, 16, 16), // 0x0E
+    opcode!(Op::Rrc(Reg::A), "rrc A", None, 2, 8, 8), // 0x0F
+    opcode!(Op::Rl(Reg::B), "rl B", None, 2, 8, 8), // 0x10
+    opcode!(Op::Rl(Reg::C), "rl C", None, 2, 8, 8), // 0x11
+    opcode!(Op::Rl(Reg::D), "rl D", None, 2, 8, 8), // 0x12
+    opcode!(Op::Rl(Reg::E), "rl E", None, 2, 8, 8), // 0x13
+    opcode!(Op::Rl(Reg::H), "rl H", None, 2, 8, 8), // 0x14
+    opcode!(Op::Rl(Reg::L), "rl L", None, 2, 8, 8), // 0x15
+    opcode!(Op::Rl(Reg::HL), "rl (HL)", None, 2, 16, 16), // 0x16
+    opcode!(Op::Rl(Reg::A), "rl A", None, 2, 8, 8), // 0x17
+    opcode!(Op::Rr(Reg::B), "rr B", None, 2, 8, 8), // 0x18
+    opcode!(Op::Rr(Reg::C), "rr C", None, 2, 8, 8), // 0x19
+    opcode!(Op::Rr(Reg::D), "rr D", None, 2, 8, 8), // 0x1A
+    opcode!(Op::Rr(Reg::E), "rr E", None, 2, 8, 8), // 0x1B
+    opcode!(Op::Rr(Reg::H), "rr H", None, 2, 8, 8), // 0x1C
+    opcode!(Op::Rr(Reg::L), "rr L", None, 2, 8, 8), // 0x1D
+    opcode!(Op::Rr(Reg::HL), "rr (HL)", None, 2, 16, 16), // 0x1E
+    opcode!(Op::Rr(Reg::A), "rr A", None, 2, 8, 8), // 0x1F
+    opcode!(Op::Sla(Reg::B), "sla B", None, 2, 8, 8), // 0x20
+    opcode!(Op::Sla(Reg::C), "sla C", None, 2, 8, 8), // 0x21
+    opcode!(Op::Sla(Reg::D), "sla D", None, 2, 8, 8), // 0x22
+    opcode!(Op::Sla(Reg::E), "sla E", None, 2, 8, 8), // 0x23
+    opcode!(Op::Sla(Reg::H), "sla H", None, 2, 8, 8), // 0x24
+    opcode!(Op::Sla(Reg::L), "sla L", None, 2, 8, 8), // 0x25
+    opcode!(Op::Sla(Reg::HL), "sla (HL)", None, 2, 16, 16), // 0x26
+    opcode!(Op::Sla(Reg::A), "sla A", None, 2, 8, 8), // 0x27
+    opcode!(Op::Sra(Reg::B), "sra B", None, 2, 8, 8), // 0x28
+    opcode!(Op::Sra(Reg::C), "sra C", None, 2, 8, 8), // 0x29
+    opcode!(Op::Sra(Reg::D), "sra D", None, 2, 8, 8), // 0x2A
+    opcode!(Op::Sra(Reg::E), "sra E", None, 2, 8, 8), // 0x2B
+    opcode!(Op::Sra(Reg::H), "sra H", None, 2, 8, 8), // 0x2C
+    opcode!(Op::Sra(Reg::L), "sra L", None, 2, 8, 8), // 0x2D
+    opcode!(Op::Sra(Reg::HL), "sra (HL)", None, 2, 16, 16), // 0x2E
+    opcode!(Op::Sra(Reg::A), "sra A", None, 2, 8, 8), // 0x2F
+    opcode!(Op::Swap(Reg::B), "swap B", None, 2, 8, 8), // 0x30
+    opcode!(Op::Swap(Reg::C), "swap C", None, 2, 8, 8), // 0x31
+    opcode!(Op::Swap(Reg::D), "swap D", None, 2, 8, 8), // 0x32
+    opcode!(Op::Swap(Reg::E), "swap E", None, 2, 8, 8), // 0x33
+    opcode!(Op::Swap(Reg::H), "swap H", None, 2, 8, 8), // 0x34
+    opcode!(Op::Swap(Reg::L), "swap L", None, 2, 8, 8), // 0x35
+    opcode!(Op::Swap(Reg::HL), "swap (HL)", None, 2, 16, 16), // 0x36
+    opcode!(Op::Swap(Reg::A), "swap A", None, 2, 8, 8), // 0x37
+    opcode!(Op::Srl(Reg::B), "srl B", None, 2, 8, 8), // 0x38
+    opcode!(Op::Srl(Reg::C), "srl C", None, 2, 8, 8), // 0x39
+    opcode!(Op::Srl(Reg::D), "srl D", None, 2, 8, 8), // 0x3A
+    opcode!(Op::Srl(Reg::E), "srl E", None, 2, 8, 8), // 0x3B
+    opcode!(Op::Srl(Reg::H), "srl H", None, 2, 8, 8), // 0x3C
+    opcode!(Op::Srl(Reg::L), "srl L", None, 2, 8, 8), // 0x3D
+    opcode!(Op::Srl(Reg::HL), "srl (HL)", None, 2, 16, 16), // 0x3E
+    opcode!(Op::Srl(Reg::A), "srl A", None, 2, 8, 8), // 0x3F
+    opcode!(Op::Bit(0, Reg::B), "bit 0,B", None, 2, 8, 8), // 0x40
+    opcode!(Op::Bit(0, Reg::C), "bit 0,C", None, 2, 8, 8), // 0x41
+    opcode!(Op::Bit(0, Reg::D), "bit 0,D", None, 2, 8, 8), // 0x42
+    opcode!(Op::Bit(0, Reg::E), "bit 0,E", None, 2, 8, 8), // 0x43
+    opcode!(Op::Bit(0, Reg::H), "bit 0,H", None, 2, 8, 8), // 0x44
+    opcode!(Op::Bit(0, Reg::L), "bit 0,L", None, 2, 8, 8), // 0x45
+    opcode!(Op::Bit(0, Reg::HL), "bit 0,(HL)", None, 2, 12, 12), // 0x46
+    opcode!(Op::Bit(0, Reg::A), "bit 0,A", None, 2, 8, 8), // 0x47
+    opcode!(Op::Bit(1, Reg::B), "bit 1,B", None, 2, 8, 8), // 0x48
+    opcode!(Op::Bit(1, Reg::C), "bit 1,C", None, 2, 8, 8), // 0x49
+    opcode!(Op::Bit(1, Reg::D), "bit 1,D", None, 2, 8, 8), // 0x4A
+    opcode!(Op::Bit(1, Reg::E), "bit 1,E", None, 2, 8, 8), // 0x4B
+    opcode!(Op::Bit(1, Reg::H), "bit 1,H", None, 2, 8, 8), // 0x4C
+    opcode!(Op::Bit(1, Reg::L), "bit 1,L", None, 2, 8, 8), // 0x4D
+    opcode!(Op::Bit(1, Reg::HL), "bit 1,(HL)", None, 2, 12, 12), // 0x4E
+    opcode!(Op::Bit(1, Reg::A), "bit 1,A", None, 2, 8, 8), // 0x4F
+    opcode!(Op::Bit(2, Reg::B), "bit 2,B", None, 2, 8, 8), // 0x50
+    opcode!(Op::Bit(2, Reg::C), "bit 2,C", None, 2, 8, 8), // 0x51
+    opcode!(Op::Bit(2, Reg::D), "bit 2,D", None, 2, 8, 8), // 0x52
+    opcode!(Op::Bit(2, Reg::E), "bit 2,E", None, 2, 8, 8), // 0x53
+    opcode!(Op::Bit(2, Reg::H), "bit 2,H", None, 2, 8, 8), // 0x54
+    opcode!(Op::Bit(2, Reg::L), "bit 2,L", None, 2, 8, 8), // 0x55
+    opcode!(Op::Bit(2, Reg::HL), "bit 2,(HL)", None, 2, 12, 12), // 0x56
+    opcode!(Op::Bit(2, Reg::A), "bit 2,A", None, 2, 8, 8), // 0x57
+    opcode!(Op::Bit(3, Reg::B), "bit 3,B", None, 2, 8, 8), // 0x58
+    opcode!(Op::Bit(3, Reg::C), "bit 3,C", None, 2, 8, 8), // 0x59
+    opcode!(Op::Bit(3, Reg::D), "bit 3,D", None, 2, 8, 8), // 0x5A
+    opcode!(Op::Bit(3, Reg::E), "bit 3,E", None, 2, 8, 8), // 0x5B
+    opcode!(Op::Bit(3, Reg::H), "bit 3,H", None, 2, 8, 8), // 0x5C
+    opcode!(Op::Bit(3, Reg::L), "bit 3,L", None, 2, 8, 8), // 0x5D
+    opcode!(Op::Bit(3, Reg::HL), "bit 3,(HL)", None, 2, 12, 12), // 0x5E
+    opcode!(Op::Bit(3, Reg::A), "bit 3,A", None, 2, 8, 8), // 0x5F
+    opcode!(Op::Bit(4, Reg::B), "bit 4,B", None, 2, 8, 8), // 0x60
+    opcode!(Op::Bit(4, Reg::C), "bit 4,C", None, 2, 8, 8), // 0x61
+    opcode!(Op::Bit(4, Reg::D), "bit 4,D", None, 2, 8, 8), // 0x62
+    opcode!(Op::Bit(4, Reg::E), "bit 4,E", None, 2, 8, 8), // 0x63
+    opcode!(Op::Bit(4, Reg::H), "bit 4,H", None, 2, 8, 8), // 0x64
+    opcode!(Op::Bit(4, Reg::L), "bit 4,L", None, 2, 8, 8), // 0x65
+    opcode!(Op::Bit(4, Reg::HL), "bit 4,(HL)", None, 2, 12, 12), // 0x66
+    opcode!(Op::Bit(4, Reg::A), "bit 4,A", None, 2, 8, 8), // 0x67
+    opcode!(Op::Bit(5, Reg::B), "bit 5,B", None, 2, 8, 8), // 0x68
+    opcode!(Op::Bit(5, Reg::C), "bit 5,C", None, 2, 8, 8), // 0x69
+    opcode!(Op::Bit(5, Reg::D), "bit 5,D", None, 2, 8, 8), // 0x6A
+    opcode!(Op::Bit(5, Reg::E), "bit 5,E", None, 2, 8, 8), // 0x6B
+    opcode!(Op::Bit(5, Reg::H), "bit 5,H", None, 2, 8, 8), // 0x6C
+    opcode!(Op::Bit(5, Reg::L), "bit 5,L", None, 2, 8, 8), // 0x6D
+    opcode!(Op::Bit(5, Reg::HL), "bit 5,(HL)", None, 2, 12, 12), // 0x6E
+    opcode!(Op::Bit(5, Reg::A), "bit 5,A", None, 2, 8, 8), // 0x6F
+    opcode!(Op::Bit(6, Reg::B), "bit 6,B", None, 2, 8, 8), // 0x70
+    opcode!(Op::Bit(6, Reg::C), "bit 6,C", None, 2, 8, 8), // 0x71
+    opcode!(Op::Bit(6, Reg::D), "bit 6,D", None, 2, 8, 8), // 0x72
+    opcode!(Op::Bit(6, Reg::E), "bit 6,E", None, 2, 8, 8), // 0x73
+    opcode!(Op::Bit(6, Reg::H), "bit 6,H", None, 2, 8, 8), // 0x74
+    opcode!(Op::Bit(6, Reg::L), "bit 6,L", None, 2, 8, 8), // 0x75
+    opcode!(Op::Bit(6, Reg::HL), "bit 6,(HL)", None, 2, 12, 12), // 0x76
+    opcode!(Op::Bit(6, Reg::A), "bit 6,A", None, 2, 8, 8), // 0x77
+    opcode!(Op::Bit(7, Reg::B), "bit 7,B", None, 2, 8, 8), // 0x78
+    opcode!(Op::Bit(7, Reg::C), "bit 7,C", None, 2, 8, 8), // 0x79
+    opcode!(Op::Bit(7, Reg::D), "bit 7,D", None, 2, 8, 8), // 0x7A
+    opcode!(Op::Bit(7, Reg::E), "bit 7,E", None, 2, 8, 8), // 0x7B
+    opcode!(Op::Bit(7, Reg::H), "bit 7,H", None, 2, 8, 8), // 0x7C
+    opcode!(Op::Bit(7, Reg::L), "bit 7,L", None, 2, 8, 8), // 0x7D
+    opcode!(Op::Bit(7, Reg::HL), "bit 7,(HL)", None, 2, 12, 12), // 0x7E
+    opcode!(Op::Bit(7, Reg::A), "bit 7,A", None, 2, 8, 8), // 0x7F
+    opcode!(Op::Res(0, Reg::B), "res 0,B", None, 2, 8, 8), // 0x80
+    opcode!(Op::Res(0, Reg::C), "res 0,C", None, 2, 8, 8), // 0x81
+    opcode!(Op::Res(0, Reg::D), "res 0,D", None, 2, 8, 8), // 0x82
+    opcode!(Op::Res(0, Reg::E), "res 0,E", None, 2, 8, 8), // 0x83
+    opcode!(Op::Res(0, Reg::H), "res 0,H", None, 2, 8, 8), // 0x84
+    opcode!(Op::Res(0, Reg::L), "res 0,L", None, 2, 8, 8), // 0x85
+    opcode!(Op::Res(0, Reg::HL), "res 0,(HL)", None, 2, 16, 16), // 0x86
+    opcode!(Op::Res(0, Reg::A), "res 0,A", None, 2, 8, 8), // 0x87
+    opcode!(Op::Res(1, Reg::B), "res 1,B", None, 2, 8, 8), // 0x88
+    opcode!(Op::Res(1, Reg::C), "res 1,C", None, 2, 8, 8), // 0x89
+    opcode!(Op::Res(1, Reg::D), "res 1,D", None, 2, 8, 8), // 0x8A
+    opcode!(Op::Res(1, Reg::E), "res 1,E", None, 2, 8, 8), // 0x8B
+    opcode!(Op::Res(1, Reg::H), "res 1,H", None, 2, 8, 8), // 0x8C
+    opcode!(Op::Res(1, Reg::L), "res 1,L", None, 2, 8, 8), // 0x8D
+    opcode!(Op::Res(1, Reg::HL), "res 1,(HL)", None, 2, 16, 16), // 0x8E
+    opcode!(Op::Res(1, Reg::A), "res 1,A", None, 2, 8, 8), // 0x8F
+    opcode!(Op::Res(2, Reg::B), "res 2,B", None, 2, 8, 8), // 0x90
+    opcode!(Op::Res(2, Reg::C), "res 2,C", None, 2, 8, 8), // 0x91
+    opcode!(Op::Res(2, Reg::D), "res 2,D", None, 2, 8, 8), // 0x92
+    opcode!(Op::Res(2, Reg::E), "res 2,E", None, 2, 8, 8), // 0x93
+    opcode!(Op::Res(2, Reg::H), "res 2,H", None, 2, 8, 8), // 0x94
+    opcode!(Op::Res(2, Reg::L), "res 2,L", None, 2, 8, 8), // 0x95
+    opcode!(Op::Res(2, Reg::HL), "res 2,(HL)", None, 2, 16, 16), // 0x96
+    opcode!(Op::Res(2, Reg::A), "res 2,A", None, 2, 8, 8), // 0x97
+    opcode!(Op::Res(3, Reg::B), "res 3,B", None, 2, 8, 8), // 0x98
+    opcode!(Op::Res(3, Reg::C), "res 3,C", None, 2, 8, 8), // 0x99
+    opcode!(Op::Res(3, Reg::D), "res 3,D", None, 2, 8, 8), // 0x9A
+    opcode!(Op::Res(3, Reg::E), "res 3,E", None, 2, 8, 8), // 0x9B
+    opcode!(Op::Res(3, Reg::H), "res 3,H", None, 2, 8, 8), // 0x9C
+    opcode!(Op::Res(3, Reg::L), "res 3,L", None, 2, 8, 8), // 0x9D
+    opcode!(Op::Res(3, Reg::HL), "res 3,(HL)", None, 2, 16, 16), // 0x9E
+    opcode!(Op::Res(3, Reg::A), "res 3,A", None, 2, 8, 8), // 0x9F
+    opcode!(Op::Res(4, Reg::B), "res 4,B", None, 2, 8, 8), // 0xA0
+    opcode!(Op::Res(4, Reg::C), "res 4,C", None, 2, 8, 8), // 0xA1
+    opcode!(Op::Res(4, Reg::D), "res 4,D", None, 2, 8, 8), // 0xA2
+    opcode!(Op::Res(4, Reg::E), "res 4,E", None, 2, 8, 8), // 0xA3
+    opcode!(Op::Res(4, Reg::H), "res 4,H", None, 2, 8, 8), // 0xA4
+    opcode!(Op::Res(4, Reg::L), "res 4,L", None, 2, 8, 8), // 0xA5
+    opcode!(Op::Res(4, Reg::HL), "res 4,(HL)", None, 2, 16, 16), // 0xA6
+    opcode!(Op::Res(4, Reg::A), "res 4,A", None, 2, 8, 8), // 0xA7
+    opcode!(Op::Res(5, Reg::B), "res 5,B", None, 2, 8, 8), // 0xA8
+    opcode!(Op::Res(5, Reg::C), "res 5,C", None, 2, 8, 8), // 0xA9
+    opcode!(Op::Res(5, Reg::D), "res 5,D", None, 2, 8, 8), // 0xAA
+    opcode!(Op::Res(5, Reg::E), "res 5,E", None, 2, 8, 8), // 0xAB
+    opcode!(Op::Res(5, Reg::H), "res 5,H", None, 2, 8, 8), // 0xAC
+    opcode!(Op::Res(5, Reg::L), "res 5,L", None, 2, 8, 8), // 0xAD
+    opcode!(Op::Res(5, Reg::HL), "res 5,(HL)", None, 2, 16, 16), // 0xAE
+    opcode!(Op::Res(5, Reg::A), "res 5,A", None, 2, 8, 8), // 0xAF
+    opcode!(Op::Res(6, Reg::B), "res 6,B", None, 2, 8, 8), // 0xB0
+    opcode!(Op::Res(6, Reg::C), "res 6,C", None, 2, 8, 8), // 0xB1
+    opcode!(Op::Res(6, Reg::D), "res 6,D", None, 2, 8, 8), // 0xB2
+    opcode!(Op::Res(6, Reg::E), "res 6,E", None, 2, 8, 8), // 0xB3
+    opcode!(Op::Res(6, Reg::H), "res 6,H", None, 2, 8, 8), // 0xB4
+    opcode!(Op::Res(6, Reg::L), "res 6,L", None, 2, 8, 8), // 0xB5
+    opcode!(Op::Res(6, Reg::HL), "res 6,(HL)", None, 2, 16, 16), // 0xB6
+    opcode!(Op::Res(6, Reg::A), "res 6,A", None, 2, 8, 8), // 0xB7
+    opcode!(Op::Res(7, Reg::B), "res 7,B", None, 2, 8, 8), // 0xB8
+    opcode!(Op::Res(7, Reg::C), "res 7,C", None, 2, 8, 8), // 0xB9
+    opcode!(Op::Res(7, Reg::D), "res 7,D", None, 2, 8, 8), // 0xBA
+    opcode!(Op::Res(7, Reg::E), "res 7,E", None, 2, 8, 8), // 0xBB
+    opcode!(Op::Res(7, Reg::H), "res 7,H", None, 2, 8, 8), // 0xBC
+    opcode!(Op::Res(7, Reg::L), "res 7,L", None, 2, 8, 8), // 0xBD
+    opcode!(Op::Res(7, Reg::HL), "res 7,(HL)", None, 2, 16, 16), // 0xBE
+    opcode!(Op::Res(7, Reg::A), "res 7,A", None, 2, 8, 8), // 0xBF
+    opcode!(Op::Set(0, Reg::B), "set 0,B", None, 2, 8, 8), // 0xC0
+    opcode!(Op::Set(0, Reg::C), "set 0,C", None, 2, 8, 8), // 0xC1
+    opcode!(Op::Set(0, Reg::D), "set 0,D", None, 2, 8, 8), // 0xC2
+    opcode!(Op::Set(0, Reg::E), "set 0,E", None, 2, 8, 8), // 0xC3
+    opcode!(Op::Set(0, Reg::H), "set 0,H", None, 2, 8, 8), // 0xC4
+    opcode!(Op::Set(0, Reg::L), "set 0,L", None, 2, 8, 8), // 0xC5
+    opcode!(Op::Set(0, Reg::HL), "set 0,(HL)", None, 2, 16, 16), // 0xC6
+    opcode!(Op::Set(0, Reg::A), "set 0,A", None, 2, 8, 8), // 0xC7
+    opcode!(Op::Set(1, Reg::B), "set 1,B", None, 2, 8, 8), // 0xC8
+    opcode!(Op::Set(1, Reg::C), "set 1,C", None, 2, 8, 8), // 0xC9
+    opcode!(Op::Set(1, Reg::D), "set 1,D", None, 2, 8, 8), // 0xCA
+    opcode!(Op::Set(1, Reg::E), "set 1,E", None, 2, 8, 8), // 0xCB
+    opcode!(Op::Set(1, Reg::H), "set 1,H", None, 2, 8, 8), // 0xCC
+    opcode!(Op::Set(1, Reg::L), "set 1,L", None, 2, 8, 8), // 0xCD
+    opcode!(Op::Set(1, Reg::HL), "set 1,(HL)", None, 2, 16, 16), // 0xCE
+    opcode!(Op::Set(1, Reg::A), "set 1,A", None, 2, 8, 8), // 0xCF
+    opcode!(Op::Set(2, Reg::B), "set 2,B", None, 2, 8, 8), // 0xD0
+    opcode!(Op::Set(2, Reg::C), "set 2,C", None, 2, 8, 8), // 0xD1
+    opcode!(Op::Set(2, Reg::D), "set 2,D", None, 2, 8, 8), // 0xD2
+    opcode!(Op::Set(2, Reg::E), "set 2,E", None, 2, 8, 8), // 0xD3
+    opcode!(Op::Set(2, Reg::H), "set 2,H", None, 2, 8, 8), // 0xD4
+    opcode!(Op::Set(2, Reg::L), "set 2,L", None, 2, 8, 8), // 0xD5
+    opcode!(Op::Set(2, Reg::HL), "set 2,(HL)", None, 2, 16, 16), // 0xD6
+    opcode!(Op::Set(2, Reg::A), "set 2,A", None, 2, 8, 8), // 0xD7
+    opcode!(Op::Set(3, Reg::B), "set 3,B", None, 2, 8, 8), // 0xD8
+    opcode!(Op::Set(3, Reg::C), "set 3,C", None, 2, 8, 8), // 0xD9
+    opcode!(Op::Set(3, Reg::D), "set 3,D", None, 2, 8, 8), // 0xDA
+    opcode!(Op::Set(3, Reg::E), "set 3,E", None, 2, 8, 8), // 0xDB
+    opcode!(Op::Set(3, Reg::H), "set 3,H", None, 2, 8, 8), // 0xDC
+    opcode!(Op::Set(3, Reg::L), "set 3,L", None, 2, 8, 8), // 0xDD
+    opcode!(Op::Set(3, Reg::HL), "set 3,(HL)", None, 2, 16, 16), // 0xDE
+    opcode!(Op::Set(3, Reg::A), "set 3,A", None, 2, 8, 8), // 0xDF
+    opcode!(Op::Set(4, Reg::B), "set 4,B", None, 2, 8, 8), // 0xE0
+    opcode!(Op::Set(4, Reg::C), "set 4,C", None, 2, 8, 8), // 0xE1
+    opcode!(Op::Set(4, Reg::D), "set 4,D", None, 2, 8, 8), // 0xE2
+    opcode!(Op::Set(4, Reg::E), "set 4,E", None, 2, 8, 8), // 0xE3
+    opcode!(Op::Set(4, Reg::H), "set 4,H", None, 2, 8, 8), // 0xE4
+    opcode!(Op::Set(4, Reg::L), "set 4,L", None, 2, 8, 8), // 0xE5
+    opcode!(Op::Set(4, Reg::HL), "set 4,(HL)", None, 2, 16, 16), // 0xE6
+    opcode!(Op::Set(4, Reg::A), "set 4,A", None, 2, 8, 8), // 0xE7
+    opcode!(Op::Set(5, Reg::B), "set 5,B", None, 2, 8, 8), // 0xE8
+    opcode!(Op::Set(5, Reg::C), "set 5,C", None, 2, 8, 8), // 0xE9
+    opcode!(Op::Set(5, Reg::D), "set 5,D", None, 2, 8, 8), // 0xEA
+    opcode!(Op::Set(5, Reg::E), "set 5,E", None, 2, 8, 8), // 0xEB
+    opcode!(Op::Set(5, Reg::H), "set 5,H", None, 2, 8, 8), // 0xEC
+    opcode!(Op::Set(5, Reg::L), "set 5,L", None, 2, 8, 8), // 0xED
+    opcode!(Op::Set(5, Reg::HL), "set 5,(HL)", None, 2, 16, 16), // 0xEE
+    opcode!(Op::Set(5, Reg::A), "set 5,A", None, 2, 8, 8), // 0xEF
+    opcode!(Op::Set(6, Reg::B), "set 6,B", None, 2, 8, 8), // 0xF0
+    opcode!(Op::Set(6, Reg::C), "set 6,C", None, 2, 8, 8), // 0xF1
+    opcode!(Op::Set(6, Reg::D), "set 6,D", None, 2, 8, 8), // 0xF2
+    opcode!(Op::Set(6, Reg::E), "set 6,E", None, 2, 8, 8), // 0xF3
+    opcode!(Op::Set(6, Reg::H), "set 6,H", None, 2, 8, 8), // 0xF4
+    opcode!(Op::Set(6, Reg::L), "set 6,L", None, 2, 8, 8), // 0xF5
+    opcode!(Op::Set(6, Reg::HL), "set 6,(HL)", None, 2, 16, 16), // 0xF6
+    opcode!(Op::Set(6, Reg::A), "set 6,A", None, 2, 8, 8), // 0xF7
+    opcode!(Op::Set(7, Reg::B), "set 7,B", None, 2, 8, 8), // 0xF8
+    opcode!(Op::Set(7, Reg::C), "set 7,C", None, 2, 8, 8), // 0xF9
+    opcode!(Op::Set(7, Reg::D), "set 7,D", None, 2, 8, 8), // 0xFA
+    opcode!(Op::Set(7, Reg::E), "set 7,E", None, 2, 8, 8), // 0xFB
+    opcode!(Op::Set(7, Reg::H), "set 7,H", None, 2, 8, 8), // 0xFC
+    opcode!(Op::Set(7, Reg::L), "set 7,L", None, 2, 8, 8), // 0xFD
+    opcode!(Op::Set(7, Reg::HL), "set 7,(HL)", None, 2, 16, 16), // 0xFE
+    opcode!(Op::Set(7, Reg::A), "set 7,A", None, 2, 8, 8), // 0xFF
+];
@@ -0,0 +1,61 @@
+use super::super::mem::bus::Bus;
+use cpu::{interrupt, consts};
+
+// SB and SC. There's no link cable, so a transfer started with the internal
+// clock shifts in 0xFF (no other Game Boy connected) and one started with the
+// external clock never finishes.
+pub struct Serial {
+    data: u8,
+    control: u8,
+    transfer_cycles: u32, // cycles left in the current transfer.
+}
+
+impl Default for Serial {
+    fn default() -> Serial {
+        Serial {
+            data: 0,
+            control: 0,
+            transfer_cycles: 0,
+        }
+    }
+}
+
+impl Serial {
+    pub fn update<B: Bus>(&mut self, cycles: u32, memory: &mut B) {
+        if self.transfer_cycles == 0 {
+            return;
+        }
+        if self.transfer_cycles > cycles {
+            self.transfer_cycles -= cycles;
+        } else {
+            self.transfer_cycles = 0;
+            self.data = 0xFF;
+            self.control &= 0b0111_1111;
+            interrupt::request(interrupt::Interrupt::SerialIO, memory);
+        }
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            consts::SB_REGISTER_ADDR => self.data,
+            consts::SC_REGISTER_ADDR => self.control | 0b0111_1110,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            consts::SB_REGISTER_ADDR => self.data = value,
+            consts::SC_REGISTER_ADDR => {
+                self.control = value & 0b1000_0001;
+                self.transfer_cycles = if self.control == 0b1000_0001 {
+                    // 8 bits at 8192Hz.
+                    8 * (consts::CPU_FREQUENCY_HZ / consts::SERIAL_CLOCK_HZ)
+                } else {
+                    0
+                };
+            }
+            _ => unreachable!(),
+        }
+    }
+}
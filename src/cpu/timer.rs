@@ -1,104 +1,222 @@
-use super::super::mem::mem;
+use super::super::mem::bus::Bus;
 use cpu::{interrupt, consts};
 use std::fmt;
 
-struct Event {
-    cycles_counter: u32,
-    cycles_rate: u32, // rate at which the event should happen
+// DIV, TIMA, TMA and TAC. DIV is just the upper byte of a 16 bit counter that
+// increments every cycle; TIMA increments whenever the counter bit selected by
+// TAC (ANDed with the timer enable bit) goes from 1 to 0. That's why writes to
+// DIV or TAC can increment TIMA too.
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    // TIMA reads 0 for one M-cycle after overflowing; only then is it reloaded
+    // with TMA and the interrupt requested.
+    overflowed: bool,
+    // TIMA was reloaded in the current M-cycle, so writes to it are ignored.
+    reloading: bool,
 }
 
-impl fmt::Display for Event {
+impl Default for Timer {
+    fn default() -> Timer {
+        Timer {
+            counter: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            overflowed: false,
+            reloading: false,
+        }
+    }
+}
+
+impl fmt::Display for Timer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f,
-               "cycles counter: {}\
-               \ncycles rate: {}",
-               self.cycles_counter,
-               self.cycles_rate)
+               "counter: {:#06x}\
+               \nDIV: {:#x} TIMA: {:#x} TMA: {:#x} TAC: {:#b}\
+               \noverflowed: {}",
+               self.counter,
+               self.counter >> 8,
+               self.tima,
+               self.tma,
+               self.tac,
+               self.overflowed)
     }
 }
 
-impl Event {
-    pub fn new(cycles_rate: u32) -> Event {
-        Event {
-            cycles_counter: 0,
-            cycles_rate: cycles_rate,
+impl Timer {
+    // the counter bit whose falling edge increments TIMA.
+    fn input_bit(&self) -> u8 {
+        match self.tac & 0b11 {
+            0b00 => 9, // 4096Hz
+            0b01 => 3, // 262144Hz
+            0b10 => 5, // 65536Hz
+            0b11 => 7, // 16384Hz
+            _ => unreachable!(),
         }
     }
 
-    // return true if event happened.
-    pub fn update(&mut self, cycles: u32) -> bool {
-        self.cycles_counter += cycles;
-        if self.cycles_counter >= self.cycles_rate {
-            self.cycles_counter = 0;
-            true
+    // what TIMA's increment circuit sees: the selected bit, if the timer is enabled.
+    fn signal(&self) -> bool {
+        let enabled: bool = (self.tac >> 2) & 0b1 == 0b1;
+        enabled && (self.counter >> self.input_bit()) & 0b1 == 0b1
+    }
+
+    fn increment_tima(&mut self) {
+        if self.tima == 0xFF {
+            self.tima = 0;
+            self.overflowed = true;
         } else {
-            false
+            self.tima += 1;
         }
     }
-}
 
-pub struct Timer {
-    div_event: Event,
-    tima_event: Event,
-}
+    // 'cycles' is always a whole number of M-cycles.
+    pub fn update<B: Bus>(&mut self, cycles: u32, memory: &mut B) {
+        for _ in 0..cycles / 4 {
+            self.reloading = false;
+            if self.overflowed {
+                self.overflowed = false;
+                self.reloading = true;
+                self.tima = self.tma;
+                interrupt::request(interrupt::Interrupt::TimerOverflow, memory);
+            }
 
-impl Default for Timer {
-    fn default() -> Timer {
-        Timer {
-            div_event: Event::new(consts::DIV_REGISTER_UPDATE_RATE_CYCLES),
-            tima_event: Event::new(0),
+            let signal: bool = self.signal();
+            self.counter = self.counter.wrapping_add(4);
+            if signal && !self.signal() {
+                self.increment_tima();
+            }
         }
     }
-}
 
-impl Timer {
-    pub fn update(&mut self, cycles: u32, memory: &mut mem::Memory) {
-        if self.div_event.update(cycles) {
-            let div: u8 = memory.read_byte(consts::DIV_REGISTER_ADDR);
-            memory.write_byte(consts::DIV_REGISTER_ADDR, div.wrapping_add(1));
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            consts::DIV_REGISTER_ADDR => (self.counter >> 8) as u8,
+            consts::TIMA_REGISTER_ADDR => self.tima,
+            consts::TMA_REGISTER_ADDR => self.tma,
+            consts::TAC_REGISTER_ADDR => self.tac | 0b1111_1000,
+            _ => unreachable!(),
         }
+    }
 
-        if !timer_stop(memory) {
-            self.tima_event.cycles_rate = cycles_from_hz(input_clock(memory));
-            if self.tima_event.update(cycles) {
-                let mut tima: u8 = memory.read_byte(consts::TIMA_REGISTER_ADDR);
-                if tima == 0xFF {
-                    // overflows
-                    tima = memory.read_byte(consts::TMA_REGISTER_ADDR);
-                    interrupt::request(interrupt::Interrupt::TimerOverflow, memory);
-                } else {
-                    tima += 1;
+    pub fn write(&mut self, address: u16, value: u8) {
+        let signal: bool = self.signal();
+        match address {
+            consts::DIV_REGISTER_ADDR => {
+                // any write resets the whole counter.
+                self.counter = 0;
+            }
+            consts::TIMA_REGISTER_ADDR => {
+                if !self.reloading {
+                    // writing during the overflow cycle cancels the reload.
+                    self.tima = value;
+                    self.overflowed = false;
+                }
+            }
+            consts::TMA_REGISTER_ADDR => {
+                self.tma = value;
+                if self.reloading {
+                    self.tima = value;
                 }
-                memory.write_byte(consts::TIMA_REGISTER_ADDR, tima);
             }
+            consts::TAC_REGISTER_ADDR => {
+                self.tac = value & 0b111;
+            }
+            _ => unreachable!(),
+        }
+        if signal && !self.signal() {
+            self.increment_tima();
         }
     }
+}
 
-    pub fn events_to_str(&self) -> String {
-        let line = "---------------------\n";
-        let div = format!("DIV #########\n{}\n", self.div_event);
-        let tima = format!("TIMA #########\n{}\n", self.tima_event);
+#[cfg(test)]
+mod tests {
+    use super::Timer;
+    use cpu::consts;
+    use mem::bus::Bus;
+    use sm83::FlatRam;
 
-        (div + line + &tima).to_owned()
+    const TIMER_INTERRUPT: u8 = 0b100;
+
+    // enabled, TIMA increments on the falling edge of counter bit 3, every 16 cycles.
+    fn fast_timer() -> Timer {
+        let mut timer: Timer = Timer::default();
+        timer.write(consts::TAC_REGISTER_ADDR, 0b101);
+        timer
     }
-}
 
-#[inline]
-pub fn timer_stop(memory: &mem::Memory) -> bool {
-    (memory.read_byte(consts::TAC_REGISTER_ADDR) >> 2) & 0b1 == 0b0
-}
+    #[test]
+    fn overflow_reloads_tma_one_m_cycle_later() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut timer: Timer = fast_timer();
+        timer.write(consts::TMA_REGISTER_ADDR, 0x42);
+        timer.write(consts::TIMA_REGISTER_ADDR, 0xFF);
 
-pub fn input_clock(memory: &mem::Memory) -> u32 {
-    match memory.read_byte(consts::TAC_REGISTER_ADDR) & 0b11 {
-        0b00 => 4096,
-        0b01 => 262144,
-        0b10 => 65536,
-        0b11 => 16384,
-        _ => unreachable!(),
+        timer.update(16, &mut ram);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 0x00);
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR) & TIMER_INTERRUPT, 0);
+
+        timer.update(4, &mut ram);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 0x42);
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR) & TIMER_INTERRUPT,
+                   TIMER_INTERRUPT);
     }
-}
 
-#[inline]
-pub fn cycles_from_hz(rate_hz: u32) -> u32 {
-    consts::CPU_FREQUENCY_HZ / rate_hz
+    #[test]
+    fn tima_write_while_reloading_is_ignored() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut timer: Timer = fast_timer();
+        timer.write(consts::TMA_REGISTER_ADDR, 0x42);
+        timer.write(consts::TIMA_REGISTER_ADDR, 0xFF);
+        timer.update(20, &mut ram);
+
+        timer.write(consts::TIMA_REGISTER_ADDR, 0x99);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 0x42);
+
+        // one M-cycle later TIMA is writable again.
+        timer.update(4, &mut ram);
+        timer.write(consts::TIMA_REGISTER_ADDR, 0x99);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 0x99);
+    }
+
+    #[test]
+    fn tima_write_during_overflow_cancels_reload() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut timer: Timer = fast_timer();
+        timer.write(consts::TMA_REGISTER_ADDR, 0x42);
+        timer.write(consts::TIMA_REGISTER_ADDR, 0xFF);
+        timer.update(16, &mut ram);
+
+        timer.write(consts::TIMA_REGISTER_ADDR, 0x10);
+        timer.update(4, &mut ram);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 0x10);
+        assert_eq!(ram.read_byte(consts::IF_REGISTER_ADDR) & TIMER_INTERRUPT, 0);
+    }
+
+    #[test]
+    fn div_reset_on_falling_edge_increments_tima() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut timer: Timer = fast_timer();
+        // counter = 8: bit 3 is set.
+        timer.update(8, &mut ram);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 0);
+
+        timer.write(consts::DIV_REGISTER_ADDR, 0x12);
+        assert_eq!(timer.read(consts::DIV_REGISTER_ADDR), 0);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 1);
+    }
+
+    #[test]
+    fn div_reset_with_the_bit_clear_does_nothing() {
+        let mut ram: FlatRam = FlatRam::default();
+        let mut timer: Timer = fast_timer();
+        // counter = 4: bit 3 is clear.
+        timer.update(4, &mut ram);
+        timer.write(consts::DIV_REGISTER_ADDR, 0);
+        assert_eq!(timer.read(consts::TIMA_REGISTER_ADDR), 0);
+    }
 }
@@ -0,0 +1,58 @@
+use sdl2;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{Renderer, Texture};
+
+// the texture format of PixelFormat::Rgba8888 pixels: ABGR8888 is R, G, B, A in
+// memory on little endian machines.
+pub const RGBA_TEXTURE_FORMAT: PixelFormatEnum = PixelFormatEnum::ABGR8888;
+
+// A window next to the game one showing an RGBA picture, scaled up.
+pub struct DebugWindow {
+    renderer: Renderer<'static>,
+    texture: Texture,
+    width: usize,
+    scale: u32,
+}
+
+impl DebugWindow {
+    pub fn new(video: &sdl2::VideoSubsystem,
+               title: &str,
+               width: usize,
+               height: usize,
+               scale: u32)
+               -> DebugWindow {
+        let window = video.window(title, width as u32 * scale, height as u32 * scale)
+                          .build()
+                          .unwrap();
+        let renderer = window.renderer().build().unwrap();
+        let texture = renderer.create_texture_streaming(RGBA_TEXTURE_FORMAT,
+                                                        (width as u32, height as u32))
+                              .unwrap();
+        DebugWindow {
+            renderer: renderer,
+            texture: texture,
+            width: width,
+            scale: scale,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.renderer.window().unwrap().id()
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.renderer.window_mut().unwrap().set_title(title);
+    }
+
+    // window coordinates to picture coordinates.
+    pub fn to_picture(&self, x: i32, y: i32) -> (usize, usize) {
+        (x.max(0) as usize / self.scale as usize, y.max(0) as usize / self.scale as usize)
+    }
+
+    pub fn show(&mut self, rgba: &[u8]) {
+        self.texture.update(None, rgba, self.width * 4).unwrap();
+        self.renderer.clear();
+        self.renderer.copy(&self.texture, None, None);
+        self.renderer.present();
+    }
+}
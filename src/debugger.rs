@@ -1,8 +1,12 @@
 use cpu;
 use cpu::cpu::{Cpu, Reg, Instruction};
-use cpu::timer::Timer;
+use cpu::opcodes;
+use cpu::opcodes::{Opcode, Operand};
 use mem;
 use mem::mem::Memory;
+use graphics;
+use graphics::graphics::{Registers, Sprite};
+use system::System;
 use std::io::{self, Write};
 
 struct BreakCommand {
@@ -29,13 +33,13 @@ impl Default for BreakCommand {
 
 impl BreakCommand {
     // true if should go to read loop;
-    fn run(&mut self, instruction: &Instruction, cpu: &Cpu, mem: &Memory) -> bool {
+    fn run(&mut self, instruction: &Instruction, cpu: &Cpu, system: &System) -> bool {
         let go_to_read_loop: bool;
         if let Some(addr) = self.break_addr {
             go_to_read_loop = instruction.address == addr;
             Debugger::print_cpu_human(self.break_debug, instruction, cpu);
         } else if let Some(ioreg) = self.break_ioreg {
-            go_to_read_loop = mem.read_byte(ioreg) as u16 == self.break_reg_value;
+            go_to_read_loop = system.read_byte(ioreg) as u16 == self.break_reg_value;
             Debugger::print_cpu_human(self.break_debug, instruction, cpu);
         } else if let Some(reg) = self.break_reg {
             go_to_read_loop = cpu.reg16(reg) == self.break_reg_value;
@@ -187,12 +191,12 @@ impl Default for Debugger {
 }
 
 impl Debugger {
-    pub fn run(&mut self, instruction: &Instruction, cpu: &Cpu, mem: &Memory, timer: &Timer) {
+    pub fn run(&mut self, instruction: &Instruction, cpu: &Cpu, system: &System) {
         if self.display_header {
             println!("##################################");
             println!("#     Gebemula Debug Console     #");
             println!("##################################");
-            self.display_info(mem);
+            self.display_info(&system.mem);
             println!("Type 'help' for the command list.");
             println!("----------------------------------");
             self.display_header = false;
@@ -201,7 +205,7 @@ impl Debugger {
             Debugger::print_cpu_human(self.run_debug.unwrap(), instruction, cpu);
             return;
         }
-        let mut go_to_loop: bool = self.break_command.run(instruction, cpu, mem);
+        let mut go_to_loop: bool = self.break_command.run(instruction, cpu, system);
         if go_to_loop && self.num_steps > 0 {
             self.num_steps -= 1;
             Debugger::print_cpu_human(self.steps_debug, instruction, cpu);
@@ -212,7 +216,7 @@ impl Debugger {
             }
         };
         if go_to_loop {
-            self.read_loop(instruction, cpu, mem, timer);
+            self.read_loop(instruction, cpu, system);
         }
     }
     pub fn display_info(&self, mem: &Memory) {
@@ -220,7 +224,7 @@ impl Debugger {
         println!("Cartridge Type: {}",
                  mem::cartridge::cartridge_type_str(mem));
     }
-    fn read_loop(&mut self, instruction: &Instruction, cpu: &Cpu, mem: &Memory, timer: &Timer) {
+    fn read_loop(&mut self, instruction: &Instruction, cpu: &Cpu, system: &System) {
         loop {
             self.should_run_cpu = false;
             print!("gdc> "); //gdc: gebemula debugger console
@@ -229,7 +233,7 @@ impl Debugger {
             match io::stdin().read_line(&mut input) {
                 Ok(_) => {
                     input.pop(); //removes the '\n'.
-                    self.parse(&input, instruction, cpu, mem, timer);
+                    self.parse(&input, instruction, cpu, system);
                 }
                 Err(error) => println!("error: {}", error),
             }
@@ -260,8 +264,7 @@ impl Debugger {
              command: &str,
              instruction: &Instruction,
              cpu: &Cpu,
-             mem: &Memory,
-             timer: &Timer) {
+             system: &System) {
         let aux: &mut Vec<&str> = &mut command.trim().split(' ').collect();
         let mut words: Vec<&str> = Vec::new();
         for w in aux.iter().filter(|x| *x.to_owned() != "") {
@@ -271,7 +274,7 @@ impl Debugger {
         if !words.is_empty() {
             match words[0] {
                 "show" => {
-                    Debugger::parse_show(&words[1..], cpu, mem, timer);
+                    Debugger::parse_show(&words[1..], cpu, system);
                     self.should_run_cpu = false;
                 }
                 "step" => {
@@ -292,7 +295,7 @@ impl Debugger {
                     self.parse_run(&words[1..]);
                 }
                 "info" => {
-                    self.display_info(mem);
+                    self.display_info(&system.mem);
                 }
                 "" => {
                     // does nothing
@@ -308,8 +311,9 @@ impl Debugger {
         if error_msg != "" {
             println!("***ERROR: {}", error_msg);
         }
-        println!("- show [cpu|ioregs|events|memory [<min_addr_hex> <max_addr_hex>]\n\tShow state \
-                  of component.");
+        println!("- show [cpu|ioregs|timer|memory [<min_addr_hex> <max_addr_hex>]|oam [<line>]]\
+                  \n\tShow state of component.\n\tWith a line, 'oam' marks the sprites drawn \
+                  on it.");
         println!("- step [decimal] [cpu|human]\n\tRun instruction pointed by PC and print \
                   it.\n\tIf a number is set, run step num times and print the last one.\n\tIf a \
                   number is set and cpu or human or both, then it will print all the \
@@ -403,7 +407,7 @@ impl Debugger {
         Some(res)
     }
 
-    fn parse_show(parameters: &[&str], cpu: &Cpu, mem: &Memory, timer: &Timer) {
+    fn parse_show(parameters: &[&str], cpu: &Cpu, system: &System) {
         if parameters.is_empty() {
             Debugger::display_help("Invalid number of parameters for 'show'.");
             return;
@@ -413,21 +417,21 @@ impl Debugger {
                 println!("{}", cpu);
             }
             "ioregs" => {
-                let tima: u8 = mem.read_byte(cpu::consts::TIMA_REGISTER_ADDR);
-                let tma: u8 = mem.read_byte(cpu::consts::TMA_REGISTER_ADDR);
-                let tac: u8 = mem.read_byte(cpu::consts::TAC_REGISTER_ADDR);
-                let div: u8 = mem.read_byte(cpu::consts::DIV_REGISTER_ADDR);
-                let if_: u8 = mem.read_byte(cpu::consts::IF_REGISTER_ADDR);
-                let ie: u8 = mem.read_byte(cpu::consts::IE_REGISTER_ADDR);
-                let ly: u8 = mem.read_byte(cpu::consts::LY_REGISTER_ADDR);
-                let lcdc: u8 = mem.read_byte(cpu::consts::LCDC_REGISTER_ADDR);
-                let scx: u8 = mem.read_byte(cpu::consts::SCX_REGISTER_ADDR);
-                let scy: u8 = mem.read_byte(cpu::consts::SCY_REGISTER_ADDR);
-                let stat: u8 = mem.read_byte(cpu::consts::STAT_REGISTER_ADDR);
-                let lyc: u8 = mem.read_byte(cpu::consts::LYC_REGISTER_ADDR);
-                let wx: u8 = mem.read_byte(cpu::consts::WX_REGISTER_ADDR);
-                let wy: u8 = mem.read_byte(cpu::consts::WY_REGISTER_ADDR);
-                let p1: u8 = mem.read_byte(cpu::consts::JOYPAD_REGISTER_ADDR);
+                let tima: u8 = system.read_byte(cpu::consts::TIMA_REGISTER_ADDR);
+                let tma: u8 = system.read_byte(cpu::consts::TMA_REGISTER_ADDR);
+                let tac: u8 = system.read_byte(cpu::consts::TAC_REGISTER_ADDR);
+                let div: u8 = system.read_byte(cpu::consts::DIV_REGISTER_ADDR);
+                let if_: u8 = system.read_byte(cpu::consts::IF_REGISTER_ADDR);
+                let ie: u8 = system.read_byte(cpu::consts::IE_REGISTER_ADDR);
+                let ly: u8 = system.read_byte(cpu::consts::LY_REGISTER_ADDR);
+                let lcdc: u8 = system.read_byte(cpu::consts::LCDC_REGISTER_ADDR);
+                let scx: u8 = system.read_byte(cpu::consts::SCX_REGISTER_ADDR);
+                let scy: u8 = system.read_byte(cpu::consts::SCY_REGISTER_ADDR);
+                let stat: u8 = system.read_byte(cpu::consts::STAT_REGISTER_ADDR);
+                let lyc: u8 = system.read_byte(cpu::consts::LYC_REGISTER_ADDR);
+                let wx: u8 = system.read_byte(cpu::consts::WX_REGISTER_ADDR);
+                let wy: u8 = system.read_byte(cpu::consts::WY_REGISTER_ADDR);
+                let p1: u8 = system.read_byte(cpu::consts::JOYPAD_REGISTER_ADDR);
 
                 println!("IF: {:#b}", if_);
                 println!("IE: {:#b}", ie);
@@ -446,10 +450,13 @@ impl Debugger {
                 println!("Joypad: {:#b}", p1);
             }
             "memory" => {
-                Debugger::parse_show_memory(&parameters[1..], mem);
+                Debugger::parse_show_memory(&parameters[1..], system);
             }
-            "events" => {
-                println!("{}", timer.events_to_str());
+            "timer" => {
+                println!("{}", system.timer);
+            }
+            "oam" => {
+                Debugger::parse_show_oam(&parameters[1..], system);
             }
             _ => {
                 Debugger::display_help(&format!("Invalid parameter for 'show': {}\n",
@@ -458,12 +465,39 @@ impl Debugger {
         }
     }
 
-    fn parse_show_memory(parameters: &[&str], mem: &Memory) {
+    // every OAM entry; with a line, the ones drawn on it are marked with '*'.
+    fn parse_show_oam(parameters: &[&str], system: &System) {
+        let regs: &Registers = system.graphics.registers();
+        let selected: Vec<Sprite> = match parameters.first() {
+            Some(line) => {
+                match line.parse::<u8>() {
+                    Ok(line) => graphics::graphics::select_sprites(&system.mem, regs, line),
+                    Err(_) => {
+                        Debugger::display_help(&format!("Invalid line for 'show oam': {}",
+                                                        line));
+                        return;
+                    }
+                }
+            }
+            None => Vec::new(),
+        };
+        println!("8x16 sprites: {}", regs.is_sprite_8_16_on());
+        for index in 0..graphics::consts::NUM_SPRITES {
+            let mark: &str = if selected.iter().any(|sprite| sprite.index == index) {
+                "*"
+            } else {
+                " "
+            };
+            println!("{}{}", mark, Sprite::from_oam(&system.mem, index));
+        }
+    }
+
+    fn parse_show_memory(parameters: &[&str], system: &System) {
         if parameters.len() == 2 {
             let min_addr = Debugger::hex_from_str(parameters[0]);
             let max_addr = Debugger::hex_from_str(parameters[1]);
             if min_addr.is_some() && max_addr.is_some() {
-                println!("{}", mem.format(min_addr, max_addr));
+                println!("{}", system.format(min_addr, max_addr));
             }
         } else {
             Debugger::display_help("Invalid number of arguments for 'show memory'\n");
@@ -485,438 +519,29 @@ impl Debugger {
 }
 
 pub fn instr_to_human(instruction: &Instruction) -> String {
-    if let Some(_) = instruction.prefix {
-        // CB-prefixed instructions
-        let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-        let mut r = format!("{:?}", reg);
-        if reg == Reg::HL {
-            r = "(HL)".to_owned();
-        }
-        let bit: u8 = instruction.opcode >> 3 & 0b111;
-        match instruction.opcode {
-            0x00...0x07 => format!("rlc {}", r),
-            0x08...0x0F => format!("rrc {}", r),
-            0x10...0x17 => {
-                // RL m
-                format!("rl {}", r)
-            }
-            0x18...0x1F => {
-                // RR m
-                format!("rr {}", r)
-            }
-            0x20...0x27 => format!("sla {}", r),
-            0x28...0x2F => {
-                // SRA n
-                format!("sra {}", r)
-            }
-            0x30...0x37 => {
-                // SWAP n
-                format!("swap {}", r)
-            }
-            0x38...0x3F => {
-                // SRL n
-                format!("srl {}", r)
-            }
-            0x40...0x7F => {
-                // BIT b,r; BIT b,(HL)
-                format!("bit {},{}", bit, r)
-            }
-            0x80...0xBF => {
-                // RES b,r; RES b,(HL)
-                format!("res {},{}", bit, r)
-            }
-            0xC0...0xFF => {
-                // SET b,r; SET b,(HL)
-                format!("set {},{}", bit, r)
-            }
-            _ => unreachable!(),
-        }
+    let opcode: &Opcode = if instruction.prefix.is_some() {
+        &opcodes::CB_OPCODES[instruction.opcode as usize]
     } else {
-        match instruction.opcode {
-            /***************************************/
-            /*      Misc/Control instructions      */
-            /***************************************/
-            0x0 => {
-                //NOP
-                "nop".to_owned()
-            },
-            0x10 => {
-                //STOP
-                "stop".to_owned()
-            },
-            0x76 => {
-                //HALT
-                "halt".to_owned()
-            },
-            0xF3 => {
-                //DI
-                "di".to_owned()
-            },
-            0xFB => {
-                //EI
-                "ei".to_owned()
-            },
-            /**************************************/
-            /*      8 bit rotations/shifts        */
-            /**************************************/
-            0x07 => {
-                "RLCA".to_owned()
-            },
-            0x17 => {
-                "RLA".to_owned()
-            },
-            0x0F => {
-                "RRCA".to_owned()
-            },
-            0x1F => {
-                "RRA".to_owned()
-            },
-            /**************************************/
-            /* 8 bit load/store/move instructions */
-            /**************************************/
-            0x02 | 0x12 => {
-                //LD (rr),A;
-                let reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                format!("ld ({:?}),A", reg)
-            },
-            0x22 => {
-                //LD (HL+),A
-                "ld (HL+),A".to_owned()
-            },
-            0x32 => {
-                //LD (HL-),A
-                "ld (HL-),A".to_owned()
-            },
-            0x0A | 0x1A => {
-                //LD A,(rr);
-                let reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                format!("ld ({:?}),A", reg)
-            },
-            0x2A => {
-                //LD A,(HL+);
-               "ld A,(HL+)".to_owned()
-            },
-            0x3A => {
-                //LD A,(HL-)
-               "ld A,(HL-)".to_owned()
-            },
-            0x06 | 0x16 | 0x26 |
-            0x0E | 0x1E | 0x2E |
-            0x3E | 0x36 => {
-                //LD r,n; LD (HL),n
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode >> 3);
-                let mut r = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    r = "(HL)".to_owned();
-                }
-                format!("ld {},{:#x}", r, instruction.imm8.unwrap())
-            },
-            0x40 ... 0x6F | 0x70 ... 0x75 |
-            0x77 ... 0x7F => {
-                //LD r,r; LD r,(HL); LD (HL),r
-                let reg_rhs: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let reg_lhs: Reg = Reg::pair_from_ddd(instruction.opcode >> 3);
-
-                let r: String;
-                let l: String;
-                if reg_rhs == Reg::HL {
-                    r = "(HL)".to_owned();
-                } else {
-                    r = format!("{:?}", reg_rhs);
-                }
-                if reg_lhs == Reg::HL {
-                    l = "(HL)".to_owned();
-                } else {
-                    l = format!("{:?}", reg_lhs);
-                }
-
-                format!("ld {},{}", l, r)
-            },
-            0xE0 => {
-                //LDH (n),A
-                format!("ldh ({:#x}),A", instruction.imm8.unwrap())
-            },
-            0xF0 => {
-                //LDH A,(n)
-                format!("ldh A,({:#x})", instruction.imm8.unwrap())
-            },
-            0xE2 => {
-                //LD (C),A
-               "ld (0xff00+C), A".to_owned()
-            },
-            0xF2 => {
-                //LD A,(C)
-               "ld A,(0xff00+C)".to_owned()
-            },
-            0xEA => {
-                //LD (nn),A
-                format!("ld ({:#x}),A", instruction.imm16.unwrap())
-            },
-            0xFA => {
-                //LD A,(nn)
-                format!("ld A,({:#x})", instruction.imm16.unwrap())
-            },
-            /***************************************/
-            /* 16 bit load/store/move instructions */
-            /***************************************/
-            0x01 | 0x11 | 0x21 | 0x31 => {
-                //LD rr,nn
-                let reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                format!("ld {:?},{:#x}", reg, instruction.imm16.unwrap())
-            },
-            0x08 => {
-                //LD (nn), SP
-                format!("ld ({:#x}),SP", instruction.imm16.unwrap())
-            },
-            0xC1 | 0xD1 | 0xE1 | 0xF1 => {
-                //POP rr
-                let mut reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                if reg == Reg::SP {
-                    reg = Reg::AF;
-                }
-                format!("pop {:?}", reg)
-            },
-            0xC5 | 0xD5 | 0xE5 | 0xF5 => {
-                //PUSH rr
-                let mut reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                if reg == Reg::SP {
-                    reg = Reg::AF;
-                }
-                format!("push {:?}", reg)
-            },
-            0xF8 => {
-                //LD HL,SP+n
-                format!("ld HL,SP+{:#x}", instruction.imm8.unwrap())
-            },
-            0xF9 => {
-                //LD SP,HL
-               "ld SP,HL".to_owned()
-            },
-            /*****************************************/
-            /* 8 bit arithmetic/logical instructions */
-            /*****************************************/
-            0x80 ... 0x87 => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("add A,{}", v)
-            },
-            0x88 ... 0x8F => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("adc A,{}", v)
-            },
-            0x90 ... 0x97 => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("sub {}", v)
-            },
-            0x98 ... 0x9F => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("sbc A,{}", v)
-            },
-            0xA0 ... 0xA7 => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("and {}", v)
-            },
-            0xA8 ... 0xAF => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("xor {}", v)
-            },
-            0xB0 ... 0xB7 => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("or {}", v)
-            },
-            0xB8 ... 0xBF => {
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("cp {}", v)
-            },
-            0xC6 => {
-                format!("add A,{:#x}", instruction.imm8.unwrap())
-            },
-            0xD6 => {
-                format!("sub {:#x}", instruction.imm8.unwrap())
-            },
-            0xE6 => {
-                format!("and {:#x}", instruction.imm8.unwrap())
-            },
-            0xF6 => {
-                format!("or {:#x}", instruction.imm8.unwrap())
-            },
-            0xCE => {
-                format!("adc A,{:#x}", instruction.imm8.unwrap())
-            },
-            0xDE => {
-                format!("sbc A,{:#x}", instruction.imm8.unwrap())
-            },
-            0xEE => {
-                format!("xor {:#x}", instruction.imm8.unwrap())
-            },
-            0xFE => {
-                format!("cp {:#x}", instruction.imm8.unwrap())
-            },
-            0x04 | 0x14 | 0x24 | 0x34 |
-            0x0C | 0x1C | 0x2C | 0x3C => {
-                //INC r; INC (HL)
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode >> 3);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned()
-                }
-                format!("inc {}", v)
-            },
-            0x05 | 0x15 | 0x25 | 0x35 |
-            0x0D | 0x1D | 0x2D | 0x3D => {
-                //DEC r; DEC (HL)
-                let reg: Reg = Reg::pair_from_ddd(instruction.opcode >> 3);
-                let mut v = format!("{:?}", reg);
-                if reg == Reg::HL {
-                    v = "(HL)".to_owned();
-                }
-                format!("dec {}", v)
-            },
-            0x27 => {
-                "DAA".to_owned()
-            },
-            0x37 => {
-                "SCF".to_owned()
-            },
-            0x2F => {
-                "CPL".to_owned()
-            },
-            0x3F => {
-                "CCF".to_owned()
-            },
-            /******************************************/
-            /* 16 bit arithmetic/logical instructions */
-            /******************************************/
-            0x03 | 0x13 | 0x23 | 0x33 => {
-                //INC rr
-                let reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                format!("inc {:?}", reg)
-            },
-            0x0B | 0x1B | 0x2B | 0x3B => {
-                //DEC rr
-                let reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                format!("dec {:?}", reg)
-            },
-            0x09 | 0x19 | 0x29 | 0x39 => {
-                //ADD HL,rr
-                let reg: Reg = Reg::pair_from_dd(instruction.opcode >> 4);
-                format!("add HL,{:?}", reg) },
-            0xE8 => {
-                //ADD SP,n
-                format!("add SP,{:#x}", instruction.imm8.unwrap())
-            },
-            /*****************************************/
-            /*            Jumps/Calls                */
-            /*****************************************/
-            0x18 => {
-                //JR n
-                format!("jr {:#x}", instruction.imm8.unwrap())
-            },
-            0x20 => {
-                //JR NZ,r8
-                format!("jr nz {:#x}", instruction.imm8.unwrap())
-            },
-            0x28 => {
-                //JR Z,r8
-                format!("jr z {:#x}", instruction.imm8.unwrap())
-            },
-            0x30 => {
-                //JR NC,r8
-                format!("jr nc {:#x}", instruction.imm8.unwrap())
-            },
-            0x38 => {
-                //JR C,r8
-                format!("jr c {:#x}", instruction.imm8.unwrap())
-            },
-            0xC3 => {
-                //JP nn
-                format!("jp {:#x}", instruction.imm16.unwrap())
-            },
-            0xC2 => {
-                format!("jp nz {:#x}", instruction.imm16.unwrap())
-            },
-            0xCA => {
-                format!("jp z {:#x}", instruction.imm16.unwrap())
-            },
-            0xD2 => {
-                format!("jp nc {:#x}", instruction.imm16.unwrap())
-            },
-            0xDA => {
-                format!("jp c {:#x}", instruction.imm16.unwrap())
-            },
-            0xE9 => {
-                "jp (HL)".to_owned()
-            },
-            0xC0 => {
-                "ret nz".to_owned()
-            },
-            0xC8 => {
-                "ret z".to_owned()
-            },
-            0xC9 => {
-                "ret".to_owned()
-            },
-            0xD0 => {
-                "ret nc".to_owned()
-            },
-            0xD8 => {
-                "ret c".to_owned()
-            },
-            0xD9 => {
-                "reti".to_owned()
-            },
-            0xC4 => {
-                format!("call nz,{:#x}", instruction.imm16.unwrap())
-            },
-            0xCC => {
-                format!("call z,{:#x}", instruction.imm16.unwrap())
-            },
-            0xCD => {
-                format!("call {:#x}", instruction.imm16.unwrap())
-            },
-            0xD4 => {
-                format!("call nc,{:#x}", instruction.imm16.unwrap())
-            },
-            0xDC => {
-                format!("call c,{:#x}", instruction.imm16.unwrap())
-            },
-            0xC7 | 0xCF | 0xD7 | 0xDF |
-            0xE7 | 0xEF | 0xF7 | 0xFF => {
-                //RST
-                "rst".to_owned()
-            },
-            _ => panic!("Unknown instruction: {:#x}", instruction.opcode),
+        &opcodes::OPCODES[instruction.opcode as usize]
+    };
+    match opcode.operand {
+        Operand::None => opcode.mnemonic.to_owned(),
+        Operand::Imm8 => {
+            let imm8: String = match instruction.imm8 {
+                Some(val) => format!("{:#x}", val),
+                None => "?".to_owned(),
+            };
+            opcode.mnemonic
+                  .replace("d8", &imm8)
+                  .replace("a8", &imm8)
+                  .replace("r8", &imm8)
+        }
+        Operand::Imm16 => {
+            let imm16: String = match instruction.imm16 {
+                Some(val) => format!("{:#x}", val),
+                None => "?".to_owned(),
+            };
+            opcode.mnemonic.replace("d16", &imm16).replace("a16", &imm16)
         }
     }
 }
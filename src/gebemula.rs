@@ -1,51 +1,72 @@
-use timeline::{EventType, Event, EventTimeline};
-
 use cpu;
-use cpu::ioregister;
-use cpu::interrupt;
 use cpu::cpu::{Cpu, Instruction};
-use cpu::timer::Timer;
 
 use graphics;
-use graphics::graphics::Graphics;
-
-use mem::mem::Memory;
+use graphics::graphics::{Renderer, Registers, Sprite};
+use graphics::palette::Palettes;
+use graphics::framebuffer;
+use graphics::framebuffer::PixelFormat;
+use graphics::viewer;
+use graphics::viewer::TilePalette;
+
+use util::png;
+use debug_window;
+use debug_window::DebugWindow;
+use recorder::Recorder;
+use wav_dump::WavDump;
+
+use system::System;
 use debugger::Debugger;
 
 use sdl2;
-use sdl2::pixels::{PixelFormatEnum, Color};
+use sdl2::pixels::Color;
 use sdl2::keyboard::{Scancode, Keycode};
 
 use time;
 use std;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::thread;
 
 pub struct Gebemula {
     cpu: Cpu,
-    mem: Memory,
-    timer: Timer,
+    system: System,
     debugger: Debugger,
     game_rom: Vec<u8>,
     cycles_per_sec: u32,
-    graphics: Graphics,
-    should_display_screen: bool,
-    timeline: EventTimeline,
     joypad: u8, // nibble to the left are direction keys and to the right button keys.
+    tile_palette: TilePalette,
+    oam_line: u8, // the OAM viewer marks the sprites selected on this line.
+    frame: u32, // frames displayed since the start, or the last restart.
+    screenshot_dir: PathBuf,
+    screenshot_scale: u32,
+    screenshot_frames: Vec<u32>, // frames to take a screenshot of automatically.
+    recorder: Option<Recorder>,
+    wav_dump: Option<WavDump>,
+}
+
+// local time, for file names.
+fn timestamp() -> String {
+    time::strftime("%Y%m%d-%H%M%S", &time::now()).unwrap()
 }
 
 impl Default for Gebemula {
     fn default() -> Gebemula {
         Gebemula {
             cpu: Cpu::default(),
-            mem: Memory::default(),
-            timer: Timer::default(),
+            system: System::default(),
             debugger: Debugger::default(),
             game_rom: Vec::new(),
             cycles_per_sec: 0,
-            graphics: Graphics::default(),
-            should_display_screen: false,
-            timeline: EventTimeline::default(),
             joypad: 0,
+            tile_palette: TilePalette::Raw,
+            oam_line: 0,
+            frame: 0,
+            screenshot_dir: PathBuf::from("."),
+            screenshot_scale: 1,
+            screenshot_frames: Vec::new(),
+            recorder: None,
+            wav_dump: None,
         }
     }
 }
@@ -53,124 +74,252 @@ impl Default for Gebemula {
 impl Gebemula {
     pub fn restart(&mut self) {
         self.cpu.restart();
-        self.mem.restart();
-        self.timer = Timer::default();
+        self.system.restart();
         self.cycles_per_sec = 0;
-        self.graphics.restart();
-        self.should_display_screen = false;
-        self.timeline = EventTimeline::default();
         self.joypad = 0;
-        ioregister::update_stat_reg_mode_flag(0b10, &mut self.mem);
-        self.mem.set_access_vram(true);
-        self.mem.set_access_oam(false);
+        self.frame = 0;
+        self.update_capture();
     }
 
     pub fn load_bootstrap_rom(&mut self, bootstrap_rom: &[u8]) {
-        self.mem.load_bootstrap_rom(bootstrap_rom);
+        self.system.mem.load_bootstrap_rom(bootstrap_rom);
     }
 
     pub fn load_game_rom(&mut self, game_rom: &[u8]) {
         for byte in game_rom {
             self.game_rom.push(*byte);
         }
-        self.mem.load_game_rom(game_rom);
-    }
-
-    fn run_event(&mut self, event: Event) {
-        let mut gpu_mode_number: Option<u8> = None;
-        match event.event_type {
-            EventType::OAM => {
-                gpu_mode_number = Some(0b11);
-                self.timeline.curr_event_type = EventType::Vram;
-                self.mem.set_access_vram(true);
-                self.mem.set_access_oam(true);
-                self.graphics.update(&mut self.mem);
-            }
-            EventType::Vram => {
-                gpu_mode_number = Some(0b00);
-                self.timeline.curr_event_type = EventType::HorizontalBlank;
-            }
-            EventType::HorizontalBlank => {
-                let mut ly: u8 = self.mem.read_byte(cpu::consts::LY_REGISTER_ADDR);
-                ly += 1;
-                if ly == graphics::consts::DISPLAY_HEIGHT_PX {
-                    self.should_display_screen = true;
-                    gpu_mode_number = Some(0b01);
-                    self.timeline.curr_event_type = EventType::VerticalBlank;
-                    interrupt::request(interrupt::Interrupt::VBlank, &mut self.mem);
-                } else {
-                    self.timeline.curr_event_type = EventType::OAM;
-                    gpu_mode_number = Some(0b10);
-                }
-                self.mem.write_byte(cpu::consts::LY_REGISTER_ADDR, ly);
-            }
-            EventType::VerticalBlank => {
-                let mut ly: u8 = self.mem.read_byte(cpu::consts::LY_REGISTER_ADDR);
-                if ly == graphics::consts::DISPLAY_HEIGHT_PX + 10 {
-                    self.timeline.curr_event_type = EventType::OAM;
-                    gpu_mode_number = Some(0b10);
-                    ly = 0;
-                } else {
-                    self.timeline.curr_event_type = EventType::VerticalBlank;
-                    ly += 1;
-                }
-                self.mem.write_byte(cpu::consts::LY_REGISTER_ADDR, ly);
-            }
-            EventType::BootstrapFinished => {
-                self.mem.disable_bootstrap();
-            }
-            EventType::DMATransfer => {
-                self.mem.set_access_oam(true);
-                ioregister::dma_transfer(event.additional_value, &mut self.mem);
-                self.mem.set_access_oam(false);
-            }
-            EventType::JoypadPressed => {
-                let buttons: u8 = if ioregister::joypad_buttons_selected(&self.mem) {
-                    self.joypad & 0b0000_1111
-                } else {
-                    self.joypad >> 4
-                };
+        self.system.mem.load_game_rom(game_rom);
+    }
 
-                ioregister::joypad_set_buttons(buttons, &mut self.mem);
-            }
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.system.graphics.set_renderer(renderer);
+    }
+
+    pub fn set_palettes(&mut self, palettes: Palettes) {
+        self.system.graphics.set_palettes(palettes);
+    }
+
+    pub fn set_tile_palette(&mut self, palette: TilePalette) {
+        self.tile_palette = palette;
+    }
+
+    fn tile_sheet_rgba(&self) -> Vec<u8> {
+        viewer::tile_sheet(&self.system.mem,
+                           self.system.graphics.registers(),
+                           self.tile_palette)
+            .to_rgba(self.system.graphics.palettes())
+    }
+
+    pub fn dump_tiles(&self, path: &Path) -> io::Result<()> {
+        png::write_rgba(path,
+                        viewer::TILE_SHEET_WIDTH as u32,
+                        viewer::TILE_SHEET_HEIGHT as u32,
+                        &self.tile_sheet_rgba())
+    }
+
+    fn tile_maps_rgba(&self) -> Vec<u8> {
+        let regs: &Registers = self.system.graphics.registers();
+        let mut rgba: Vec<u8> = viewer::tile_maps(&self.system.mem, regs)
+                                    .to_rgba(self.system.graphics.palettes());
+        viewer::draw_outlines(&mut rgba,
+                              viewer::TILE_MAPS_WIDTH,
+                              &viewer::tile_maps_outlines(regs));
+        rgba
+    }
+
+    pub fn set_oam_line(&mut self, line: u8) {
+        self.oam_line = line;
+    }
+
+    fn oam_sheet_rgba(&self) -> Vec<u8> {
+        let regs: &Registers = self.system.graphics.registers();
+        let mut rgba: Vec<u8> = viewer::oam_sheet(&self.system.mem, regs)
+                                    .to_rgba(self.system.graphics.palettes());
+        viewer::draw_oam_selection(&mut rgba,
+                                   &graphics::graphics::select_sprites(&self.system.mem,
+                                                                       regs,
+                                                                       self.oam_line));
+        rgba
+    }
+
+    pub fn dump_oam(&self, path: &Path) -> io::Result<()> {
+        png::write_rgba(path,
+                        viewer::OAM_SHEET_WIDTH as u32,
+                        viewer::OAM_SHEET_HEIGHT as u32,
+                        &self.oam_sheet_rgba())
+    }
+
+    pub fn dump_tile_maps(&self, path: &Path) -> io::Result<()> {
+        png::write_rgba(path,
+                        viewer::TILE_MAPS_WIDTH as u32,
+                        viewer::TILE_MAPS_HEIGHT as u32,
+                        &self.tile_maps_rgba())
+    }
+
+    pub fn set_screenshot_dir(&mut self, dir: &Path) {
+        self.screenshot_dir = dir.to_path_buf();
+    }
+
+    pub fn set_screenshot_scale(&mut self, scale: u32) {
+        self.screenshot_scale = scale;
+    }
+
+    pub fn set_screenshot_frames(&mut self, frames: &[u32]) {
+        self.screenshot_frames = frames.to_vec();
+    }
+
+    // writes the last frame to 'path', scaled up 'scale' times.
+    pub fn screenshot(&self, path: &Path, scale: u32) -> io::Result<()> {
+        let mut pixels: Vec<u8> = Vec::new();
+        self.system.graphics.render(PixelFormat::Rgba8888, &mut pixels);
+        let scaled: Vec<u8> = framebuffer::scale(&pixels,
+                                                 graphics::consts::DISPLAY_WIDTH_PX as usize,
+                                                 PixelFormat::Rgba8888.bytes_per_pixel(),
+                                                 scale as usize);
+        png::write_rgba(path,
+                        graphics::consts::DISPLAY_WIDTH_PX as u32 * scale,
+                        graphics::consts::DISPLAY_HEIGHT_PX as u32 * scale,
+                        &scaled)
+    }
+
+    // screenshot in the screenshot directory, named after the time and the frame.
+    pub fn take_screenshot(&self) -> io::Result<PathBuf> {
+        let path: PathBuf = self.screenshot_dir
+                                .join(format!("gebemula-{}-f{}.png", timestamp(), self.frame));
+        try!(self.screenshot(&path, self.screenshot_scale));
+        Ok(path)
+    }
+
+    fn print_screenshot(&self) {
+        match self.take_screenshot() {
+            Ok(path) => println!("Screenshot: {}", path.display()),
+            Err(error) => println!("Couldn't take screenshot: {}", error),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    // records from the next frame on, until stop_recording.
+    pub fn start_recording(&mut self, dir: &Path) -> io::Result<()> {
+        try!(self.stop_recording());
+        self.recorder = Some(try!(Recorder::start(dir)));
+        self.update_capture();
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        let recorder: Option<Recorder> = self.recorder.take();
+        self.update_capture();
+        match recorder {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
         }
+    }
+
+    // writes the sound from now on to 'path' and, with 'stems', every channel to
+    // a file next to it.
+    pub fn start_wav_dump(&mut self, path: &Path, stems: bool) -> io::Result<()> {
+        try!(self.stop_wav_dump());
+        self.wav_dump = Some(try!(WavDump::start(path, stems)));
+        self.update_capture();
+        Ok(())
+    }
+
+    pub fn stop_wav_dump(&mut self) -> io::Result<()> {
+        let wav_dump: Option<WavDump> = self.wav_dump.take();
+        self.update_capture();
+        match wav_dump {
+            Some(wav_dump) => wav_dump.finish(),
+            None => Ok(()),
+        }
+    }
+
+    // the apu only keeps samples while something uses them.
+    fn update_capture(&mut self) {
+        let stems: bool = match self.wav_dump {
+            Some(ref wav_dump) => wav_dump.has_stems(),
+            None => false,
+        };
+        let capture: bool = self.recorder.is_some() || self.wav_dump.is_some();
+        self.system.apu.set_capture(capture, stems);
+    }
+
+    fn record_frame(&mut self, samples: &[i16]) {
+        if self.recorder.is_none() {
+            return;
+        }
+        let mut pixels: Vec<u8> = Vec::new();
+        self.system.graphics.render(PixelFormat::Rgba8888, &mut pixels);
+        let result: io::Result<()> = match self.recorder {
+            Some(ref mut recorder) => recorder.add_frame(&pixels, samples),
+            None => return,
+        };
+        if let Err(error) = result {
+            println!("Couldn't record frame, stopping: {}", error);
+            let _ = self.stop_recording();
+        }
+    }
 
-        if let Some(gpu_mode) = gpu_mode_number {
-            self.mem.set_access_vram(true);
-            self.mem.set_access_oam(true);
-            // self.mem.set_access_vram(gpu_mode <= 2);
-            // self.mem.set_access_oam(gpu_mode <= 1);
+    fn dump_sound(&mut self, samples: &[i16], stems: &[Vec<i16>; 4]) {
+        let result: io::Result<()> = match self.wav_dump {
+            Some(ref mut wav_dump) => wav_dump.add(samples, stems),
+            None => return,
+        };
+        if let Err(error) = result {
+            println!("Couldn't write sound, stopping: {}", error);
+            let _ = self.stop_wav_dump();
+        }
+    }
+
+    // called after every frame is displayed.
+    fn end_frame(&mut self) {
+        self.frame += 1;
+        if self.screenshot_frames.contains(&self.frame) {
+            self.print_screenshot();
+        }
+        // a frame's worth of sound goes to both the recording and the wav dump.
+        let samples: Vec<i16> = self.system.apu.take_samples();
+        let stems: [Vec<i16>; 4] = self.system.apu.take_stems();
+        self.record_frame(&samples);
+        self.dump_sound(&samples, &stems);
+    }
+
+    fn update_joypad(&mut self) {
+        // a key that was already held when STOP ran doesn't end it.
+        let line_fell: bool = self.system.joypad.set_keys(self.joypad, &mut self.system.mem);
+        if self.cpu.is_stopped() && line_fell {
+            self.cpu.resume_from_stop();
+        }
+    }
 
-            ioregister::update_stat_reg_mode_flag(gpu_mode, &mut self.mem);
+    // the system runs along with the cpu, so the cycles are the ones of the instruction
+    // plus the ones of the interrupt dispatch, if any.
+    fn run_instruction(&mut self) -> u32 {
+        let was_locked: bool = self.cpu.is_locked();
+        let instruction: Instruction = self.cpu.run_instruction(&mut self.system);
+        if !was_locked && self.cpu.is_locked() {
+            println!("CPU locked up: illegal opcode {:#x} at {:#x}. Press R to restart.",
+                     instruction.opcode,
+                     instruction.address);
         }
-        ioregister::update_stat_reg_coincidence_flag(&mut self.mem);
-        ioregister::lcdc_stat_interrupt(&mut self.mem);
+        let interrupt_cycles: u32 = self.cpu.handle_interrupts(&mut self.system);
+        if cfg!(debug_assertions) {
+            self.debugger.run(&instruction, &self.cpu, &self.system);
+        }
+        instruction.cycles + interrupt_cycles
     }
 
+    // runs the cpu for about one scanline, or until a frame is ready to be displayed.
     fn step(&mut self) -> u32 {
-        self.should_display_screen = false;
-        let event: Event = self.timeline.curr_event().unwrap();
+        self.system.should_display_screen = false;
         let mut cycles: u32 = 0;
-        while cycles < event.duration {
-            if !ioregister::LCDCRegister::is_lcd_display_enable(&self.mem) {
-                self.mem.set_access_vram(true);
-                self.mem.set_access_oam(true);
-            }
-            let (instruction, one_event): (Instruction, Option<Event>) =
-                self.cpu.run_instruction(&mut self.mem);
-            self.timer.update(instruction.cycles, &mut self.mem);
-            if let Some(e) = one_event {
-                self.run_event(e);
-                cycles += e.duration;
-                self.timer.update(e.duration, &mut self.mem);
-            }
-            self.cpu.handle_interrupts(&mut self.mem);
-            if cfg!(debug_assertions) {
-                self.debugger.run(&instruction, &self.cpu, &self.mem, &self.timer);
-            }
-            cycles += instruction.cycles;
+        while cycles < cpu::consts::SCANLINE_DURATION_CYCLES &&
+              !self.system.should_display_screen && !self.cpu.is_stopped() {
+            cycles += self.run_instruction();
         }
-        self.run_event(event);
         cycles
     }
 
@@ -227,10 +376,37 @@ impl Gebemula {
         println!(" F1: toggle background");
         println!(" F2: toggle window");
         println!(" F3: toggle sprites");
+        println!(" F4: tile viewer");
+        println!("  P: change tile viewer palette");
+        println!(" F5: tile map viewer");
+        println!(" F6: OAM viewer");
+        println!("[ ]: change OAM viewer line");
+        println!(" F9: start recording");
+        println!("F10: stop recording");
+        println!("F12: screenshot");
         println!("Esc: quit");
         println!("######################");
     }
 
+    // runs 'frames' frames as fast as possible, without a window.
+    pub fn run_headless(&mut self, frames: u32) {
+        self.joypad = 0b1111_1111;
+        let mut frame: u32 = 0;
+        while frame < frames {
+            self.update_joypad();
+            self.step();
+            if self.system.should_display_screen {
+                frame += 1;
+                self.end_frame();
+            }
+            if self.cpu.is_stopped() {
+                // no key will ever be pressed.
+                println!("CPU stopped after {} frames.", frame);
+                break;
+            }
+        }
+    }
+
     pub fn run_sdl(&mut self) {
         Gebemula::print_buttons();
 
@@ -248,13 +424,17 @@ impl Gebemula {
         renderer.set_draw_color(Color::RGBA(0, 0, 0, 255));
 
         let mut texture =
-            renderer.create_texture_streaming(PixelFormatEnum::ABGR8888,
+            renderer.create_texture_streaming(debug_window::RGBA_TEXTURE_FORMAT,
                                               (graphics::consts::DISPLAY_WIDTH_PX as u32,
                                                graphics::consts::DISPLAY_HEIGHT_PX as u32))
                     .unwrap();
 
         renderer.clear();
         renderer.present();
+        let mut pixels: Vec<u8> = Vec::new();
+        let mut tile_window: Option<DebugWindow> = None;
+        let mut map_window: Option<DebugWindow> = None;
+        let mut oam_window: Option<DebugWindow> = None;
 
         let mut event_pump = sdl_context.event_pump().unwrap();
         let mut last_time_seconds = time::now();
@@ -266,19 +446,135 @@ impl Gebemula {
         let mut desired_frametime_ns: u32 = 1_000_000_000 / target_fps;
         let mut fps: u32 = 0;
         if !cfg!(debug_assertions) {
-            self.debugger.display_info(&self.mem);
+            self.debugger.display_info(&self.system.mem);
         }
         'running: loop {
             for event in event_pump.poll_iter() {
                 match event {
                     sdl2::event::Event::KeyDown { keycode: Some(Keycode::F1), .. } => {
-                        self.graphics.toggle_bg();
+                        self.system.graphics.toggle_bg();
                     }
                     sdl2::event::Event::KeyDown { keycode: Some(Keycode::F2), .. } => {
-                        self.graphics.toggle_wn();
+                        self.system.graphics.toggle_wn();
                     }
                     sdl2::event::Event::KeyDown { keycode: Some(Keycode::F3), .. } => {
-                        self.graphics.toggle_sprites();
+                        self.system.graphics.toggle_sprites();
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::F4), .. } => {
+                        tile_window = match tile_window {
+                            Some(_) => None,
+                            None => {
+                                Some(DebugWindow::new(&vide_subsystem,
+                                                      "Tiles",
+                                                      viewer::TILE_SHEET_WIDTH,
+                                                      viewer::TILE_SHEET_HEIGHT,
+                                                      3))
+                            }
+                        };
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::F5), .. } => {
+                        map_window = match map_window {
+                            Some(_) => None,
+                            None => {
+                                Some(DebugWindow::new(&vide_subsystem,
+                                                      "Tile maps",
+                                                      viewer::TILE_MAPS_WIDTH,
+                                                      viewer::TILE_MAPS_HEIGHT,
+                                                      2))
+                            }
+                        };
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::F6), .. } => {
+                        oam_window = match oam_window {
+                            Some(_) => None,
+                            None => {
+                                Some(DebugWindow::new(&vide_subsystem,
+                                                      "OAM",
+                                                      viewer::OAM_SHEET_WIDTH,
+                                                      viewer::OAM_SHEET_HEIGHT,
+                                                      4))
+                            }
+                        };
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::LeftBracket), .. } => {
+                        self.oam_line = if self.oam_line == 0 {
+                            graphics::consts::DISPLAY_HEIGHT_PX - 1
+                        } else {
+                            self.oam_line - 1
+                        };
+                        println!("OAM line: {}", self.oam_line);
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::RightBracket), .. } => {
+                        self.oam_line = (self.oam_line + 1) % graphics::consts::DISPLAY_HEIGHT_PX;
+                        println!("OAM line: {}", self.oam_line);
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::F9), .. } => {
+                        if !self.is_recording() {
+                            let dir: PathBuf = PathBuf::from(format!("gebemula-{}",
+                                                                     timestamp()));
+                            match self.start_recording(&dir) {
+                                Ok(()) => println!("Recording to {}", dir.display()),
+                                Err(error) => println!("Couldn't start recording: {}", error),
+                            }
+                        }
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::F10), .. } => {
+                        if self.is_recording() {
+                            match self.stop_recording() {
+                                Ok(()) => println!("Recording stopped"),
+                                Err(error) => println!("Couldn't finish recording: {}", error),
+                            }
+                        }
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::F12), .. } => {
+                        self.print_screenshot();
+                    }
+                    sdl2::event::Event::KeyDown { keycode: Some(Keycode::P), .. } => {
+                        self.tile_palette = self.tile_palette.next();
+                        println!("tile palette: {:?}", self.tile_palette);
+                    }
+                    sdl2::event::Event::MouseMotion { window_id, x, y, .. } => {
+                        if let Some(ref mut window) = tile_window {
+                            if window.id() == window_id {
+                                let (x, y) = window.to_picture(x, y);
+                                if let Some(tile) = viewer::tile_sheet_tile_at(x, y) {
+                                    let title: &str = &format!("Tiles - {:#05x} @ {:#06x}",
+                                                               tile,
+                                                               viewer::tile_sheet_addr(tile));
+                                    window.set_title(title);
+                                }
+                            }
+                        }
+                        if let Some(ref mut window) = oam_window {
+                            if window.id() == window_id {
+                                let (x, y) = window.to_picture(x, y);
+                                if let Some(index) = viewer::oam_sheet_sprite_at(x, y) {
+                                    let sprite: Sprite = Sprite::from_oam(&self.system.mem,
+                                                                          index);
+                                    let title: &str = &format!("OAM (line {}) - {}",
+                                                               self.oam_line,
+                                                               sprite);
+                                    window.set_title(title);
+                                }
+                            }
+                        }
+                        if let Some(ref mut window) = map_window {
+                            if window.id() == window_id {
+                                let (x, y) = window.to_picture(x, y);
+                                if let Some((map_addr, tile, tile_addr)) =
+                                       viewer::tile_maps_tile_at(&self.system.mem,
+                                                                 self.system.graphics.registers(),
+                                                                 x,
+                                                                 y) {
+                                    let title: &str = &format!("Tile maps - {:#06x}: tile \
+                                                                {:#04x} @ {:#06x}",
+                                                               map_addr,
+                                                               tile,
+                                                               tile_addr);
+                                    window.set_title(title);
+                                }
+                            }
+                        }
                     }
                     sdl2::event::Event::KeyDown { keycode: Some(Keycode::Q), .. } => {
                         self.debugger.cancel_run();
@@ -310,11 +606,15 @@ impl Gebemula {
                 }
             }
 
-            if self.adjust_joypad_buttons(&event_pump) {
-                interrupt::request(interrupt::Interrupt::Joypad, &mut self.mem);
-            }
+            // the joypad only requests an interrupt when a line goes low.
+            self.adjust_joypad_buttons(&event_pump);
+            self.update_joypad();
 
             self.cycles_per_sec += self.step();
+            if self.cpu.is_stopped() {
+                // nothing to emulate until a key is pressed.
+                thread::sleep(std::time::Duration::new(0, desired_frametime_ns));
+            }
 
             /*
              * Yuri Kunde Schlesner:
@@ -330,17 +630,25 @@ impl Gebemula {
              *
              * https://github.com/yuriks/super-match-5-dx/blob/master/src/main.cpp#L224
              */
-            if self.should_display_screen {
+            if self.system.should_display_screen {
                 renderer.clear();
-                texture.update(None, &self.graphics.screen_buffer,
-                               graphics::consts::DISPLAY_WIDTH_PX as usize * 4).unwrap();
+                self.system.graphics.render(PixelFormat::Rgba8888, &mut pixels);
+                texture.update(None, &pixels,
+                               graphics::consts::DISPLAY_WIDTH_PX as usize *
+                               PixelFormat::Rgba8888.bytes_per_pixel()).unwrap();
                 renderer.copy(&texture, None, None);
                 renderer.present();
+                self.end_frame();
+                if let Some(ref mut window) = tile_window {
+                    window.show(&self.tile_sheet_rgba());
+                }
+                if let Some(ref mut window) = map_window {
+                    window.show(&self.tile_maps_rgba());
+                }
+                if let Some(ref mut window) = oam_window {
+                    window.show(&self.oam_sheet_rgba());
+                }
 
-                //clear buffer
-                self.graphics.screen_buffer = [255;
-                (graphics::consts::DISPLAY_HEIGHT_PX as usize *
-                 graphics::consts::DISPLAY_WIDTH_PX as usize * 4)];
                 let now = time::now();
                 let elapsed: u32 = (now - last_time).num_nanoseconds().unwrap() as u32;
                 if elapsed < desired_frametime_ns {
@@ -353,7 +661,12 @@ impl Gebemula {
             let now = time::now();
             if now - last_time_seconds >= time::Duration::seconds(1) {
                 last_time_seconds = now;
-                let title: &str = &format!("{} Gebemula - {}", fps, self.cycles_per_sec);
+                let locked: &str = if self.cpu.is_locked() {
+                    " - LOCKED"
+                } else {
+                    ""
+                };
+                let title: &str = &format!("{} Gebemula - {}{}", fps, self.cycles_per_sec, locked);
                 renderer.window_mut().unwrap().set_title(title);
                 self.cycles_per_sec = 0;
                 fps = 0;
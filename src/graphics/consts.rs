@@ -8,6 +8,8 @@ pub const TILE_DATA_TABLE_1_ADDR_START: u16 = 0x8000;
 
 pub const SPRITE_PATTERN_TABLE_ADDR_START: u16 = 0x8000;
 pub const SPRITE_ATTRIBUTE_TABLE: u16 = 0xFE00;
+pub const NUM_SPRITES: u8 = 40;
+pub const MAX_SPRITES_PER_LINE: usize = 10;
 
 pub const BG_NORMAL_ADDR_START: u16 = 0x9800;
 pub const BG_WINDOW_ADDR_START: u16 = 0x9C00;
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+
+use graphics::consts;
+use graphics::tiles;
+use graphics::graphics::{Registers, Sprite};
+use super::super::mem::mem::Memory;
+
+// A pixel leaving the FIFO, before palettes and priorities are applied.
+#[derive(Copy, Clone, Debug)]
+pub struct Pixel {
+    pub x: u8,
+    pub bg_color: u8,
+    pub window: bool,
+    pub sprite_color: u8, // 0 if there's no sprite pixel.
+    pub sprite_obp1: bool,
+    pub sprite_behind_bg: bool,
+}
+
+#[derive(Copy, Clone)]
+struct SpritePixel {
+    color: u8,
+    obp1: bool,
+    behind_bg: bool,
+}
+
+const TRANSPARENT: SpritePixel = SpritePixel {
+    color: 0,
+    obp1: false,
+    behind_bg: false,
+};
+
+// dots spent by the fetcher before the first tile of a line can be fetched.
+const LINE_START_DELAY: u8 = 6;
+// dots spent fetching a sprite once the background fetcher is idle.
+const SPRITE_FETCH_DOTS: u8 = 6;
+
+// Mode 3 done the way the hardware does it: a fetcher reads 8 pixels of a tile
+// every few dots and pushes them into a FIFO, which shifts one pixel out per
+// dot. Sprites pause the background fetcher while they are fetched, and the
+// window restarts it, so mode 3 length changes from line to line.
+pub struct PixelFifo {
+    bg_fifo: VecDeque<u8>,
+    sprite_fifo: VecDeque<SpritePixel>,
+    sprites: Vec<Sprite>, // sprites of the line not fetched yet, by priority.
+    sprite_fetch: Option<(Sprite, u8)>,
+    // background fetcher
+    fetcher_dots: u8,
+    fetcher_x: u8, // tile column, relative to the bg scroll or the window start.
+    tile_number: u8,
+    tile_low: u8,
+    tile_high: u8,
+    x: u8, // screen column of the next pixel.
+    discard: u8, // pixels still to be thrown away because of SCX.
+    window: bool,
+    window_line: Option<u8>, // None if the window can't show up on this line.
+    window_whole_line: bool,
+    delay: u8,
+}
+
+impl Default for PixelFifo {
+    fn default() -> PixelFifo {
+        PixelFifo {
+            bg_fifo: VecDeque::with_capacity(16),
+            sprite_fifo: VecDeque::with_capacity(8),
+            sprites: Vec::new(),
+            sprite_fetch: None,
+            fetcher_dots: 0,
+            fetcher_x: 0,
+            tile_number: 0,
+            tile_low: 0,
+            tile_high: 0,
+            x: 0,
+            discard: 0,
+            window: false,
+            window_line: None,
+            window_whole_line: false,
+            delay: 0,
+        }
+    }
+}
+
+impl PixelFifo {
+    pub fn start_line(&mut self,
+                      regs: &Registers,
+                      sprites: Vec<Sprite>,
+                      window_line: Option<u8>,
+                      window_whole_line: bool) {
+        *self = PixelFifo::default();
+        self.sprites = sprites;
+        self.window_line = window_line;
+        self.window_whole_line = window_whole_line;
+        self.discard = regs.scx % 8;
+        self.delay = LINE_START_DELAY;
+    }
+
+    pub fn is_line_done(&self) -> bool {
+        self.x == consts::DISPLAY_WIDTH_PX
+    }
+
+    pub fn is_window_drawn(&self) -> bool {
+        self.window
+    }
+
+    // runs one dot; returns the pixel shifted out, if any.
+    pub fn step(&mut self, regs: &Registers, memory: &Memory) -> Option<Pixel> {
+        if self.is_line_done() {
+            return None;
+        }
+        if self.delay > 0 {
+            self.delay -= 1;
+            return None;
+        }
+
+        if !self.window && self.discard == 0 && self.window_line.is_some() &&
+           regs.is_window_display_on() &&
+           (self.window_whole_line || (regs.wx <= 166 && self.x + 7 >= regs.wx)) {
+            self.window = true;
+            self.bg_fifo.clear();
+            self.fetcher_dots = 0;
+            self.fetcher_x = 0;
+            if !self.window_whole_line && regs.wx < 7 {
+                // the window starts left of the screen.
+                self.discard = 7 - regs.wx;
+            }
+        }
+
+        if self.sprite_fetch.is_none() && regs.is_sprite_display_on() {
+            let x: u8 = self.x;
+            if let Some(index) = self.sprites.iter().position(|s| s.x <= x + 8) {
+                let sprite: Sprite = self.sprites.remove(index);
+                self.sprite_fetch = Some((sprite, 0));
+            }
+        }
+
+        if let Some((sprite, dots)) = self.sprite_fetch {
+            // the background fetcher finishes its tile before the sprite is fetched.
+            if self.bg_fifo.is_empty() {
+                self.step_fetcher(regs, memory);
+            } else if dots + 1 == SPRITE_FETCH_DOTS {
+                self.fetch_sprite(sprite, regs, memory);
+                self.sprite_fetch = None;
+            } else {
+                self.sprite_fetch = Some((sprite, dots + 1));
+            }
+            return None;
+        }
+
+        self.step_fetcher(regs, memory);
+        self.shift_out()
+    }
+
+    fn shift_out(&mut self) -> Option<Pixel> {
+        let bg_color: u8 = match self.bg_fifo.pop_front() {
+            Some(color) => color,
+            None => return None,
+        };
+        if self.discard > 0 {
+            self.discard -= 1;
+            return None;
+        }
+        let sprite: SpritePixel = self.sprite_fifo.pop_front().unwrap_or(TRANSPARENT);
+        let pixel: Pixel = Pixel {
+            x: self.x,
+            bg_color: bg_color,
+            window: self.window,
+            sprite_color: sprite.color,
+            sprite_obp1: sprite.obp1,
+            sprite_behind_bg: sprite.behind_bg,
+        };
+        self.x += 1;
+        Some(pixel)
+    }
+
+    // each fetcher step takes two dots: tile number, low data, high data; then
+    // it waits until the FIFO is empty to push the 8 pixels.
+    fn step_fetcher(&mut self, regs: &Registers, memory: &Memory) {
+        self.fetcher_dots += 1;
+        match self.fetcher_dots {
+            2 => self.tile_number = memory.read_byte(self.tile_map_addr(regs)),
+            4 => self.tile_low = memory.read_byte(self.tile_data_addr(regs)),
+            6 => self.tile_high = memory.read_byte(self.tile_data_addr(regs) + 1),
+            _ => (),
+        }
+        if self.fetcher_dots >= 6 && self.bg_fifo.is_empty() {
+            for col in 0..8 {
+                self.bg_fifo.push_back(tiles::pixel_data(self.tile_low, self.tile_high, col));
+            }
+            self.fetcher_dots = 0;
+            self.fetcher_x = self.fetcher_x.wrapping_add(1);
+        }
+    }
+
+    // line inside the 256x256 map being fetched.
+    fn map_line(&self, regs: &Registers) -> u8 {
+        if self.window {
+            self.window_line.unwrap_or(0)
+        } else {
+            regs.ly.wrapping_add(regs.scy)
+        }
+    }
+
+    fn tile_map_addr(&self, regs: &Registers) -> u16 {
+        let (map_start, col) = if self.window {
+            let map_start: u16 = if regs.is_window_tile_map_display_normal() {
+                consts::BG_NORMAL_ADDR_START
+            } else {
+                consts::BG_WINDOW_ADDR_START
+            };
+            (map_start, self.fetcher_x)
+        } else {
+            let map_start: u16 = if regs.is_bg_tile_map_display_normal() {
+                consts::BG_NORMAL_ADDR_START
+            } else {
+                consts::BG_WINDOW_ADDR_START
+            };
+            // SCX is read on every fetch, so mid line writes take effect.
+            (map_start, (regs.scx / 8).wrapping_add(self.fetcher_x))
+        };
+        let row: u16 = (self.map_line(regs) / 8) as u16;
+        map_start + row * 32 + (col % 32) as u16
+    }
+
+    fn tile_data_addr(&self, regs: &Registers) -> u16 {
+        let tile_line: u16 = (self.map_line(regs) % 8) as u16 * 2;
+        tiles::tile_addr(self.tile_number, regs.is_tile_data_0()) + tile_line
+    }
+
+    fn fetch_sprite(&mut self, sprite: Sprite, regs: &Registers, memory: &Memory) {
+        let line_data: (u8, u8) = sprite.line_data(regs, memory);
+        for col in 0..8 {
+            // x is the screen column + 8.
+            let screen_x: i16 = sprite.x as i16 - 8 + col as i16;
+            if screen_x < self.x as i16 {
+                continue;
+            }
+            let pos: usize = (screen_x - self.x as i16) as usize;
+            let pixel: SpritePixel = SpritePixel {
+                color: sprite.pixel_data(line_data, col),
+                obp1: sprite.is_obp1(),
+                behind_bg: sprite.is_behind_bg(),
+            };
+            while self.sprite_fifo.len() <= pos {
+                self.sprite_fifo.push_back(TRANSPARENT);
+            }
+            // sprites fetched first have priority.
+            if self.sprite_fifo[pos].color == 0 {
+                self.sprite_fifo[pos] = pixel;
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use graphics::consts;
+use graphics::palette::{Palettes, Rgb};
+
+// The lcd outputs one index per pixel: the palette it comes from plus the
+// shade (0 to 3) it got after going through BGP, OBP0 or OBP1. It's only turned
+// into colors when a frontend asks for it.
+pub const BG_PALETTE: u8 = 0;
+pub const OBP0_PALETTE: u8 = 4;
+pub const OBP1_PALETTE: u8 = 8;
+// the color of an lcd that's off.
+pub const LCD_OFF: u8 = 12;
+pub const NUM_INDEXES: usize = 13;
+
+pub const FRAME_SIZE: usize = consts::DISPLAY_WIDTH_PX as usize *
+                              consts::DISPLAY_HEIGHT_PX as usize;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PixelFormat {
+    // 4 bytes per pixel, in R, G, B, A order.
+    Rgba8888,
+    // 2 bytes per pixel, a little endian u16 with red in the upper bits. None of
+    // the frontends uses it yet.
+    #[cfg(test)]
+    Rgb565,
+    // 1 byte per pixel, the index itself; colors come from color_table.
+    Indexed,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match *self {
+            PixelFormat::Rgba8888 => 4,
+            #[cfg(test)]
+            PixelFormat::Rgb565 => 2,
+            PixelFormat::Indexed => 1,
+        }
+    }
+}
+
+// the color of every index.
+pub fn color_table(palettes: &Palettes) -> [Rgb; NUM_INDEXES] {
+    let mut table: [Rgb; NUM_INDEXES] = [(0, 0, 0); NUM_INDEXES];
+    for shade in 0..4 {
+        table[(BG_PALETTE + shade) as usize] = Palettes::color(&palettes.bg, shade);
+        table[(OBP0_PALETTE + shade) as usize] = Palettes::color(&palettes.obp0, shade);
+        table[(OBP1_PALETTE + shade) as usize] = Palettes::color(&palettes.obp1, shade);
+    }
+    table[LCD_OFF as usize] = Palettes::color(&palettes.bg, 0);
+    table
+}
+
+// converts the indexes of a frame (or any other picture) to 'format', replacing
+// the contents of 'buffer'.
+pub fn convert(indexes: &[u8], palettes: &Palettes, format: PixelFormat, buffer: &mut Vec<u8>) {
+    buffer.clear();
+    buffer.reserve(indexes.len() * format.bytes_per_pixel());
+    if format == PixelFormat::Indexed {
+        buffer.extend_from_slice(indexes);
+        return;
+    }
+
+    let table: [Rgb; NUM_INDEXES] = color_table(palettes);
+    for index in indexes {
+        let (r, g, b) = table[*index as usize];
+        match format {
+            PixelFormat::Rgba8888 => {
+                buffer.push(r);
+                buffer.push(g);
+                buffer.push(b);
+                buffer.push(255);
+            }
+            #[cfg(test)]
+            PixelFormat::Rgb565 => {
+                let color: u16 = ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) |
+                                 (b as u16 >> 3);
+                buffer.push(color as u8);
+                buffer.push((color >> 8) as u8);
+            }
+            PixelFormat::Indexed => unreachable!(),
+        }
+    }
+}
+
+// scales a picture of any format up by an integer factor, repeating every pixel
+// 'scale' times in both directions.
+pub fn scale(pixels: &[u8], width: usize, bytes_per_pixel: usize, scale: usize) -> Vec<u8> {
+    let row_size: usize = width * bytes_per_pixel;
+    let mut scaled: Vec<u8> = Vec::with_capacity(pixels.len() * scale * scale);
+    for row in pixels.chunks(row_size) {
+        let start: usize = scaled.len();
+        for pixel in row.chunks(bytes_per_pixel) {
+            for _ in 0..scale {
+                scaled.extend_from_slice(pixel);
+            }
+        }
+        let scaled_row: Vec<u8> = scaled[start..].to_vec();
+        for _ in 1..scale {
+            scaled.extend_from_slice(&scaled_row);
+        }
+    }
+    scaled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::palette::{Palette, Palettes};
+
+    fn palettes() -> Palettes {
+        Palettes {
+            bg: Palette::gray(),
+            obp0: Palette::green(),
+            obp1: Palette::pocket(),
+        }
+    }
+
+    const INDEXES: [u8; 4] = [BG_PALETTE + 1, OBP0_PALETTE + 3, OBP1_PALETTE, LCD_OFF];
+
+    #[test]
+    fn rgba8888() {
+        let mut buffer: Vec<u8> = vec![1, 2, 3];
+        convert(&INDEXES, &palettes(), PixelFormat::Rgba8888, &mut buffer);
+        assert_eq!(buffer,
+                   vec![192, 192, 192, 255, 0x0F, 0x38, 0x0F, 255, 0xC4, 0xCF, 0xA1, 255, 255,
+                        255, 255, 255]);
+    }
+
+    #[test]
+    fn rgb565() {
+        let mut buffer: Vec<u8> = Vec::new();
+        convert(&INDEXES, &palettes(), PixelFormat::Rgb565, &mut buffer);
+        // 0xC618, 0x09C1, 0xC674, 0xFFFF.
+        assert_eq!(buffer, vec![0x18, 0xC6, 0xC1, 0x09, 0x74, 0xC6, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn indexed() {
+        let mut buffer: Vec<u8> = vec![0xFF; 10];
+        convert(&INDEXES, &palettes(), PixelFormat::Indexed, &mut buffer);
+        assert_eq!(buffer, INDEXES.to_vec());
+        assert_eq!(color_table(&palettes())[INDEXES[1] as usize], (0x0F, 0x38, 0x0F));
+    }
+
+    #[test]
+    fn scale_repeats_pixels_and_rows() {
+        let pixels: [u8; 4] = [1, 2, 3, 4];
+        assert_eq!(scale(&pixels, 2, 1, 2),
+                   vec![1, 1, 2, 2, 1, 1, 2, 2, 3, 3, 4, 4, 3, 3, 4, 4]);
+        assert_eq!(scale(&pixels, 1, 2, 2), vec![1, 2, 1, 2, 1, 2, 1, 2, 3, 4, 3, 4, 3, 4, 3, 4]);
+    }
+}
@@ -1,95 +1,686 @@
+use std::cmp;
+use std::fmt;
+
 use graphics::consts;
-use super::super::util::util;
 use super::super::mem::mem::Memory;
-use super::super::cpu::ioregister;
 use super::super::cpu;
+use super::super::cpu::interrupt;
+use graphics::fifo::{PixelFifo, Pixel};
+use graphics::tiles;
+use graphics::palette::Palettes;
+use graphics::framebuffer;
+use graphics::framebuffer::PixelFormat;
+
+// LCD registers.
+pub struct Registers {
+    pub lcdc: u8,
+    pub stat: u8,
+    pub scy: u8,
+    pub scx: u8,
+    pub ly: u8,
+    pub lyc: u8,
+    pub bgp: u8,
+    pub obp0: u8,
+    pub obp1: u8,
+    pub wy: u8,
+    pub wx: u8,
+}
+
+impl Default for Registers {
+    fn default() -> Registers {
+        Registers {
+            lcdc: 0,
+            stat: 0,
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            bgp: 0,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+        }
+    }
+}
+
+impl Registers {
+    pub fn mode(&self) -> u8 {
+        self.stat & 0b11
+    }
+
+    fn is_lcdc_bit_set(&self, bit: u8) -> bool {
+        (self.lcdc >> bit) & 0b1 == 0b1
+    }
+    pub fn is_lcd_display_enable(&self) -> bool {
+        self.is_lcdc_bit_set(7)
+    }
+    pub fn is_window_tile_map_display_normal(&self) -> bool {
+        !self.is_lcdc_bit_set(6)
+    }
+    pub fn is_window_display_on(&self) -> bool {
+        self.is_lcdc_bit_set(5)
+    }
+    pub fn is_tile_data_0(&self) -> bool {
+        !self.is_lcdc_bit_set(4)
+    }
+    pub fn is_bg_tile_map_display_normal(&self) -> bool {
+        !self.is_lcdc_bit_set(3)
+    }
+    pub fn is_sprite_8_16_on(&self) -> bool {
+        self.is_lcdc_bit_set(2)
+    }
+    pub fn is_sprite_display_on(&self) -> bool {
+        self.is_lcdc_bit_set(1)
+    }
+    pub fn is_bg_window_display_on(&self) -> bool {
+        self.is_lcdc_bit_set(0)
+    }
+
+    // pixel_data has to have a value from 0 to 3.
+    pub fn bg_window_palette(&self, pixel_data: u8) -> u8 {
+        (self.bgp >> (pixel_data * 2)) & 0b11
+    }
+    pub fn sprite_palette(&self, obp0: bool, pixel_data: u8) -> u8 {
+        let palette: u8 = if obp0 {
+            self.obp0
+        } else {
+            self.obp1
+        };
+        (palette >> (pixel_data * 2)) & 0b11
+    }
+}
+
+// An OAM entry.
+#[derive(Copy, Clone, Debug)]
+pub struct Sprite {
+    pub index: u8, // position in OAM, from 0 to 39.
+    pub y: u8,
+    pub x: u8,
+    pub tile_number: u8,
+    pub flags: u8,
+}
+
+impl fmt::Display for Sprite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let priority: &str = if self.is_behind_bg() {
+            "behind bg"
+        } else {
+            "above bg"
+        };
+        let palette: &str = if self.is_obp1() {
+            "OBP1"
+        } else {
+            "OBP0"
+        };
+        write!(f,
+               "#{:02} Y: {:3} X: {:3} tile: {:#04x} flags: {:#04x} ({}, {}{}{})",
+               self.index,
+               self.y,
+               self.x,
+               self.tile_number,
+               self.flags,
+               priority,
+               palette,
+               if self.is_x_flip() {
+                   ", x flip"
+               } else {
+                   ""
+               },
+               if self.is_y_flip() {
+                   ", y flip"
+               } else {
+                   ""
+               })
+    }
+}
+
+impl Sprite {
+    pub fn from_oam(memory: &Memory, index: u8) -> Sprite {
+        let addr: u16 = consts::SPRITE_ATTRIBUTE_TABLE + index as u16 * 4;
+        Sprite {
+            index: index,
+            y: memory.read_byte(addr),
+            x: memory.read_byte(addr + 1),
+            tile_number: memory.read_byte(addr + 2),
+            flags: memory.read_byte(addr + 3),
+        }
+    }
+
+    pub fn height(regs: &Registers) -> u8 {
+        if regs.is_sprite_8_16_on() {
+            16
+        } else {
+            8
+        }
+    }
+
+    pub fn is_on_line(&self, regs: &Registers, line: u8) -> bool {
+        // y is the line + 16.
+        let line: u8 = line.wrapping_add(16);
+        line >= self.y && line < self.y.wrapping_add(Sprite::height(regs))
+    }
+
+    pub fn is_behind_bg(&self) -> bool {
+        (self.flags >> 7) & 0b1 == 0b1
+    }
+    pub fn is_y_flip(&self) -> bool {
+        (self.flags >> 6) & 0b1 == 0b1
+    }
+    pub fn is_x_flip(&self) -> bool {
+        (self.flags >> 5) & 0b1 == 0b1
+    }
+    pub fn is_obp1(&self) -> bool {
+        (self.flags >> 4) & 0b1 == 0b1
+    }
+
+    // the two bytes of the sprite's row that falls on the current line.
+    pub fn line_data(&self, regs: &Registers, memory: &Memory) -> (u8, u8) {
+        self.row_data(regs, memory, regs.ly.wrapping_add(16).wrapping_sub(self.y))
+    }
+
+    // the two bytes of a row of the sprite as it's shown, y flip applied.
+    pub fn row_data(&self, regs: &Registers, memory: &Memory, row: u8) -> (u8, u8) {
+        let height: u8 = Sprite::height(regs);
+        let tile_number: u8 = if regs.is_sprite_8_16_on() {
+            // the top tile is always the even one.
+            self.tile_number & 0b1111_1110
+        } else {
+            self.tile_number
+        };
+        let mut tile_line: u8 = row;
+        if self.is_y_flip() {
+            tile_line = height - 1 - tile_line;
+        }
+        // tile_line*2 because each tile uses 2 bytes per line.
+        let addr: u16 = consts::SPRITE_PATTERN_TABLE_ADDR_START +
+                        tile_number as u16 * consts::TILE_SIZE_BYTES as u16 +
+                        tile_line as u16 * 2;
+        (memory.read_byte(addr), memory.read_byte(addr + 1))
+    }
+
+    // pixel_data (0 to 3) of one of the 8 columns, flipping applied.
+    pub fn pixel_data(&self, line_data: (u8, u8), col: u8) -> u8 {
+        let col: u8 = if self.is_x_flip() {
+            7 - col
+        } else {
+            col
+        };
+        let (low, high) = line_data;
+        tiles::pixel_data(low, high, col)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Renderer {
+    // the whole line is drawn when mode 3 starts, which is fast but misses
+    // register writes made during mode 3.
+    Scanline,
+    // pixels are drawn one per dot, as the hardware does.
+    PixelFifo,
+}
+
+const MODE_HBLANK: u8 = 0b00;
+const MODE_VBLANK: u8 = 0b01;
+const MODE_OAM: u8 = 0b10;
+const MODE_VRAM: u8 = 0b11;
+
+const LINES_PER_FRAME: u8 = 154;
+// dots where update_coincidence_flag can change the flag, see there.
+const COINCIDENCE_EVENT_DOTS: [u16; 3] = [4, 8, 12];
+pub const FRAME_DURATION_DOTS: u32 = cpu::consts::SCANLINE_DURATION_CYCLES * LINES_PER_FRAME as u32;
 
 pub struct Graphics {
     // FIXME: find the correct size to bg_wn_pixel_indexes
     bg_wn_pixel_indexes: [u8; 160 * 144 * 4],
-    pub screen_buffer: [u8; 160 * 144 * 4],
+    frame: [u8; framebuffer::FRAME_SIZE],
     bg_on: bool,
     wn_on: bool,
     sprites_on: bool,
+    regs: Registers,
+    renderer: Renderer,
+    palettes: Palettes,
+    fifo: PixelFifo,
+    dot: u16, // position in the current line.
+    line: u8, // the line being drawn; it's not always LY, see update_coincidence_flag.
+    stat_line: bool,
+    off_dots: u32, // dots since the last blank frame, while the lcd is off.
+    skip_frame: bool,
+    // The window has its own line counter, which only advances on lines where
+    // the window was drawn. It starts being drawn once LY == WY in a frame.
+    window_y_reached: bool,
+    window_line: u8,
+    window_drawn: bool,
+    // with WX == 166 the window covers the whole next line.
+    window_whole_line: bool,
 }
 
 impl Default for Graphics {
     fn default() -> Graphics {
         Graphics {
-            screen_buffer: [255; 160 * 144 * 4],
+            frame: [framebuffer::LCD_OFF; framebuffer::FRAME_SIZE],
             bg_wn_pixel_indexes: [0; 160 * 144 * 4],
             bg_on: true,
             wn_on: true,
             sprites_on: true,
+            regs: Registers::default(),
+            renderer: Renderer::Scanline,
+            palettes: Palettes::default(),
+            fifo: PixelFifo::default(),
+            dot: 0,
+            line: 0,
+            stat_line: false,
+            off_dots: 0,
+            skip_frame: false,
+            window_y_reached: false,
+            window_line: 0,
+            window_drawn: false,
+            window_whole_line: false,
         }
     }
 }
 
 impl Graphics {
     pub fn restart(&mut self) {
-        self.screen_buffer = [255; 160 * 144 * 4];
-        self.bg_wn_pixel_indexes = [0; 160 * 144 * 4];
-        self.bg_on = true;
-        self.wn_on = true;
-        self.sprites_on = true;
+        let renderer: Renderer = self.renderer;
+        let palettes: Palettes = self.palettes;
+        *self = Graphics::default();
+        self.renderer = renderer;
+        self.palettes = palettes;
+    }
+
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.renderer = renderer;
+    }
+
+    pub fn set_palettes(&mut self, palettes: Palettes) {
+        self.palettes = palettes;
     }
 
-    pub fn update(&mut self, memory: &mut Memory) {
-        if ioregister::LCDCRegister::is_lcd_display_enable(memory) {
-            self.update_line_buffer(memory);
-            self.draw_sprites(memory);
+    pub fn read_register(&self, address: u16) -> u8 {
+        match address {
+            cpu::consts::LCDC_REGISTER_ADDR => self.regs.lcdc,
+            cpu::consts::STAT_REGISTER_ADDR => self.regs.stat | 0b1000_0000,
+            cpu::consts::SCY_REGISTER_ADDR => self.regs.scy,
+            cpu::consts::SCX_REGISTER_ADDR => self.regs.scx,
+            cpu::consts::LY_REGISTER_ADDR => self.regs.ly,
+            cpu::consts::LYC_REGISTER_ADDR => self.regs.lyc,
+            cpu::consts::BGP_REGISTER_ADDR => self.regs.bgp,
+            cpu::consts::OBP_0_REGISTER_ADDR => self.regs.obp0,
+            cpu::consts::OBP_1_REGISTER_ADDR => self.regs.obp1,
+            cpu::consts::WY_REGISTER_ADDR => self.regs.wy,
+            cpu::consts::WX_REGISTER_ADDR => self.regs.wx,
+            _ => unreachable!(),
         }
     }
 
-    fn update_line_buffer(&mut self, memory: &Memory) {
-        let mut bg_on: bool = ioregister::LCDCRegister::is_bg_window_display_on(memory);
-        let mut wn_on: bool = ioregister::LCDCRegister::is_window_display_on(memory);
+    pub fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            cpu::consts::LCDC_REGISTER_ADDR => {
+                let was_enabled: bool = self.regs.is_lcd_display_enable();
+                self.regs.lcdc = value;
+                match (was_enabled, self.regs.is_lcd_display_enable()) {
+                    (true, false) => self.turn_off(),
+                    (false, true) => self.turn_on(),
+                    _ => (),
+                }
+            }
+            cpu::consts::STAT_REGISTER_ADDR => {
+                // mode and coincidence flags are read only.
+                self.regs.stat = (self.regs.stat & 0b0000_0111) | (value & 0b0111_1000);
+            }
+            cpu::consts::SCY_REGISTER_ADDR => self.regs.scy = value,
+            cpu::consts::SCX_REGISTER_ADDR => self.regs.scx = value,
+            cpu::consts::LY_REGISTER_ADDR => (), // read only
+            cpu::consts::LYC_REGISTER_ADDR => self.regs.lyc = value,
+            cpu::consts::BGP_REGISTER_ADDR => self.regs.bgp = value,
+            cpu::consts::OBP_0_REGISTER_ADDR => self.regs.obp0 = value,
+            cpu::consts::OBP_1_REGISTER_ADDR => self.regs.obp1 = value,
+            cpu::consts::WY_REGISTER_ADDR => self.regs.wy = value,
+            cpu::consts::WX_REGISTER_ADDR => self.regs.wx = value,
+            _ => unreachable!(),
+        }
+    }
 
-        bg_on = bg_on & self.bg_on;
-        wn_on = wn_on & self.wn_on;
+    pub fn mode(&self) -> u8 {
+        self.regs.mode()
+    }
 
-        if !bg_on && !wn_on {
-            return;
+    // LY is reset and the lcd stays in mode 0 until it's turned on again.
+    fn turn_off(&mut self) {
+        self.regs.ly = 0;
+        self.line = 0;
+        self.dot = 0;
+        self.off_dots = 0;
+        self.stat_line = false;
+        self.set_mode(MODE_HBLANK);
+        self.clear_screen();
+    }
+
+    // a new frame starts from line 0.
+    fn turn_on(&mut self) {
+        self.regs.ly = 0;
+        self.line = 0;
+        self.dot = 0;
+        self.window_y_reached = false;
+        self.window_line = 0;
+        self.window_whole_line = false;
+        self.skip_frame = true;
+        self.set_mode(MODE_OAM);
+    }
+
+    // fills the screen with the color of an lcd that's off, which is close to
+    // the lightest shade.
+    fn clear_screen(&mut self) {
+        for pixel in self.frame.iter_mut() {
+            *pixel = framebuffer::LCD_OFF;
         }
+    }
 
-        let curr_line: u8 = memory.read_byte(cpu::consts::LY_REGISTER_ADDR);
-        if curr_line >= consts::DISPLAY_HEIGHT_PX {
-            return;
+    // the last frame, as framebuffer indexes.
+    #[cfg(test)]
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    pub fn palettes(&self) -> &Palettes {
+        &self.palettes
+    }
+
+    // the last frame in a frontend's format.
+    pub fn render(&self, format: PixelFormat, buffer: &mut Vec<u8>) {
+        framebuffer::convert(&self.frame, &self.palettes, format, buffer);
+    }
+
+    pub fn is_lcd_display_enable(&self) -> bool {
+        self.regs.is_lcd_display_enable()
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.regs
+    }
+
+    fn set_mode(&mut self, mode: u8) {
+        self.regs.stat = (self.regs.stat & 0b1111_1100) | (mode & 0b11);
+    }
+
+    // LY == LYC is compared one M-cycle after LY changes, so the flag is clear
+    // for the first 4 dots of a line. On line 153 LY reads 153 only for one
+    // M-cycle and then 0: 153 is still compared for the next M-cycle, the flag is
+    // clear for another one and then 0 is compared for the rest of the line.
+    fn update_coincidence_flag(&mut self) {
+        let last_line: bool = self.line == LINES_PER_FRAME - 1;
+        if last_line && self.dot == 4 {
+            self.regs.ly = 0;
         }
-        let scx: u8 = memory.read_byte(cpu::consts::SCX_REGISTER_ADDR);
-        let scy: u8 = memory.read_byte(cpu::consts::SCY_REGISTER_ADDR);
-        let mut ypos: u16 = curr_line.wrapping_add(scy) as u16;
-        let wy: u8 = memory.read_byte(cpu::consts::WY_REGISTER_ADDR);
-        let wx: u8 = memory.read_byte(cpu::consts::WX_REGISTER_ADDR).wrapping_sub(7);
+        let compared: Option<u8> = match self.dot {
+            0...3 => None,
+            4...7 if last_line => Some(self.line),
+            8...11 if last_line => None,
+            _ => Some(self.regs.ly),
+        };
+        let coincidence_flag: u8 = if compared == Some(self.regs.lyc) {
+            0b100
+        } else {
+            0b000
+        };
+        self.regs.stat = (self.regs.stat & 0b1111_1011) | coincidence_flag;
+    }
 
-        let mut is_window: bool = false;
+    // state of the STAT interrupt line.
+    fn stat_interrupt(&self) -> bool {
+        let reg: u8 = self.regs.stat;
+        ((((reg >> 6) & 0b1) == 0b1) && ((reg >> 2) & 0b1 == 0b1)) ||
+        ((((reg >> 5) & 0b1) == 0b1) && (reg & 0b11 == 0b10)) ||
+        ((((reg >> 4) & 0b1) == 0b1) && (reg & 0b11 == 0b01)) ||
+        ((((reg >> 3) & 0b1) == 0b1) && (reg & 0b11 == 0b00))
+    }
+
+    // all the STAT sources are ORed into a single line, and the interrupt is
+    // only requested when it goes from low to high. While one source keeps the
+    // line high, the others can't request it ("STAT blocking").
+    fn update_stat_line(&mut self, memory: &mut Memory) {
+        let stat_line: bool = self.stat_interrupt();
+        if stat_line && !self.stat_line {
+            interrupt::request(interrupt::Interrupt::LCDC, memory);
+        }
+        self.stat_line = stat_line;
+    }
+
+    // runs the lcd for 'dots' dots (4194304Hz, regardless of the cpu speed).
+    // Returns true if a frame was completed.
+    pub fn tick(&mut self, dots: u32, memory: &mut Memory) -> bool {
+        if !self.regs.is_lcd_display_enable() {
+            // nothing happens while the lcd is off, but the blank screen is still
+            // shown at the usual frame rate.
+            self.off_dots += dots;
+            if self.off_dots >= FRAME_DURATION_DOTS {
+                self.off_dots -= FRAME_DURATION_DOTS;
+                return true;
+            }
+            return false;
+        }
+        let mut frame_ready: bool = false;
+        let mut dots: u32 = dots;
+        while dots > 0 {
+            frame_ready |= self.step(memory);
+            dots -= 1;
+            // the dots before the next event only move the position in the line.
+            let idle: u32 = cmp::min((self.next_event_dot() - self.dot - 1) as u32, dots);
+            self.dot += idle as u16;
+            dots -= idle;
+        }
+        frame_ready
+    }
+
+    // the next dot where step has something to do: a mode or line change, or a
+    // change of the coincidence flag. The pixel fifo draws on every dot of mode 3.
+    fn next_event_dot(&self) -> u16 {
+        let mode_end: u16 = match self.regs.mode() {
+            MODE_OAM => cpu::consts::STAT_MODE_2_DURATION_CYCLES as u16,
+            MODE_VRAM => {
+                if self.renderer == Renderer::PixelFifo {
+                    return self.dot + 1;
+                }
+                (cpu::consts::STAT_MODE_2_DURATION_CYCLES +
+                 cpu::consts::STAT_MODE_3_DURATION_CYCLES) as u16
+            }
+            _ => cpu::consts::SCANLINE_DURATION_CYCLES as u16,
+        };
+        match COINCIDENCE_EVENT_DOTS.iter().find(|&&dot| dot > self.dot) {
+            Some(&dot) => cmp::min(dot, mode_end),
+            None => mode_end,
+        }
+    }
+
+    fn step(&mut self, memory: &mut Memory) -> bool {
+        let mut frame_ready: bool = false;
+        self.dot += 1;
+        match self.regs.mode() {
+            MODE_OAM => {
+                if self.dot == cpu::consts::STAT_MODE_2_DURATION_CYCLES as u16 {
+                    self.set_mode(MODE_VRAM);
+                    if self.regs.ly == self.regs.wy {
+                        self.window_y_reached = true;
+                    }
+                    match self.renderer {
+                        Renderer::Scanline => {
+                            self.window_drawn = self.window_start().is_some();
+                            self.update(memory);
+                        }
+                        Renderer::PixelFifo => {
+                            let sprites: Vec<Sprite> = self.oam_scan(memory);
+                            let window_line: Option<u8> = if self.window_start().is_some() {
+                                Some(self.window_line)
+                            } else {
+                                None
+                            };
+                            self.fifo.start_line(&self.regs,
+                                                 sprites,
+                                                 window_line,
+                                                 self.window_whole_line);
+                        }
+                    }
+                }
+            }
+            MODE_VRAM => {
+                let done: bool = match self.renderer {
+                    Renderer::Scanline => {
+                        self.dot ==
+                        (cpu::consts::STAT_MODE_2_DURATION_CYCLES +
+                         cpu::consts::STAT_MODE_3_DURATION_CYCLES) as u16
+                    }
+                    Renderer::PixelFifo => {
+                        if let Some(pixel) = self.fifo.step(&self.regs, memory) {
+                            self.draw_pixel(pixel);
+                        }
+                        self.fifo.is_line_done()
+                    }
+                };
+                if done {
+                    if self.renderer == Renderer::PixelFifo {
+                        self.window_drawn = self.fifo.is_window_drawn();
+                    }
+                    if self.window_drawn {
+                        self.window_line += 1;
+                    }
+                    self.window_whole_line = self.window_drawn && self.regs.wx == 166;
+                    self.set_mode(MODE_HBLANK);
+                }
+            }
+            _ => {
+                if self.dot == cpu::consts::SCANLINE_DURATION_CYCLES as u16 {
+                    self.dot = 0;
+                    self.line += 1;
+                    if self.line == LINES_PER_FRAME {
+                        self.line = 0;
+                        self.window_y_reached = false;
+                        self.window_line = 0;
+                        self.window_whole_line = false;
+                    }
+                    self.regs.ly = self.line;
+                    if self.line == consts::DISPLAY_HEIGHT_PX {
+                        if self.skip_frame {
+                            // the first frame after the lcd is turned on isn't shown.
+                            self.skip_frame = false;
+                            self.clear_screen();
+                        } else {
+                            frame_ready = true;
+                        }
+                        self.set_mode(MODE_VBLANK);
+                        interrupt::request(interrupt::Interrupt::VBlank, memory);
+                    } else if self.line < consts::DISPLAY_HEIGHT_PX {
+                        self.set_mode(MODE_OAM);
+                    }
+                }
+            }
+        }
+        self.update_coincidence_flag();
+        self.update_stat_line(memory);
+        frame_ready
+    }
+
+    // screen column where the window starts on the current line, if it's drawn.
+    // WX 0 to 6 give negative columns: the window's first pixels are cut off.
+    fn window_start(&self) -> Option<i16> {
+        if !self.window_y_reached || !self.regs.is_window_display_on() {
+            None
+        } else if self.window_whole_line {
+            Some(0)
+        } else if self.regs.wx <= 166 {
+            Some(self.regs.wx as i16 - 7)
+        } else {
+            None
+        }
+    }
+
+    fn oam_scan(&self, memory: &Memory) -> Vec<Sprite> {
+        select_sprites(memory, &self.regs, self.regs.ly)
+    }
 
-        let startx: u8 = if bg_on {
+    fn draw_pixel(&mut self, pixel: Pixel) {
+        let bg_on: bool = if pixel.window {
+            self.wn_on
+        } else {
+            self.bg_on
+        };
+        let bg_color: u8 = if bg_on && self.regs.is_bg_window_display_on() {
+            pixel.bg_color
+        } else {
             0
+        };
+        let sprite_visible: bool = self.sprites_on && self.regs.is_sprite_display_on() &&
+                                   pixel.sprite_color != 0 &&
+                                   !(pixel.sprite_behind_bg && bg_color != 0);
+        let index: u8 = if sprite_visible {
+            let pixel_index: u8 = self.regs.sprite_palette(!pixel.sprite_obp1, pixel.sprite_color);
+            sprite_index(pixel.sprite_obp1, pixel_index)
         } else {
-            wx
+            framebuffer::BG_PALETTE + self.regs.bg_window_palette(bg_color)
         };
+        self.set_pixel(pixel.x, index);
+    }
 
-        let (tile_table_addr_pattern_0, is_tile_number_signed) =
-            if ioregister::LCDCRegister::is_tile_data_0(&memory) {
-                (consts::TILE_DATA_TABLE_0_ADDR_START, true)
-            } else {
-                (consts::TILE_DATA_TABLE_1_ADDR_START, false)
-            };
+    // writes a framebuffer index to column x of the current line.
+    fn set_pixel(&mut self, x: u8, index: u8) {
+        let buffer_pos: usize = (self.regs.ly as usize * consts::DISPLAY_WIDTH_PX as usize) +
+                                x as usize;
+        self.frame[buffer_pos] = index;
+    }
+
+    // scanline renderer: draws the whole current line.
+    fn update(&mut self, memory: &Memory) {
+        self.update_line_buffer(memory);
+        self.draw_sprites(memory);
+    }
+
+    fn update_line_buffer(&mut self, memory: &Memory) {
+        // on DMG, LCDC bit 0 clear blanks the window as well as the bg.
+        let bg_wn_on: bool = self.regs.is_bg_window_display_on();
+        let bg_on: bool = bg_wn_on && self.bg_on;
+        let wn_on: bool = bg_wn_on && self.regs.is_window_display_on() && self.wn_on;
+
+        let curr_line: u8 = self.regs.ly;
+        if curr_line >= consts::DISPLAY_HEIGHT_PX {
+            return;
+        }
+
+        // without bg and window the line is left with color 0.
+        let line_start: usize = curr_line as usize * consts::DISPLAY_WIDTH_PX as usize;
+        for pos in line_start..line_start + consts::DISPLAY_WIDTH_PX as usize {
+            self.bg_wn_pixel_indexes[pos] = 0;
+            self.frame[pos] = framebuffer::BG_PALETTE;
+        }
+        if !bg_on && !wn_on {
+            return;
+        }
+        let scx: u8 = self.regs.scx;
+        let scy: u8 = self.regs.scy;
+        let mut ypos: u16 = curr_line.wrapping_add(scy) as u16;
+        let window_start: Option<i16> = if wn_on {
+            self.window_start()
+        } else {
+            None
+        };
+
+        let mut is_window: bool = false;
+
+        let is_tile_number_signed: bool = self.regs.is_tile_data_0();
 
         let mut tile_row: u16 = (ypos / 8) * 32;
         let mut tile_line: u16 = (ypos % 8) * 2;
-        for i in startx..consts::DISPLAY_WIDTH_PX {
-            if wn_on && !is_window && i >= wx && wx < consts::DISPLAY_WIDTH_PX && curr_line >= wy {
-
-                is_window = true;
-                ypos = (curr_line - wy) as u16;
-                tile_row = (ypos / 8) * 32;
-                tile_line = (ypos % 8) * 2;
+        for i in 0..consts::DISPLAY_WIDTH_PX {
+            if let Some(wx) = window_start {
+                if !is_window && i as i16 >= wx {
+                    is_window = true;
+                    ypos = self.window_line as u16;
+                    tile_row = (ypos / 8) * 32;
+                    tile_line = (ypos % 8) * 2;
+                }
             }
 
             let xpos: u16 = if is_window {
-                i.wrapping_sub(wx) as u16
+                (i as i16 - window_start.unwrap()) as u16
             } else {
                 scx.wrapping_add(i) as u16
             };
@@ -103,12 +694,12 @@ impl Graphics {
             }
 
             let addr_start = if is_window {
-                if ioregister::LCDCRegister::is_window_tile_map_display_normal(&memory) {
+                if self.regs.is_window_tile_map_display_normal() {
                     consts::BG_NORMAL_ADDR_START
                 } else {
                     consts::BG_WINDOW_ADDR_START
                 }
-            } else if ioregister::LCDCRegister::is_bg_tile_map_display_normal(&memory) {
+            } else if self.regs.is_bg_tile_map_display_normal() {
                 consts::BG_NORMAL_ADDR_START
             } else {
                 consts::BG_WINDOW_ADDR_START
@@ -116,147 +707,56 @@ impl Graphics {
 
             let tile_col_bg: u16 = xpos >> 3;
             let tile_addr: u16 = addr_start + tile_row + tile_col_bg;
-            let tile_location: u16 = if is_tile_number_signed {
-                let mut tile_number: u16 = util::sign_extend(memory.read_byte(tile_addr));
-                if util::is_neg16(tile_number) {
-                    tile_number = 128 - util::twos_complement(tile_number);
-                } else {
-                    tile_number += 128;
-                }
-                tile_table_addr_pattern_0 + (tile_number * consts::TILE_SIZE_BYTES as u16)
-            } else {
-                tile_table_addr_pattern_0 +
-                (memory.read_byte(tile_addr) as u16 * consts::TILE_SIZE_BYTES as u16)
-            };
-            let tile_col: u16 = xpos % 8;
+            let tile_location: u16 = tiles::tile_addr(memory.read_byte(tile_addr),
+                                                      is_tile_number_signed);
+            let tile_col: u8 = (xpos % 8) as u8;
             // two bytes representing 8 pixel indexes
-            let lhs: u8 = memory.read_byte(tile_location + tile_line) >> (7 - tile_col);
-            let rhs: u8 = memory.read_byte(tile_location + tile_line + 1) >> (7 - tile_col);
-            let pixel_data: u8 = ((rhs << 1) & 0b10) | (lhs & 0b01);
-            let pixel_index: u8 = ioregister::bg_window_palette(pixel_data, memory);
-
-            // Apply palette
-            let (r, g, b) = match pixel_index {
-                0b00 => (255, 255, 255),
-                0b01 => (192, 192, 192),
-                0b10 => (96, 96, 96),
-                0b11 => (0, 0, 0),
-                _ => unreachable!(),
-            };
+            let low: u8 = memory.read_byte(tile_location + tile_line);
+            let high: u8 = memory.read_byte(tile_location + tile_line + 1);
+            let pixel_data: u8 = tiles::pixel_data(low, high, tile_col);
+            let pixel_index: u8 = self.regs.bg_window_palette(pixel_data);
 
             self.bg_wn_pixel_indexes[buffer_pos] = pixel_data;
-
-            let buffer_pos: usize = buffer_pos * 4; //*4 because of RGBA
-
-            self.screen_buffer[buffer_pos] = r;
-            self.screen_buffer[buffer_pos + 1] = g;
-            self.screen_buffer[buffer_pos + 2] = b;
-            self.screen_buffer[buffer_pos + 3] = 255; //alpha
+            self.frame[buffer_pos] = framebuffer::BG_PALETTE + pixel_index;
         }
     }
 
     fn draw_sprites(&mut self, memory: &Memory) {
-        // TODO draw sprites based on X priority.
-        if !ioregister::LCDCRegister::is_sprite_display_on(memory) || !self.sprites_on {
+        if !self.regs.is_sprite_display_on() || !self.sprites_on {
             return;
         }
 
-        let curr_line: u8 = memory.read_byte(cpu::consts::LY_REGISTER_ADDR);
-        if curr_line >= consts::DISPLAY_HEIGHT_PX || !self.sprites_on {
+        let curr_line: u8 = self.regs.ly;
+        if curr_line >= consts::DISPLAY_HEIGHT_PX {
             return;
         }
-        let mut index: u16 = 160; //40*4: 40 sprites that use 4 bytes
-        while index != 0 {
-            index -= 4;
-            let sprite_8_16: bool = ioregister::LCDCRegister::is_sprite_8_16_on(memory);
-            let height: u8 = if sprite_8_16 {
-                16
-            } else {
-                8
-            };
-            let mut y: i16 = memory.read_byte(consts::SPRITE_ATTRIBUTE_TABLE + index) as i16;
-            if y == 0 || y >= 160 {
-                continue;
-            }
-            y -= 16;
-            if ((curr_line as i16) < y) || (curr_line as i16 >= y + height as i16) {
-                // outside sprite
-                continue;
-            }
-
-            let mut x: i16 = memory.read_byte(consts::SPRITE_ATTRIBUTE_TABLE + index + 1) as i16;
-            if x == 0 || x >= 168 {
-                continue;
-            }
-
-            let tile_number: u8 = memory.read_byte(consts::SPRITE_ATTRIBUTE_TABLE + index + 2);
-
-            let tile_location: u16 = consts::SPRITE_PATTERN_TABLE_ADDR_START +
-                                     (tile_number as u16 * consts::TILE_SIZE_BYTES as u16);
 
-            let flags: u8 = memory.read_byte(consts::SPRITE_ATTRIBUTE_TABLE + index + 3);
-            let above_bg: bool = (flags >> 7) & 0b1 == 0b0;
-            let y_flip: bool = (flags >> 6) & 0b1 == 0b1;
-            let x_flip: bool = (flags >> 5) & 0b1 == 0b1;
-            let obp0: bool = (flags >> 4) & 0b1 == 0b0;
-
-            x -= 8;
-            let endx: u8 = if x + 8 >= consts::DISPLAY_WIDTH_PX as i16 {
-                consts::DISPLAY_WIDTH_PX.wrapping_sub(x as u8)
-            } else {
-                8
-            };
-            let tile_line: u8 = (curr_line as i16 - y) as u8;
-            for tile_col in 0..endx {
-                // tile_line*2 because each tile uses 2 bytes per line.
-                let lhs: u8 = memory.read_byte(tile_location + (tile_line as u16 * 2)) >>
-                              (7 - tile_col);
-                let rhs: u8 = memory.read_byte(tile_location + (tile_line as u16 * 2) + 1) >>
-                              (7 - tile_col);
-                let pixel_data: u8 = ((rhs << 1) & 0b10) | (lhs & 0b01);
-                if pixel_data == 0 {
+        // each column belongs to the highest priority sprite with a non
+        // transparent pixel there, even if that pixel ends up behind the bg.
+        let mut line: [Option<(u8, Sprite)>; consts::DISPLAY_WIDTH_PX as usize] =
+            [None; consts::DISPLAY_WIDTH_PX as usize];
+        for sprite in self.oam_scan(memory) {
+            let line_data: (u8, u8) = sprite.line_data(&self.regs, memory);
+            for tile_col in 0..8 {
+                // x is the screen column + 8.
+                let x: i16 = sprite.x as i16 - 8 + tile_col as i16;
+                if x < 0 || x >= consts::DISPLAY_WIDTH_PX as i16 || line[x as usize].is_some() {
                     continue;
                 }
-                let pixel_index: u8 = ioregister::sprite_palette(obp0, pixel_data, memory);
-                let (r, g, b, a) = match pixel_index {
-                    0b00 => (255, 255, 255, 255),
-                    0b01 => (192, 192, 192, 255),
-                    0b10 => (96, 96, 96, 255),
-                    0b11 => (0, 0, 0, 255),
-                    _ => unreachable!(),
-                };
-
-                let mut buffer_pos: usize;
-
-                if y_flip {
-                    buffer_pos =
-                        (y.wrapping_add(height as i16 - 1 - tile_line as i16) as u16) as usize *
-                        consts::DISPLAY_WIDTH_PX as usize;
-                } else {
-                    // y + tile_line = curr_line
-                    buffer_pos = curr_line as usize * consts::DISPLAY_WIDTH_PX as usize;
-                }
-
-                let old_pos: usize = buffer_pos;
-                if x_flip {
-                    buffer_pos += (x.wrapping_add(7 - tile_col as i16) as u16) as usize;
-                } else {
-                    buffer_pos += (x.wrapping_add(tile_col as i16) as u16) as usize;
+                let pixel_data: u8 = sprite.pixel_data(line_data, tile_col);
+                if pixel_data != 0 {
+                    line[x as usize] = Some((pixel_data, sprite));
                 }
+            }
+        }
 
-                if buffer_pos < old_pos {
-                    continue;
-                }
-
-                if above_bg || self.bg_wn_pixel_indexes[buffer_pos] == 0 {
-                    buffer_pos *= 4;
-                    if buffer_pos > self.screen_buffer.len() - 4 {
-                        continue;
-                    }
-                    self.screen_buffer[buffer_pos] = r;
-                    self.screen_buffer[buffer_pos + 1] = g;
-                    self.screen_buffer[buffer_pos + 2] = b;
-                    self.screen_buffer[buffer_pos + 3] = a;
+        for x in 0..consts::DISPLAY_WIDTH_PX {
+            if let Some((pixel_data, sprite)) = line[x as usize] {
+                let buffer_pos: usize = (curr_line as usize * consts::DISPLAY_WIDTH_PX as usize) +
+                                        x as usize;
+                if !sprite.is_behind_bg() || self.bg_wn_pixel_indexes[buffer_pos] == 0 {
+                    let pixel_index: u8 = self.regs.sprite_palette(!sprite.is_obp1(), pixel_data);
+                    self.set_pixel(x, sprite_index(sprite.is_obp1(), pixel_index));
                 }
             }
         }
@@ -275,3 +775,319 @@ impl Graphics {
         println!("sprites: {}", self.sprites_on);
     }
 }
+
+// framebuffer index of a sprite pixel, once it went through OBP0 or OBP1.
+fn sprite_index(obp1: bool, pixel_index: u8) -> u8 {
+    let palette: u8 = if obp1 {
+        framebuffer::OBP1_PALETTE
+    } else {
+        framebuffer::OBP0_PALETTE
+    };
+    palette + pixel_index
+}
+
+// the sprites drawn on a line: the first 10 OAM entries that cover it, sorted by
+// priority. On DMG the one with the lower X wins, then the one that comes first
+// in OAM.
+pub fn select_sprites(memory: &Memory, regs: &Registers, line: u8) -> Vec<Sprite> {
+    let mut sprites: Vec<Sprite> = Vec::new();
+    for index in 0..consts::NUM_SPRITES {
+        let sprite: Sprite = Sprite::from_oam(memory, index);
+        if sprite.is_on_line(regs, line) {
+            sprites.push(sprite);
+            if sprites.len() == consts::MAX_SPRITES_PER_LINE {
+                break;
+            }
+        }
+    }
+    // the sort is stable, so OAM order is kept between equal Xs.
+    sprites.sort_by_key(|sprite| sprite.x);
+    sprites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{select_sprites, Graphics, Registers, Renderer, FRAME_DURATION_DOTS};
+    use cpu::consts;
+    use graphics::consts as graphics_consts;
+    use graphics::framebuffer;
+    use mem::bus::Bus;
+    use mem::mem::Memory;
+
+    fn lcd_on(renderer: Renderer, stat: u8, lyc: u8) -> Graphics {
+        let mut graphics: Graphics = Graphics::default();
+        graphics.set_renderer(renderer);
+        graphics.write_register(consts::STAT_REGISTER_ADDR, stat);
+        graphics.write_register(consts::LYC_REGISTER_ADDR, lyc);
+        graphics.write_register(consts::LCDC_REGISTER_ADDR, 0b1001_0001);
+        graphics
+    }
+
+    // ticking many dots at once has to give the same registers, interrupts and
+    // frames as ticking them one by one.
+    fn compare_batched(renderer: Renderer, stat: u8, lyc: u8) {
+        let mut single: Graphics = lcd_on(renderer, stat, lyc);
+        let mut batched: Graphics = lcd_on(renderer, stat, lyc);
+        let mut single_mem: Memory = Memory::default();
+        let mut batched_mem: Memory = Memory::default();
+
+        let mut elapsed: u32 = 0;
+        let mut chunk: u32 = 1;
+        while elapsed < FRAME_DURATION_DOTS * 2 {
+            let mut single_frame: bool = false;
+            for _ in 0..chunk {
+                single_frame |= single.tick(1, &mut single_mem);
+            }
+            assert_eq!(batched.tick(chunk, &mut batched_mem), single_frame);
+            assert_eq!(batched.regs.ly, single.regs.ly);
+            assert_eq!(batched.regs.stat, single.regs.stat);
+            assert_eq!(batched.dot, single.dot);
+            assert_eq!(batched_mem.read_byte(consts::IF_REGISTER_ADDR),
+                       single_mem.read_byte(consts::IF_REGISTER_ADDR));
+            elapsed += chunk;
+            chunk = chunk % 24 + 4;
+        }
+        assert!(batched.frame() == single.frame());
+    }
+
+    #[test]
+    fn ticking_in_batches_matches_ticking_dot_by_dot() {
+        for &renderer in &[Renderer::Scanline, Renderer::PixelFifo] {
+            compare_batched(renderer, 0b0111_1000, 0);
+            compare_batched(renderer, 0b0100_0000, 153);
+            compare_batched(renderer, 0b0100_0000, 0);
+            compare_batched(renderer, 0b0100_0000, 70);
+        }
+    }
+
+    const STAT_INTERRUPT: u8 = 0b10;
+    const LINE_DOTS: u32 = consts::SCANLINE_DURATION_CYCLES;
+
+    fn stat_requested(memory: &mut Memory) -> bool {
+        let requested: bool = memory.read_byte(consts::IF_REGISTER_ADDR) & STAT_INTERRUPT ==
+                              STAT_INTERRUPT;
+        memory.write_byte(consts::IF_REGISTER_ADDR, 0);
+        requested
+    }
+
+    #[test]
+    fn another_stat_source_going_high_doesnt_request_the_interrupt_again() {
+        // LYC == 0 and mode 0 sources.
+        let mut graphics: Graphics = lcd_on(Renderer::Scanline, 0b0100_1000, 0);
+        let mut memory: Memory = Memory::default();
+
+        graphics.tick(4, &mut memory);
+        assert!(stat_requested(&mut memory));
+        // mode 0 starts while LY == LYC still holds the line high.
+        graphics.tick(LINE_DOTS - 4, &mut memory);
+        assert_eq!(graphics.regs.ly, 1);
+        assert!(!stat_requested(&mut memory));
+        // on line 1 the line went low in mode 2, so mode 0 raises it again.
+        graphics.tick(252, &mut memory);
+        assert_eq!(graphics.regs.mode(), 0);
+        assert!(stat_requested(&mut memory));
+    }
+
+    #[test]
+    fn ly_reads_0_after_the_first_m_cycle_of_line_153() {
+        let mut graphics: Graphics = lcd_on(Renderer::Scanline, 0, 0);
+        let mut memory: Memory = Memory::default();
+        graphics.tick(LINE_DOTS * 153, &mut memory);
+
+        let mut lys: Vec<u8> = Vec::new();
+        for _ in 0..8 {
+            lys.push(graphics.read_register(consts::LY_REGISTER_ADDR));
+            graphics.tick(1, &mut memory);
+        }
+        assert_eq!(lys, vec![153, 153, 153, 153, 0, 0, 0, 0]);
+    }
+
+    // the coincidence flag on each of the first 16 dots of line 153.
+    fn line_153_coincidence(lyc: u8) -> Vec<bool> {
+        let mut graphics: Graphics = lcd_on(Renderer::Scanline, 0b0100_0000, lyc);
+        let mut memory: Memory = Memory::default();
+        graphics.tick(LINE_DOTS * 153, &mut memory);
+        stat_requested(&mut memory);
+
+        let mut flags: Vec<bool> = Vec::new();
+        for _ in 0..16 {
+            flags.push(graphics.read_register(consts::STAT_REGISTER_ADDR) & 0b100 == 0b100);
+            graphics.tick(1, &mut memory);
+        }
+        assert!(stat_requested(&mut memory));
+        flags
+    }
+
+    #[test]
+    fn lyc_153_and_lyc_0_both_match_on_line_153() {
+        let flags: Vec<bool> = line_153_coincidence(153);
+        assert_eq!(&flags[..4], &[false; 4]);
+        assert_eq!(&flags[4..8], &[true; 4]);
+        assert_eq!(&flags[8..], &[false; 8]);
+
+        let flags: Vec<bool> = line_153_coincidence(0);
+        assert_eq!(&flags[..12], &[false; 12]);
+        assert_eq!(&flags[12..], &[true; 4]);
+    }
+
+    // writes OAM entry 'index', with tile 0 and no flags.
+    fn put_sprite(memory: &mut Memory, index: u8, y: u8, x: u8) {
+        let addr: u16 = graphics_consts::SPRITE_ATTRIBUTE_TABLE + index as u16 * 4;
+        memory.write_byte(addr, y);
+        memory.write_byte(addr + 1, x);
+    }
+
+    fn selected_indexes(memory: &Memory, lcdc: u8, line: u8) -> Vec<u8> {
+        let regs: Registers = Registers { lcdc: lcdc, ..Registers::default() };
+        select_sprites(memory, &regs, line).iter().map(|sprite| sprite.index).collect()
+    }
+
+    #[test]
+    fn only_the_first_10_sprites_in_oam_are_drawn_on_a_line() {
+        let mut memory: Memory = Memory::default();
+        for index in 0..12 {
+            // even entries are on line 0, odd ones on line 8.
+            let y: u8 = if index % 2 == 0 { 16 } else { 24 };
+            put_sprite(&mut memory, index, y, 8 + index);
+        }
+        // x doesn't matter: sprites that are off screen still count.
+        for index in 12..20 {
+            put_sprite(&mut memory, index, 16, 0);
+        }
+
+        // 16 to 19 come too late in OAM.
+        assert_eq!(selected_indexes(&memory, 0, 0),
+                   vec![12, 13, 14, 15, 0, 2, 4, 6, 8, 10]);
+        assert_eq!(selected_indexes(&memory, 0, 8), vec![1, 3, 5, 7, 9, 11]);
+    }
+
+    #[test]
+    fn sprites_are_16_lines_tall_in_8x16_mode() {
+        let mut memory: Memory = Memory::default();
+        put_sprite(&mut memory, 0, 16, 8);
+
+        assert_eq!(selected_indexes(&memory, 0b000, 7), vec![0]);
+        assert!(selected_indexes(&memory, 0b000, 8).is_empty());
+        assert_eq!(selected_indexes(&memory, 0b100, 15), vec![0]);
+        assert!(selected_indexes(&memory, 0b100, 16).is_empty());
+    }
+
+    #[test]
+    fn sprites_with_a_lower_x_come_first_then_oam_order() {
+        let mut memory: Memory = Memory::default();
+        put_sprite(&mut memory, 0, 16, 30);
+        put_sprite(&mut memory, 1, 16, 20);
+        put_sprite(&mut memory, 2, 16, 40);
+        put_sprite(&mut memory, 3, 16, 20);
+
+        assert_eq!(selected_indexes(&memory, 0, 0), vec![1, 3, 0, 2]);
+    }
+
+    const WINDOW_ON: u8 = 0b1111_0001; // window map at 0x9C00, tiles at 0x8000.
+    const WINDOW_OFF: u8 = 0b1101_0001;
+
+    // the bg is all color 2. The window tiles have color 3 on their first row
+    // and color 1 on column 4 of the others.
+    fn window_setup(renderer: Renderer, wx: u8) -> (Graphics, Memory) {
+        let mut memory: Memory = Memory::default();
+        for row in 0..8 {
+            memory.write_byte(0x8000 + row * 2 + 1, 0xFF);
+            let window_row: u8 = if row == 0 { 0xFF } else { 0b0000_1000 };
+            memory.write_byte(0x8010 + row * 2, window_row);
+            memory.write_byte(0x8010 + row * 2 + 1, if row == 0 { 0xFF } else { 0x00 });
+        }
+        for tile in 0..0x400 {
+            memory.write_byte(0x9C00 + tile, 1);
+        }
+        let mut graphics: Graphics = Graphics::default();
+        graphics.set_renderer(renderer);
+        graphics.write_register(consts::BGP_REGISTER_ADDR, 0b1110_0100);
+        graphics.write_register(consts::WY_REGISTER_ADDR, 0);
+        graphics.write_register(consts::WX_REGISTER_ADDR, wx);
+        graphics.write_register(consts::LCDC_REGISTER_ADDR, WINDOW_ON);
+        (graphics, memory)
+    }
+
+    fn pixel(graphics: &Graphics, x: usize, y: usize) -> u8 {
+        graphics.frame()[y * graphics_consts::DISPLAY_WIDTH_PX as usize + x]
+    }
+
+    #[test]
+    fn the_window_line_only_advances_on_lines_with_the_window() {
+        for &renderer in &[Renderer::Scanline, Renderer::PixelFifo] {
+            let (mut graphics, mut memory) = window_setup(renderer, 7);
+            graphics.tick(LINE_DOTS * 10, &mut memory);
+            assert_eq!(graphics.window_line, 10);
+            // turned off for lines 10 to 19, and on again.
+            graphics.write_register(consts::LCDC_REGISTER_ADDR, WINDOW_OFF);
+            graphics.tick(LINE_DOTS * 10, &mut memory);
+            assert_eq!(graphics.window_line, 10);
+            graphics.write_register(consts::LCDC_REGISTER_ADDR, WINDOW_ON);
+            graphics.tick(LINE_DOTS * 10, &mut memory);
+            assert_eq!(graphics.window_line, 20);
+
+            assert_eq!(pixel(&graphics, 0, 8), 3);
+            assert_eq!(pixel(&graphics, 0, 15), 2);
+            // line 16 of the window is drawn on line 26, not 16 or 24.
+            assert_eq!(pixel(&graphics, 0, 24), 0);
+            assert_eq!(pixel(&graphics, 0, 26), 3);
+        }
+    }
+
+    #[test]
+    fn the_window_is_cut_off_on_the_left_with_wx_below_7() {
+        for &renderer in &[Renderer::Scanline, Renderer::PixelFifo] {
+            let (mut graphics, mut memory) = window_setup(renderer, 3);
+            graphics.tick(LINE_DOTS * 2, &mut memory);
+
+            // the first 4 columns of the window aren't shown.
+            let line: Vec<u8> = (0..8).map(|x| pixel(&graphics, x, 1)).collect();
+            assert_eq!(line, vec![1, 0, 0, 0, 0, 0, 0, 0]);
+
+            let (mut graphics, mut memory) = window_setup(renderer, 7);
+            graphics.tick(LINE_DOTS * 2, &mut memory);
+            let line: Vec<u8> = (0..8).map(|x| pixel(&graphics, x, 1)).collect();
+            assert_eq!(line, vec![0, 0, 0, 0, 1, 0, 0, 0]);
+        }
+    }
+
+    fn is_blank(graphics: &Graphics) -> bool {
+        graphics.frame().iter().all(|&index| index == framebuffer::LCD_OFF)
+    }
+
+    #[test]
+    fn the_lcd_stays_on_line_0_in_mode_0_while_off() {
+        let mut graphics: Graphics = lcd_on(Renderer::Scanline, 0, 0);
+        let mut memory: Memory = Memory::default();
+        graphics.tick(LINE_DOTS * 50 + 100, &mut memory);
+        assert_eq!(graphics.regs.mode(), 0b11);
+
+        graphics.write_register(consts::LCDC_REGISTER_ADDR, 0b0001_0001);
+        assert!(is_blank(&graphics));
+        for _ in 0..3 {
+            // a blank frame is still given at the usual rate.
+            assert!(!graphics.tick(FRAME_DURATION_DOTS - 1, &mut memory));
+            assert!(graphics.tick(1, &mut memory));
+            assert_eq!(graphics.read_register(consts::LY_REGISTER_ADDR), 0);
+            assert_eq!(graphics.regs.mode(), 0b00);
+            assert!(is_blank(&graphics));
+        }
+    }
+
+    #[test]
+    fn the_first_frame_after_turning_the_lcd_on_is_not_shown() {
+        let mut graphics: Graphics = lcd_on(Renderer::Scanline, 0, 0);
+        let mut memory: Memory = Memory::default();
+        assert_eq!(graphics.regs.mode(), 0b10);
+
+        // the lines are drawn, but the frame is blanked when it ends.
+        graphics.tick(LINE_DOTS * 10, &mut memory);
+        assert!(!is_blank(&graphics));
+        let to_vblank: u32 = LINE_DOTS * (graphics_consts::DISPLAY_HEIGHT_PX as u32 - 10);
+        assert!(!graphics.tick(to_vblank, &mut memory));
+        assert!(is_blank(&graphics));
+
+        assert!(graphics.tick(FRAME_DURATION_DOTS, &mut memory));
+        assert!(!is_blank(&graphics));
+    }
+}
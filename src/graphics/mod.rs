@@ -1,2 +1,7 @@
 pub mod graphics;
+pub mod fifo;
+pub mod palette;
+pub mod framebuffer;
+pub mod tiles;
+pub mod viewer;
 pub mod consts;
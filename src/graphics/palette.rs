@@ -0,0 +1,201 @@
+use std::fs::File;
+use std::path::Path;
+
+use rustc_serialize::json::Json;
+
+pub type Rgb = (u8, u8, u8);
+
+// The 4 colors a shade (0 = lightest, 3 = darkest) is shown as.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Palette {
+    pub colors: [Rgb; 4],
+}
+
+impl Palette {
+    pub fn gray() -> Palette {
+        Palette {
+            colors: [(255, 255, 255),
+                     (192, 192, 192),
+                     (96, 96, 96),
+                     (0, 0, 0)],
+        }
+    }
+
+    // the original DMG screen.
+    pub fn green() -> Palette {
+        Palette {
+            colors: [(0x9B, 0xBC, 0x0F),
+                     (0x8B, 0xAC, 0x0F),
+                     (0x30, 0x62, 0x30),
+                     (0x0F, 0x38, 0x0F)],
+        }
+    }
+
+    pub fn pocket() -> Palette {
+        Palette {
+            colors: [(0xC4, 0xCF, 0xA1),
+                     (0x8B, 0x95, 0x6D),
+                     (0x4D, 0x53, 0x3C),
+                     (0x1F, 0x1F, 0x1F)],
+        }
+    }
+
+    // the backlit Game Boy Light.
+    pub fn light() -> Palette {
+        Palette {
+            colors: [(0x00, 0xB5, 0x81),
+                     (0x00, 0x9A, 0x71),
+                     (0x00, 0x69, 0x4A),
+                     (0x00, 0x4F, 0x3B)],
+        }
+    }
+
+    pub fn from_preset(name: &str) -> Option<Palette> {
+        match name {
+            "gray" => Some(Palette::gray()),
+            "green" => Some(Palette::green()),
+            "pocket" => Some(Palette::pocket()),
+            "light" => Some(Palette::light()),
+            _ => None,
+        }
+    }
+
+    // either a preset name or a list of 4 "#RRGGBB" colors.
+    fn from_json(json: &Json) -> Result<Palette, String> {
+        if let Some(name) = json.as_string() {
+            return Palette::from_preset(name)
+                       .ok_or_else(|| format!("unknown palette '{}'", name));
+        }
+        let colors = match json.as_array() {
+            Some(colors) if colors.len() == 4 => colors,
+            _ => return Err("a palette must be a preset name or 4 colors".to_owned()),
+        };
+        let mut palette: Palette = Palette::gray();
+        for (i, color) in colors.iter().enumerate() {
+            palette.colors[i] = match color.as_string().and_then(parse_color) {
+                Some(rgb) => rgb,
+                None => return Err(format!("invalid color {}", color)),
+            };
+        }
+        Ok(palette)
+    }
+}
+
+fn parse_color(color: &str) -> Option<Rgb> {
+    // from_str_radix alone would take a sign too.
+    if color.len() != 7 || !color.starts_with('#') ||
+       !color[1..].chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match u32::from_str_radix(&color[1..], 16) {
+        Ok(value) => Some(((value >> 16) as u8, (value >> 8) as u8, value as u8)),
+        Err(_) => None,
+    }
+}
+
+// Palettes used for the background/window and the two sprite palettes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Palettes {
+    pub bg: Palette,
+    pub obp0: Palette,
+    pub obp1: Palette,
+}
+
+impl Default for Palettes {
+    fn default() -> Palettes {
+        Palettes::uniform(Palette::gray())
+    }
+}
+
+impl Palettes {
+    pub fn uniform(palette: Palette) -> Palettes {
+        Palettes {
+            bg: palette,
+            obp0: palette,
+            obp1: palette,
+        }
+    }
+
+    // A json file like:
+    //     {"bg": "green", "obp0": ["#FFFFFF", "#AAAAAA", "#555555", "#000000"]}
+    // Missing palettes default to the bg one, and bg defaults to gray.
+    pub fn load(path: &Path) -> Result<Palettes, String> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) => return Err(format!("{}", error)),
+        };
+        let json: Json = match Json::from_reader(&mut file) {
+            Ok(json) => json,
+            Err(error) => return Err(format!("{}", error)),
+        };
+        let bg: Palette = match json.find("bg") {
+            Some(palette) => try!(Palette::from_json(palette)),
+            None => Palette::gray(),
+        };
+        let mut palettes: Palettes = Palettes::uniform(bg);
+        if let Some(palette) = json.find("obp0") {
+            palettes.obp0 = try!(Palette::from_json(palette));
+        }
+        if let Some(palette) = json.find("obp1") {
+            palettes.obp1 = try!(Palette::from_json(palette));
+        }
+        Ok(palettes)
+    }
+
+    // shade has to have a value from 0 to 3.
+    pub fn color(palette: &Palette, shade: u8) -> Rgb {
+        palette.colors[shade as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_color, Palette, Palettes};
+    use std::path::PathBuf;
+    use util::temp_file;
+
+    fn load(name: &str, json: &str) -> Result<Palettes, String> {
+        let path: PathBuf = temp_file::write(name, json.as_bytes());
+        let palettes: Result<Palettes, String> = Palettes::load(&path);
+        temp_file::take(&path);
+        palettes
+    }
+
+    #[test]
+    fn colors_are_rrggbb_in_hex() {
+        assert_eq!(parse_color("#FF8000"), Some((0xFF, 0x80, 0x00)));
+        assert_eq!(parse_color("#0a0b0c"), Some((0x0A, 0x0B, 0x0C)));
+        assert_eq!(parse_color("FF8000"), None);
+        assert_eq!(parse_color("#FF800"), None);
+        assert_eq!(parse_color("#FF80000"), None);
+        assert_eq!(parse_color("#GG8000"), None);
+        assert_eq!(parse_color("#+F8000"), None);
+    }
+
+    #[test]
+    fn missing_palettes_default_to_the_bg_one() {
+        let palettes: Palettes =
+            load("palettes.json",
+                 r##"{"bg": "green", "obp1": ["#FFFFFF", "#AAAAAA", "#555555", "#000000"]}"##)
+                .unwrap();
+        assert_eq!(palettes.bg, Palette::green());
+        assert_eq!(palettes.obp0, Palette::green());
+        assert_eq!(palettes.obp1.colors,
+                   [(0xFF, 0xFF, 0xFF), (0xAA, 0xAA, 0xAA), (0x55, 0x55, 0x55), (0, 0, 0)]);
+
+        let palettes: Palettes = load("empty.json", "{}").unwrap();
+        assert_eq!(palettes, Palettes::default());
+    }
+
+    #[test]
+    fn bad_palette_files_are_errors() {
+        assert!(load("malformed.json", r#"{"bg": "green""#).is_err());
+        assert_eq!(load("preset.json", r#"{"bg": "blue"}"#),
+                   Err("unknown palette 'blue'".to_owned()));
+        assert!(load("three.json", r##"{"bg": ["#FFFFFF", "#AAAAAA", "#555555"]}"##).is_err());
+        assert_eq!(load("hex.json",
+                        r##"{"obp0": ["#FFFFFF", "#AAAAAA", "#55555Z", "#000000"]}"##),
+                   Err("invalid color \"#55555Z\"".to_owned()));
+        assert!(Palettes::load(&temp_file::path("missing.json")).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use graphics::consts;
+use super::super::mem::mem::Memory;
+
+// Tiles are 8x8 pixels in 2bpp: each row is two bytes, the first one with the
+// low bit of every pixel and the second one with the high bit. Bit 7 is the
+// leftmost pixel.
+pub fn pixel_data(low: u8, high: u8, col: u8) -> u8 {
+    let bit: u8 = 7 - col;
+    (((high >> bit) & 0b1) << 1) | ((low >> bit) & 0b1)
+}
+
+// address of a bg/window tile. With signed numbers (LCDC bit 4 cleared) tile 0
+// is at 0x9000 and tiles -128 to -1 are at 0x8800 to 0x8FF0.
+pub fn tile_addr(tile_number: u8, signed: bool) -> u16 {
+    if signed {
+        consts::TILE_DATA_TABLE_0_ADDR_START +
+        (tile_number as i8 as i16 + 128) as u16 * consts::TILE_SIZE_BYTES as u16
+    } else {
+        consts::TILE_DATA_TABLE_1_ADDR_START +
+        tile_number as u16 * consts::TILE_SIZE_BYTES as u16
+    }
+}
+
+// the 8 pixel_data of a tile row.
+pub fn decode_row(memory: &Memory, tile_addr: u16, row: u8) -> [u8; 8] {
+    let low: u8 = memory.read_byte(tile_addr + row as u16 * 2);
+    let high: u8 = memory.read_byte(tile_addr + row as u16 * 2 + 1);
+    let mut pixels: [u8; 8] = [0; 8];
+    for col in 0..8 {
+        pixels[col as usize] = pixel_data(low, high, col);
+    }
+    pixels
+}
@@ -0,0 +1,322 @@
+use graphics::consts;
+use graphics::tiles;
+use graphics::framebuffer;
+use graphics::framebuffer::PixelFormat;
+use graphics::graphics::{Registers, Sprite};
+use graphics::palette::{Palettes, Rgb};
+use super::super::mem::mem::Memory;
+
+// Debug views of the video memory. They are drawn with the same framebuffer
+// indexes the lcd uses, so the palettes apply to them too.
+pub struct Image {
+    pub width: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width: width,
+            pixels: vec![framebuffer::BG_PALETTE; width * height],
+        }
+    }
+
+    pub fn to_rgba(&self, palettes: &Palettes) -> Vec<u8> {
+        let mut rgba: Vec<u8> = Vec::new();
+        framebuffer::convert(&self.pixels, palettes, PixelFormat::Rgba8888, &mut rgba);
+        rgba
+    }
+
+    // draws the 8x8 tile at tile_addr with its top left corner at (x, y).
+    fn draw_tile(&mut self,
+                 memory: &Memory,
+                 tile_addr: u16,
+                 x: usize,
+                 y: usize,
+                 palette: TilePalette,
+                 regs: &Registers) {
+        for row in 0..8 {
+            let pixels: [u8; 8] = tiles::decode_row(memory, tile_addr, row);
+            for (col, pixel_data) in pixels.iter().enumerate() {
+                let pos: usize = (y + row as usize) * self.width + x + col;
+                self.pixels[pos] = palette.index(regs, *pixel_data);
+            }
+        }
+    }
+}
+
+// How the tiles' pixel_data are shown.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TilePalette {
+    // pixel_data as the shade, without BGP/OBP.
+    Raw,
+    Bgp,
+    Obp0,
+    Obp1,
+}
+
+impl TilePalette {
+    pub fn next(&self) -> TilePalette {
+        match *self {
+            TilePalette::Raw => TilePalette::Bgp,
+            TilePalette::Bgp => TilePalette::Obp0,
+            TilePalette::Obp0 => TilePalette::Obp1,
+            TilePalette::Obp1 => TilePalette::Raw,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TilePalette> {
+        match name {
+            "raw" => Some(TilePalette::Raw),
+            "bgp" => Some(TilePalette::Bgp),
+            "obp0" => Some(TilePalette::Obp0),
+            "obp1" => Some(TilePalette::Obp1),
+            _ => None,
+        }
+    }
+
+    fn index(&self, regs: &Registers, pixel_data: u8) -> u8 {
+        match *self {
+            TilePalette::Raw => framebuffer::BG_PALETTE + pixel_data,
+            TilePalette::Bgp => framebuffer::BG_PALETTE + regs.bg_window_palette(pixel_data),
+            TilePalette::Obp0 => framebuffer::OBP0_PALETTE + regs.sprite_palette(true, pixel_data),
+            TilePalette::Obp1 => framebuffer::OBP1_PALETTE + regs.sprite_palette(false, pixel_data),
+        }
+    }
+}
+
+// 0x8000 to 0x97FF. The CGB has another bank with as many tiles, which will
+// show up here once its VRAM banking is emulated.
+pub const NUM_TILES: usize = 384;
+pub const TILE_SHEET_COLUMNS: usize = 16;
+pub const TILE_SHEET_WIDTH: usize = TILE_SHEET_COLUMNS * 8;
+pub const TILE_SHEET_HEIGHT: usize = NUM_TILES / TILE_SHEET_COLUMNS * 8;
+
+// all the tiles in VRAM, in address order, 16 per row.
+pub fn tile_sheet(memory: &Memory, regs: &Registers, palette: TilePalette) -> Image {
+    let mut image: Image = Image::new(TILE_SHEET_WIDTH, TILE_SHEET_HEIGHT);
+    for tile in 0..NUM_TILES {
+        let x: usize = (tile % TILE_SHEET_COLUMNS) * 8;
+        let y: usize = (tile / TILE_SHEET_COLUMNS) * 8;
+        image.draw_tile(memory, tile_sheet_addr(tile), x, y, palette, regs);
+    }
+    image
+}
+
+pub fn tile_sheet_addr(tile: usize) -> u16 {
+    consts::TILE_DATA_TABLE_1_ADDR_START + (tile * consts::TILE_SIZE_BYTES) as u16
+}
+
+// the tile under the pixel (x, y) of the tile sheet.
+pub fn tile_sheet_tile_at(x: usize, y: usize) -> Option<usize> {
+    if x < TILE_SHEET_WIDTH && y < TILE_SHEET_HEIGHT {
+        Some((y / 8) * TILE_SHEET_COLUMNS + x / 8)
+    } else {
+        None
+    }
+}
+
+// both 32x32 tile maps side by side: 0x9800 on the left and 0x9C00 on the right.
+pub const TILE_MAP_SIZE: usize = 256;
+pub const TILE_MAPS_WIDTH: usize = TILE_MAP_SIZE * 2;
+pub const TILE_MAPS_HEIGHT: usize = TILE_MAP_SIZE;
+
+fn tile_map_start(map: usize) -> u16 {
+    if map == 0 {
+        consts::BG_NORMAL_ADDR_START
+    } else {
+        consts::BG_WINDOW_ADDR_START
+    }
+}
+
+// the tiles are the ones the lcd would use with the current LCDC addressing mode.
+pub fn tile_maps(memory: &Memory, regs: &Registers) -> Image {
+    let mut image: Image = Image::new(TILE_MAPS_WIDTH, TILE_MAPS_HEIGHT);
+    for map in 0..2 {
+        for tile in 0..32 * 32 {
+            let (x, y, tile_addr) = tile_map_tile(memory, regs, map, tile);
+            image.draw_tile(memory,
+                            tile_addr,
+                            map * TILE_MAP_SIZE + x,
+                            y,
+                            TilePalette::Bgp,
+                            regs);
+        }
+    }
+    image
+}
+
+// position in the map and address of the tile data of one of the map's tiles.
+fn tile_map_tile(memory: &Memory,
+                 regs: &Registers,
+                 map: usize,
+                 tile: usize)
+                 -> (usize, usize, u16) {
+    let tile_number: u8 = memory.read_byte(tile_map_start(map) + tile as u16);
+    ((tile % 32) * 8,
+     (tile / 32) * 8,
+     tiles::tile_addr(tile_number, regs.is_tile_data_0()))
+}
+
+// map address, tile number and tile data address under the pixel (x, y) of the
+// tile maps picture.
+pub fn tile_maps_tile_at(memory: &Memory,
+                         regs: &Registers,
+                         x: usize,
+                         y: usize)
+                         -> Option<(u16, u8, u16)> {
+    if x >= TILE_MAPS_WIDTH || y >= TILE_MAPS_HEIGHT {
+        return None;
+    }
+    let map: usize = x / TILE_MAP_SIZE;
+    let tile: usize = (y / 8) * 32 + (x % TILE_MAP_SIZE) / 8;
+    let map_addr: u16 = tile_map_start(map) + tile as u16;
+    let (_, _, tile_addr) = tile_map_tile(memory, regs, map, tile);
+    Some((map_addr, memory.read_byte(map_addr), tile_addr))
+}
+
+// An outline drawn over a 256x256 tile map. It wraps around the map's edges,
+// like the lcd does when scrolling.
+pub struct Outline {
+    pub map: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub color: Rgb,
+}
+
+// the part of the maps on the screen (SCX, SCY) and the part of the window
+// that's visible (from WX, WY).
+pub fn tile_maps_outlines(regs: &Registers) -> Vec<Outline> {
+    let bg_map: usize = if regs.is_bg_tile_map_display_normal() {
+        0
+    } else {
+        1
+    };
+    let mut outlines: Vec<Outline> = vec![Outline {
+                                              map: bg_map,
+                                              x: regs.scx as usize,
+                                              y: regs.scy as usize,
+                                              width: consts::DISPLAY_WIDTH_PX as usize,
+                                              height: consts::DISPLAY_HEIGHT_PX as usize,
+                                              color: (255, 0, 0),
+                                          }];
+    if regs.is_window_display_on() && regs.wx <= 166 && regs.wy < consts::DISPLAY_HEIGHT_PX {
+        let window_map: usize = if regs.is_window_tile_map_display_normal() {
+            0
+        } else {
+            1
+        };
+        // WX below 7 cuts off the window's leftmost columns.
+        let (x, screen_x): (usize, usize) = if regs.wx < 7 {
+            (7 - regs.wx as usize, 0)
+        } else {
+            (0, regs.wx as usize - 7)
+        };
+        outlines.push(Outline {
+            map: window_map,
+            x: x,
+            y: 0,
+            width: consts::DISPLAY_WIDTH_PX as usize - screen_x,
+            height: (consts::DISPLAY_HEIGHT_PX - regs.wy) as usize,
+            color: (0, 0, 255),
+        });
+    }
+    outlines
+}
+
+fn set_rgba(rgba: &mut [u8], image_width: usize, x: usize, y: usize, color: Rgb) {
+    let pos: usize = (y * image_width + x) * 4;
+    let (r, g, b) = color;
+    rgba[pos] = r;
+    rgba[pos + 1] = g;
+    rgba[pos + 2] = b;
+    rgba[pos + 3] = 255;
+}
+
+pub fn draw_outlines(rgba: &mut [u8], image_width: usize, outlines: &[Outline]) {
+    for outline in outlines {
+        let mut set = |x: usize, y: usize| {
+            set_rgba(rgba,
+                     image_width,
+                     outline.map * TILE_MAP_SIZE + x % TILE_MAP_SIZE,
+                     y % TILE_MAP_SIZE,
+                     outline.color);
+        };
+        for i in 0..outline.width {
+            set(outline.x + i, outline.y);
+            set(outline.x + i, outline.y + outline.height - 1);
+        }
+        for i in 0..outline.height {
+            set(outline.x, outline.y + i);
+            set(outline.x + outline.width - 1, outline.y + i);
+        }
+    }
+}
+
+// all 40 OAM entries, 8 per row, each one in a cell with its tile (or tiles, in
+// 8x16 mode) as it's shown on the screen: flipped and with its palette.
+pub const OAM_SHEET_COLUMNS: usize = 8;
+const OAM_CELL_WIDTH: usize = 16;
+const OAM_CELL_HEIGHT: usize = 24;
+pub const OAM_SHEET_WIDTH: usize = OAM_SHEET_COLUMNS * OAM_CELL_WIDTH;
+pub const OAM_SHEET_HEIGHT: usize = consts::NUM_SPRITES as usize / OAM_SHEET_COLUMNS *
+                                    OAM_CELL_HEIGHT;
+
+fn oam_cell(index: u8) -> (usize, usize) {
+    ((index as usize % OAM_SHEET_COLUMNS) * OAM_CELL_WIDTH,
+     (index as usize / OAM_SHEET_COLUMNS) * OAM_CELL_HEIGHT)
+}
+
+pub fn oam_sheet(memory: &Memory, regs: &Registers) -> Image {
+    let mut image: Image = Image::new(OAM_SHEET_WIDTH, OAM_SHEET_HEIGHT);
+    for index in 0..consts::NUM_SPRITES {
+        let sprite: Sprite = Sprite::from_oam(memory, index);
+        let palette: TilePalette = if sprite.is_obp1() {
+            TilePalette::Obp1
+        } else {
+            TilePalette::Obp0
+        };
+        let (cell_x, cell_y) = oam_cell(index);
+        for row in 0..Sprite::height(regs) {
+            let row_data: (u8, u8) = sprite.row_data(regs, memory, row);
+            for col in 0..8 {
+                let pixel_data: u8 = sprite.pixel_data(row_data, col);
+                if pixel_data != 0 {
+                    let pos: usize = (cell_y + 4 + row as usize) * image.width + cell_x + 4 +
+                                     col as usize;
+                    image.pixels[pos] = palette.index(regs, pixel_data);
+                }
+            }
+        }
+    }
+    image
+}
+
+// the OAM index of the cell under the pixel (x, y) of the OAM sheet.
+pub fn oam_sheet_sprite_at(x: usize, y: usize) -> Option<u8> {
+    if x < OAM_SHEET_WIDTH && y < OAM_SHEET_HEIGHT {
+        Some(((y / OAM_CELL_HEIGHT) * OAM_SHEET_COLUMNS + x / OAM_CELL_WIDTH) as u8)
+    } else {
+        None
+    }
+}
+
+// outlines the cells of the given sprites, e.g. the ones selected on a line.
+pub fn draw_oam_selection(rgba: &mut [u8], sprites: &[Sprite]) {
+    for sprite in sprites {
+        let (cell_x, cell_y) = oam_cell(sprite.index);
+        let mut set = |x: usize, y: usize| {
+            set_rgba(rgba, OAM_SHEET_WIDTH, cell_x + x, cell_y + y, (255, 0, 0));
+        };
+        for i in 0..OAM_CELL_WIDTH {
+            set(i, 0);
+            set(i, OAM_CELL_HEIGHT - 1);
+        }
+        for i in 0..OAM_CELL_HEIGHT {
+            set(0, i);
+            set(OAM_CELL_WIDTH - 1, i);
+        }
+    }
+}
@@ -3,34 +3,219 @@
 
 extern crate sdl2;
 extern crate time;
+extern crate rustc_serialize;
 
 mod graphics;
+mod apu;
 mod debugger;
+mod debug_window;
+mod recorder;
+mod wav_dump;
 mod cpu;
 mod mem;
 mod util;
 mod gebemula;
-mod timeline;
+mod system;
+mod sm83;
 
 use std::env;
+use std::io;
 use std::io::Read;
 use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process;
 
 use gebemula::Gebemula;
+use graphics::graphics::Renderer;
+use graphics::palette::{Palette, Palettes};
+use graphics::viewer::TilePalette;
+
+struct Options {
+    renderer: Renderer,
+    palettes: Palettes,
+    // runs this many frames without a window, then writes the dumps.
+    frames: Option<u32>,
+    dump_tiles: Option<PathBuf>,
+    dump_tile_maps: Option<PathBuf>,
+    dump_oam: Option<PathBuf>,
+    oam_line: u8,
+    tile_palette: TilePalette,
+    screenshot_dir: PathBuf,
+    screenshot_scale: u32,
+    screenshot_frames: Vec<u32>,
+    record: Option<PathBuf>,
+    dump_wav: Option<PathBuf>,
+    wav_stems: bool,
+    files: Vec<String>,
+}
+
+fn load_palettes(value: &str) -> Result<Palettes, String> {
+    match Palette::from_preset(value) {
+        Some(palette) => Ok(Palettes::uniform(palette)),
+        None => Palettes::load(Path::new(value)),
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options: Options = Options {
+        renderer: Renderer::Scanline,
+        palettes: Palettes::default(),
+        frames: None,
+        dump_tiles: None,
+        dump_tile_maps: None,
+        dump_oam: None,
+        oam_line: 0,
+        tile_palette: TilePalette::Raw,
+        screenshot_dir: PathBuf::from("."),
+        screenshot_scale: 1,
+        screenshot_frames: Vec::new(),
+        record: None,
+        dump_wav: None,
+        wav_stems: false,
+        files: Vec::new(),
+    };
+    for arg in args {
+        // --name=value
+        let (name, value): (&str, &str) = match arg.find('=') {
+            Some(pos) => (&arg[..pos], &arg[pos + 1..]),
+            None => (&arg[..], ""),
+        };
+        match name {
+            "--fifo" => options.renderer = Renderer::PixelFifo,
+            "--palette" => {
+                options.palettes = match load_palettes(value) {
+                    Ok(palettes) => palettes,
+                    Err(error) => {
+                        return Err(format!("couldn't load palette {}: {}", value, error))
+                    }
+                };
+            }
+            "--frames" => {
+                options.frames = match value.parse::<u32>() {
+                    Ok(frames) => Some(frames),
+                    Err(_) => return Err(format!("invalid number of frames: {}", value)),
+                };
+            }
+            "--dump-tiles" => options.dump_tiles = Some(PathBuf::from(value)),
+            "--dump-tile-maps" => options.dump_tile_maps = Some(PathBuf::from(value)),
+            "--dump-oam" => options.dump_oam = Some(PathBuf::from(value)),
+            "--oam-line" => {
+                options.oam_line = match value.parse::<u8>() {
+                    Ok(line) => line,
+                    Err(_) => return Err(format!("invalid line: {}", value)),
+                };
+            }
+            "--tile-palette" => {
+                options.tile_palette = match TilePalette::from_name(value) {
+                    Some(palette) => palette,
+                    None => return Err(format!("invalid tile palette: {}", value)),
+                };
+            }
+            "--screenshot-dir" => options.screenshot_dir = PathBuf::from(value),
+            "--screenshot-scale" => {
+                options.screenshot_scale = match value.parse::<u32>() {
+                    Ok(scale) if scale >= 1 => scale,
+                    _ => return Err(format!("invalid scale: {}", value)),
+                };
+            }
+            "--screenshot-at" => {
+                for frame in value.split(',') {
+                    match frame.parse::<u32>() {
+                        Ok(frame) => options.screenshot_frames.push(frame),
+                        Err(_) => return Err(format!("invalid frame: {}", frame)),
+                    }
+                }
+            }
+            "--record" => options.record = Some(PathBuf::from(value)),
+            "--dump-wav" => options.dump_wav = Some(PathBuf::from(value)),
+            "--wav-stems" => options.wav_stems = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => options.files.push(arg.clone()),
+        }
+    }
+    if options.wav_stems && options.dump_wav.is_none() {
+        return Err("--wav-stems needs --dump-wav".to_owned());
+    }
+    if options.frames.is_none() &&
+       (options.dump_tiles.is_some() || options.dump_tile_maps.is_some() ||
+        options.dump_oam.is_some()) {
+        return Err("dumps are only written when running headless with --frames".to_owned());
+    }
+    Ok(options)
+}
+
+fn exit_on_error(path: &Path, result: io::Result<()>) {
+    if let Err(error) = result {
+        println!("Couldn't write {}: {}", path.display(), error);
+        process::exit(1);
+    }
+}
 
 fn main() {
     let args: Vec<_> = env::args().collect();
-    if args.len() == 3 {
+    if args.len() >= 3 && args[1] == "--sm83" {
+        // gebemula --sm83 <json_file|json_dir>...
+        if !sm83::run(&args[2..]) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    // gebemula [options] <bootstrap_rom> <game_rom>
+    let options: Options = match parse_options(&args[1..]) {
+        Ok(options) => options,
+        Err(error) => {
+            println!("{}", error);
+            process::exit(1);
+        }
+    };
+
+    if options.files.len() == 2 {
         let mut bootstrap_data: Vec<u8> = Vec::new();
-        File::open(&args[1]).unwrap().read_to_end(&mut bootstrap_data).unwrap();
+        File::open(&options.files[0]).unwrap().read_to_end(&mut bootstrap_data).unwrap();
 
         let mut game_data: Vec<u8> = Vec::new();
-        File::open(&args[2]).unwrap().read_to_end(&mut game_data).unwrap();
+        File::open(&options.files[1]).unwrap().read_to_end(&mut game_data).unwrap();
 
         let mut gebemula: Gebemula = Gebemula::default();
+        gebemula.set_renderer(options.renderer);
+        gebemula.set_palettes(options.palettes);
+        gebemula.set_tile_palette(options.tile_palette);
+        gebemula.set_oam_line(options.oam_line);
+        gebemula.set_screenshot_dir(&options.screenshot_dir);
+        gebemula.set_screenshot_scale(options.screenshot_scale);
+        gebemula.set_screenshot_frames(&options.screenshot_frames);
         gebemula.load_game_rom(&game_data);
         gebemula.load_bootstrap_rom(&bootstrap_data);
-        gebemula.run_sdl();
+        if let Some(ref path) = options.record {
+            exit_on_error(path, gebemula.start_recording(path));
+        }
+        if let Some(ref path) = options.dump_wav {
+            exit_on_error(path, gebemula.start_wav_dump(path, options.wav_stems));
+        }
+        match options.frames {
+            Some(frames) => {
+                gebemula.run_headless(frames);
+                if let Some(ref path) = options.dump_tiles {
+                    exit_on_error(path, gebemula.dump_tiles(path));
+                }
+                if let Some(ref path) = options.dump_tile_maps {
+                    exit_on_error(path, gebemula.dump_tile_maps(path));
+                }
+                if let Some(ref path) = options.dump_oam {
+                    exit_on_error(path, gebemula.dump_oam(path));
+                }
+            }
+            None => gebemula.run_sdl(),
+        }
+        // also finishes a recording started with F9.
+        if let Err(error) = gebemula.stop_recording() {
+            println!("Couldn't finish recording: {}", error);
+            process::exit(1);
+        }
+        if let Some(ref path) = options.dump_wav {
+            exit_on_error(path, gebemula.stop_wav_dump());
+        }
     } else {
         println!("Invalid number of arguments.");
     }
@@ -0,0 +1,14 @@
+// Anything the cpu can run against. Memory implements it with the whole DMG
+// memory map, but test harnesses, tracers or other systems can supply their own.
+pub trait Bus {
+    // &mut so a bus can count or log the accesses, or give reads side effects.
+    fn read_byte(&mut self, address: u16) -> u8;
+    fn write_byte(&mut self, address: u16, value: u8);
+    // lets the rest of the system know that 'cycles' cpu cycles have passed.
+    fn tick(&mut self, cycles: u32);
+    // called by STOP: performs the CGB speed switch if it was armed through KEY1.
+    // Returns true if the speed was switched, in which case the cpu doesn't stop.
+    fn speed_switch(&mut self) -> bool {
+        false
+    }
+}
@@ -1,5 +1,6 @@
 pub const GAME_TITLE_ADDR_START: u16 = 0x134;
 pub const GAME_TITLE_ADDR_END: u16 = 0x142;
+pub const CGB_FLAG_ADDR: u16 = 0x143;
 pub const CARTRIDGE_TYPE_ADDR: u16 = 0x147;
 pub const ROM_BANK_SIZE: u16 = 0x4000;
 pub const RAM_BANK_SIZE: u16 = 0x2000;
@@ -1,5 +1,6 @@
 use util::util;
 use mem::consts;
+use mem::bus::Bus;
 use time;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -36,10 +37,10 @@ impl Default for Rtc {
 pub struct Memory {
     bootstrap_rom: [u8; 0x100],
     vram: [u8; 0x2000],
-    external_ram: [u8; 0x7A1200], // TODO: dinamically allocate size?
+    external_ram: Vec<u8>, // TODO: dinamically allocate size?
     wram: [u8; 0x2000],
     oam: [u8; 0xA0],
-    io_registers: [u8; 0x80],
+    interrupt_flags: u8, // IF; the other IO registers live in their components.
     hram: [u8; 0x7F],
     interrupts_enable: u8,
     cartridge: Vec<u8>,
@@ -49,8 +50,6 @@ pub struct Memory {
     rom_banking_enabled: bool,
     external_ram_enabled: bool,
     bootstrap_enabled: bool,
-    can_access_vram: bool,
-    can_access_oam: bool,
     rtc: Rtc,
 }
 
@@ -59,10 +58,10 @@ impl Default for Memory {
         Memory {
             bootstrap_rom: [0; 0x100],
             vram: [0; 0x2000],
-            external_ram: [0; 0x7A1200],
+            external_ram: vec![0; 0x7A1200],
             wram: [0; 0x2000],
             oam: [0; 0xA0],
-            io_registers: [0; 0x80],
+            interrupt_flags: 0x0,
             hram: [0; 0x7F],
             interrupts_enable: 0x0,
             cartridge: vec![0; 0x200000],
@@ -72,126 +71,12 @@ impl Default for Memory {
             rom_banking_enabled: true,
             external_ram_enabled: false,
             bootstrap_enabled: true,
-            can_access_vram: true,
-            can_access_oam: true,
             rtc: Rtc::default(),
         }
     }
 }
 
 impl Memory {
-    // returns a string with the memory data from min_addr to max_addr.
-    pub fn format(&self, min_addr: Option<u16>, max_addr: Option<u16>) -> String {
-        let columns: u8 = 16;
-
-        let mut res: String = "".to_owned();
-
-        let mut to: usize = 0xffff;
-        let mut from: usize = 0;
-
-        if let Some(fr) = min_addr {
-            from = fr as usize;
-        }
-        if let Some(t) = max_addr {
-            to = t as usize;
-        }
-
-        let mut i: usize = from;
-        while i >= from && i < to {
-            if i as u8 % columns == 0 {
-                res = res + &format!("\n{:01$x}: ", i, 8);
-            }
-            let lhs: u8 = self.read_byte(i as u16);
-            i += 1;
-            let rhs: u8 = self.read_byte(i as u16);
-            res = res + &format!("{:01$x}", lhs, 2);
-            res = res + &format!("{:01$x} ", rhs, 2);
-
-            i += 1;
-        }
-        res
-    }
-
-    pub fn write_byte(&mut self, address: u16, value: u8) {
-        match address {
-            0x0000...0x7FFF => {
-                if self.cartridge_type == CartridgeType::RomOnly {
-                    // self.cartridge[address as usize] = value;
-                } else {
-                    self.handle_banking(address, value);
-                }
-            }
-            0x8000...0x9FFF => {
-                if self.can_access_vram {
-                    self.vram[(address - 0x8000) as usize] = value;
-                }
-            }
-            0xA000...0xBFFF => {
-                // TODO && if battery powered?
-                if self.external_ram_enabled {
-                    self.external_ram[address as usize - 0xA000 +
-                                      (self.current_ram_bank as usize *
-                                       consts::RAM_BANK_SIZE as usize)] = value;
-                    // self.external_ram_enabled = false;
-                }
-            }
-            0xC000...0xDFFF => self.wram[(address - 0xC000) as usize] = value,
-            0xE000...0xFDFF => self.wram[(address - 0xE000) as usize] = value,
-            0xFE00...0xFE9F => {
-                if self.can_access_oam {
-                    self.oam[(address - 0xFE00) as usize] = value;
-                }
-            }
-            0xFEA0...0xFEFF => (),// panic!("writing to unusable ram."),
-            0xFF00...0xFF7F => self.io_registers[(address - 0xFF00) as usize] = value,
-            0xFF80...0xFFFE => self.hram[(address - 0xFF80) as usize] = value,
-            0xFFFF => self.interrupts_enable = value,
-            _ => panic!("Out of bound! Tried to write to {:#x}.", address),
-        }
-    }
-
-    pub fn read_byte(&self, address: u16) -> u8 {
-        match address {
-            0x0000...0x00FF => {
-                if self.bootstrap_enabled {
-                    self.bootstrap_rom[address as usize]
-                } else {
-                    self.cartridge[address as usize]
-                }
-            }
-            0x0100...0x3FFF => self.cartridge[address as usize],
-            0x4000...0x7FFF => {
-                self.cartridge[address as usize - 0x4000 +
-                               (self.current_rom_bank as usize * consts::ROM_BANK_SIZE as usize)]
-            }
-            0x8000...0x9FFF => {
-                if self.can_access_vram {
-                    self.vram[(address - 0x8000) as usize]
-                } else {
-                    0xFF
-                }
-            }
-            0xA000...0xBFFF => {
-                let bank_addr: usize = self.current_ram_bank as usize *
-                                       consts::RAM_BANK_SIZE as usize;
-                self.external_ram[address as usize - 0xA000 + bank_addr]
-            }
-            0xC000...0xDFFF => self.wram[(address - 0xC000) as usize],
-            0xE000...0xFDFF => self.wram[(address - 0xE000) as usize],
-            0xFE00...0xFE9F => {
-                if self.can_access_oam {
-                    self.oam[(address - 0xFE00) as usize]
-                } else {
-                    0xFF
-                }
-            }
-            0xFF00...0xFF7F => self.io_registers[(address - 0xFF00) as usize],
-            0xFF80...0xFFFE => self.hram[(address - 0xFF80) as usize],
-            0xFFFF => self.interrupts_enable,
-            _ => panic!("Out of bound! Tried to read from {:#x}.", address),
-        }
-    }
-
     fn handle_banking(&mut self, address: u16, byte: u8) {
         match address {
             0x0000...0x1FFF => {
@@ -385,18 +270,16 @@ impl Memory {
         self.current_ram_bank = (byte & 0b11) as u16;
     }
 
-    pub fn set_access_vram(&mut self, can_access: bool) {
-        self.can_access_vram = can_access;
-    }
-    pub fn set_access_oam(&mut self, can_access: bool) {
-        self.can_access_oam = can_access;
+    // used by OAM DMA, which doesn't go through the cpu bus.
+    pub fn write_oam(&mut self, index: u16, value: u8) {
+        self.oam[index as usize] = value;
     }
 
     pub fn restart(&mut self) {
         self.vram = [0; 0x2000];
         self.wram = [0; 0x2000];
         self.oam = [0; 0xA0];
-        self.io_registers = [0; 0x80];
+        self.interrupt_flags = 0x0;
         self.hram = [0; 0x7F];
         self.interrupts_enable = 0x0;
         self.current_rom_bank = 0x1;
@@ -404,43 +287,20 @@ impl Memory {
         self.rom_banking_enabled = true;
         self.external_ram_enabled = false;
         self.bootstrap_enabled = true;
-        self.can_access_vram = true;
         self.rtc = Rtc::default();
     }
 
     pub fn disable_bootstrap(&mut self) {
         self.bootstrap_enabled = false;
-        self.write_byte(0xFF05, 0x00);
-        self.write_byte(0xFF06, 0x00);
-        self.write_byte(0xFF07, 0x00);
-        self.write_byte(0xFF10, 0x80);
-        self.write_byte(0xFF11, 0xBF);
-        self.write_byte(0xFF12, 0xF3);
-        self.write_byte(0xFF14, 0xBF);
-        self.write_byte(0xFF16, 0x3F);
-        self.write_byte(0xFF17, 0x00);
-        self.write_byte(0xFF19, 0xBF);
-        self.write_byte(0xFF1A, 0x7F);
-        self.write_byte(0xFF1B, 0xFF);
-        self.write_byte(0xFF1C, 0x9F);
-        self.write_byte(0xFF1E, 0xBF);
-        self.write_byte(0xFF20, 0xFF);
-        self.write_byte(0xFF21, 0x00);
-        self.write_byte(0xFF22, 0x00);
-        self.write_byte(0xFF23, 0xBF);
-        self.write_byte(0xFF24, 0x77);
-        self.write_byte(0xFF25, 0xF3);
-        self.write_byte(0xFF26, 0xF1);
-        self.write_byte(0xFF40, 0x91);
-        self.write_byte(0xFF42, 0x00);
-        self.write_byte(0xFF43, 0x00);
-        self.write_byte(0xFF45, 0x00);
-        self.write_byte(0xFF47, 0xFC);
-        self.write_byte(0xFF48, 0xFF);
-        self.write_byte(0xFF49, 0xFF);
-        self.write_byte(0xFF4A, 0x00);
-        self.write_byte(0xFF4B, 0x00);
-        self.write_byte(0xFFFF, 0x00);
+    }
+
+    pub fn is_bootstrap_enabled(&self) -> bool {
+        self.bootstrap_enabled
+    }
+
+    // true if the game supports CGB features (e.g. double speed mode).
+    pub fn is_cgb_game(&self) -> bool {
+        self.cartridge[consts::CGB_FLAG_ADDR as usize] & 0x80 == 0x80
     }
 
     pub fn load_bootstrap_rom(&mut self, rom: &[u8]) {
@@ -465,4 +325,76 @@ impl Memory {
             }
         }
     }
+
+    // reads have no side effects here, so the ppu and the debugger can peek
+    // without going through the Bus.
+    pub fn read_byte(&self, address: u16) -> u8 {
+        match address {
+            0x0000...0x00FF => {
+                if self.bootstrap_enabled {
+                    self.bootstrap_rom[address as usize]
+                } else {
+                    self.cartridge[address as usize]
+                }
+            }
+            0x0100...0x3FFF => self.cartridge[address as usize],
+            0x4000...0x7FFF => {
+                self.cartridge[address as usize - 0x4000 +
+                               (self.current_rom_bank as usize * consts::ROM_BANK_SIZE as usize)]
+            }
+            0x8000...0x9FFF => self.vram[(address - 0x8000) as usize],
+            0xA000...0xBFFF => {
+                let bank_addr: usize = self.current_ram_bank as usize *
+                                       consts::RAM_BANK_SIZE as usize;
+                self.external_ram[address as usize - 0xA000 + bank_addr]
+            }
+            0xC000...0xDFFF => self.wram[(address - 0xC000) as usize],
+            0xE000...0xFDFF => self.wram[(address - 0xE000) as usize],
+            0xFE00...0xFE9F => self.oam[(address - 0xFE00) as usize],
+            0xFF0F => self.interrupt_flags,
+            0xFF00...0xFF7F => 0xFF,
+            0xFF80...0xFFFE => self.hram[(address - 0xFF80) as usize],
+            0xFFFF => self.interrupts_enable,
+            _ => panic!("Out of bound! Tried to read from {:#x}.", address),
+        }
+    }
+}
+
+impl Bus for Memory {
+    fn write_byte(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000...0x7FFF => {
+                if self.cartridge_type == CartridgeType::RomOnly {
+                    // self.cartridge[address as usize] = value;
+                } else {
+                    self.handle_banking(address, value);
+                }
+            }
+            0x8000...0x9FFF => self.vram[(address - 0x8000) as usize] = value,
+            0xA000...0xBFFF => {
+                // TODO && if battery powered?
+                if self.external_ram_enabled {
+                    self.external_ram[address as usize - 0xA000 +
+                                      (self.current_ram_bank as usize *
+                                       consts::RAM_BANK_SIZE as usize)] = value;
+                    // self.external_ram_enabled = false;
+                }
+            }
+            0xC000...0xDFFF => self.wram[(address - 0xC000) as usize] = value,
+            0xE000...0xFDFF => self.wram[(address - 0xE000) as usize] = value,
+            0xFE00...0xFE9F => self.oam[(address - 0xFE00) as usize] = value,
+            0xFEA0...0xFEFF => (),// panic!("writing to unusable ram."),
+            0xFF0F => self.interrupt_flags = value,
+            0xFF00...0xFF7F => (),
+            0xFF80...0xFFFE => self.hram[(address - 0xFF80) as usize] = value,
+            0xFFFF => self.interrupts_enable = value,
+            _ => panic!("Out of bound! Tried to write to {:#x}.", address),
+        }
+    }
+
+    fn read_byte(&mut self, address: u16) -> u8 {
+        Memory::read_byte(self, address)
+    }
+
+    fn tick(&mut self, _: u32) {}
 }
@@ -1,3 +1,4 @@
 pub mod mem;
 pub mod consts;
 pub mod cartridge;
+pub mod bus;
@@ -0,0 +1,59 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use apu::apu::SAMPLE_RATE;
+use cpu::consts::CPU_FREQUENCY_HZ;
+use graphics::consts;
+use graphics::graphics::FRAME_DURATION_DOTS;
+use util::png;
+use util::wav::WavWriter;
+
+// Records the screen as a sequence of pngs (frame_000000.png, ...) and the sound
+// as audio.wav, in a directory. The lcd doesn't always show a frame every
+// FRAME_DURATION_DOTS (e.g. the first one after it's turned on is skipped), so
+// frames are repeated or dropped to keep the sequence in sync with the audio:
+// frame n is always the one shown at n * FRAME_DURATION_DOTS cycles.
+pub struct Recorder {
+    dir: PathBuf,
+    audio: WavWriter,
+    frames: u64,
+    samples: u64, // per channel.
+}
+
+impl Recorder {
+    pub fn start(dir: &Path) -> io::Result<Recorder> {
+        try!(fs::create_dir_all(dir));
+        let audio: WavWriter = try!(WavWriter::create(&dir.join("audio.wav"), 2, SAMPLE_RATE));
+        Ok(Recorder {
+            dir: dir.to_path_buf(),
+            audio: audio,
+            frames: 0,
+            samples: 0,
+        })
+    }
+
+    // 'samples' are the stereo ones since the last frame.
+    pub fn add_frame(&mut self, rgba: &[u8], samples: &[i16]) -> io::Result<()> {
+        try!(self.audio.write_samples(samples));
+        self.samples += samples.len() as u64 / 2;
+
+        // frames that should have been shown by the end of the audio.
+        let frames_due: u64 = (self.samples * CPU_FREQUENCY_HZ as u64 +
+                               SAMPLE_RATE as u64 * FRAME_DURATION_DOTS as u64 / 2) /
+                              (SAMPLE_RATE as u64 * FRAME_DURATION_DOTS as u64);
+        while self.frames < frames_due {
+            let path: PathBuf = self.dir.join(format!("frame_{:06}.png", self.frames));
+            try!(png::write_rgba(&path,
+                                 consts::DISPLAY_WIDTH_PX as u32,
+                                 consts::DISPLAY_HEIGHT_PX as u32,
+                                 rgba));
+            self.frames += 1;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        self.audio.finish()
+    }
+}
@@ -0,0 +1,248 @@
+// Runs the community SM83 single step test vectors
+// (https://github.com/SingleStepTests/sm83) against the cpu.
+// Each file holds every test case for one opcode: initial registers and ram,
+// the expected final registers and ram and one entry per M-cycle.
+use cpu::cpu::{Cpu, Reg};
+use mem::bus::Bus;
+
+use rustc_serialize::json::Json;
+
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+const MAX_FAILURES_PRINTED: u32 = 10;
+
+// the whole 64KB address space as plain ram, without the DMG memory map.
+pub struct FlatRam {
+    data: Vec<u8>,
+    cycles: u32,
+}
+
+impl Default for FlatRam {
+    fn default() -> FlatRam {
+        FlatRam {
+            data: vec![0; 0x10000],
+            cycles: 0,
+        }
+    }
+}
+
+impl FlatRam {
+    // cycles ticked so far.
+    #[cfg(test)]
+    pub fn cycles(&self) -> u32 {
+        self.cycles
+    }
+}
+
+impl Bus for FlatRam {
+    fn read_byte(&mut self, address: u16) -> u8 {
+        self.data[address as usize]
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.data[address as usize] = value;
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.cycles += cycles;
+    }
+}
+
+struct State {
+    regs: [(Reg, u16); 10],
+    ime: Option<bool>,
+    ram: Vec<(u16, u8)>,
+}
+
+fn json_u64(json: &Json, key: &str) -> Result<u64, String> {
+    match json.find(key).and_then(|v| v.as_u64()) {
+        Some(v) => Ok(v),
+        None => Err(format!("missing or invalid field '{}'", key)),
+    }
+}
+
+fn parse_state(json: &Json) -> Result<State, String> {
+    let names = [(Reg::PC, "pc"),
+                 (Reg::SP, "sp"),
+                 (Reg::A, "a"),
+                 (Reg::F, "f"),
+                 (Reg::B, "b"),
+                 (Reg::C, "c"),
+                 (Reg::D, "d"),
+                 (Reg::E, "e"),
+                 (Reg::H, "h"),
+                 (Reg::L, "l")];
+    let mut regs: [(Reg, u16); 10] = [(Reg::A, 0); 10];
+    for (i, &(reg, name)) in names.iter().enumerate() {
+        regs[i] = (reg, try!(json_u64(json, name)) as u16);
+    }
+
+    let ime: Option<bool> = json.find("ime").and_then(|v| v.as_u64()).map(|v| v == 1);
+
+    let mut ram: Vec<(u16, u8)> = Vec::new();
+    let entries = match json.find("ram").and_then(|v| v.as_array()) {
+        Some(entries) => entries,
+        None => return Err("missing or invalid field 'ram'".to_owned()),
+    };
+    for entry in entries {
+        let pair: Vec<u64> = match entry.as_array() {
+            Some(pair) => pair.iter().filter_map(|v| v.as_u64()).collect(),
+            None => Vec::new(),
+        };
+        if pair.len() != 2 {
+            return Err("invalid ram entry".to_owned());
+        }
+        ram.push((pair[0] as u16, pair[1] as u8));
+    }
+
+    Ok(State {
+        regs: regs,
+        ime: ime,
+        ram: ram,
+    })
+}
+
+// returns the list of differences between the expected state and the actual one.
+fn compare(expected: &State, cpu: &Cpu, ram: &mut FlatRam) -> Vec<String> {
+    let mut diffs: Vec<String> = Vec::new();
+    for &(reg, value) in expected.regs.iter() {
+        if cpu.reg16(reg) != value {
+            diffs.push(format!("{:?}: expected {:#x}, got {:#x}", reg, value, cpu.reg16(reg)));
+        }
+    }
+    if let Some(ime) = expected.ime {
+        if cpu.ime() != ime {
+            diffs.push(format!("IME: expected {}, got {}", ime, cpu.ime()));
+        }
+    }
+    for &(addr, value) in &expected.ram {
+        if ram.read_byte(addr) != value {
+            diffs.push(format!("({:#x}): expected {:#x}, got {:#x}",
+                               addr,
+                               value,
+                               ram.read_byte(addr)));
+        }
+    }
+    diffs
+}
+
+// returns a description of the failures of the test case, if any.
+fn run_case(case: &Json) -> Result<Vec<String>, String> {
+    let initial: State = match case.find("initial") {
+        Some(json) => try!(parse_state(json)),
+        None => return Err("missing field 'initial'".to_owned()),
+    };
+    let expected: State = match case.find("final") {
+        Some(json) => try!(parse_state(json)),
+        None => return Err("missing field 'final'".to_owned()),
+    };
+    let expected_cycles: u32 = match case.find("cycles").and_then(|v| v.as_array()) {
+        Some(cycles) => cycles.len() as u32 * 4,
+        None => return Err("missing field 'cycles'".to_owned()),
+    };
+
+    let mut cpu: Cpu = Cpu::default();
+    let mut ram: FlatRam = FlatRam::default();
+    for &(reg, value) in initial.regs.iter() {
+        cpu.reg_set16(reg, value);
+    }
+    if let Some(ime) = initial.ime {
+        cpu.set_ime(ime);
+    }
+    for &(addr, value) in &initial.ram {
+        ram.write_byte(addr, value);
+    }
+
+    cpu.run_instruction(&mut ram);
+
+    let mut diffs: Vec<String> = compare(&expected, &cpu, &mut ram);
+    if ram.cycles != expected_cycles {
+        diffs.push(format!("cycles: expected {}, got {}", expected_cycles, ram.cycles));
+    }
+    Ok(diffs)
+}
+
+// returns (passed, failed) for the test cases in the file.
+fn run_file(path: &Path) -> Result<(u32, u32), String> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => return Err(format!("{}", error)),
+    };
+    let json: Json = match Json::from_reader(&mut file) {
+        Ok(json) => json,
+        Err(error) => return Err(format!("{}", error)),
+    };
+    let cases = match json.as_array() {
+        Some(cases) => cases,
+        None => return Err("expected an array of test cases".to_owned()),
+    };
+
+    let mut passed: u32 = 0;
+    let mut failed: u32 = 0;
+    for case in cases {
+        let name: &str = case.find("name").and_then(|v| v.as_string()).unwrap_or("?");
+        let diffs: Vec<String> = try!(run_case(case));
+        if diffs.is_empty() {
+            passed += 1;
+        } else {
+            if failed < MAX_FAILURES_PRINTED {
+                println!("FAIL {}:", name);
+                for diff in diffs {
+                    println!("\t{}", diff);
+                }
+            }
+            failed += 1;
+        }
+    }
+    Ok((passed, failed))
+}
+
+// every path can be either a json file or a directory of json files.
+// Returns true if all the test cases passed.
+pub fn run(paths: &[String]) -> bool {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        let path: &Path = Path::new(path);
+        if path.is_dir() {
+            let mut dir_files: Vec<PathBuf> = match fs::read_dir(path) {
+                Ok(entries) => {
+                    entries.filter_map(|e| e.ok())
+                           .map(|e| e.path())
+                           .filter(|p| p.extension().map_or(false, |ext| ext == "json"))
+                           .collect()
+                }
+                Err(error) => {
+                    println!("{}: {}", path.display(), error);
+                    return false;
+                }
+            };
+            dir_files.sort();
+            files.extend(dir_files);
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    let mut total_passed: u32 = 0;
+    let mut total_failed: u32 = 0;
+    let mut all_ok: bool = true;
+    for file in &files {
+        match run_file(file) {
+            Ok((passed, failed)) => {
+                println!("{}: {} passed, {} failed", file.display(), passed, failed);
+                total_passed += passed;
+                total_failed += failed;
+            }
+            Err(error) => {
+                println!("{}: {}", file.display(), error);
+                all_ok = false;
+            }
+        }
+    }
+    println!("######################");
+    println!("{} passed, {} failed", total_passed, total_failed);
+
+    all_ok && total_failed == 0
+}
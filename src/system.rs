@@ -0,0 +1,337 @@
+use cpu;
+use cpu::timer::Timer;
+use cpu::joypad::Joypad;
+use cpu::serial::Serial;
+use cpu::dma::Dma;
+
+use graphics::graphics::Graphics;
+
+use apu::apu::Apu;
+
+use mem::mem::Memory;
+use mem::bus::Bus;
+
+// Everything the cpu is connected to. The cpu ticks it on every M-cycle,
+// so timer and lcd advance along with the instruction being executed.
+pub struct System {
+    pub mem: Memory,
+    pub timer: Timer,
+    pub graphics: Graphics,
+    pub joypad: Joypad,
+    pub serial: Serial,
+    pub apu: Apu,
+    pub dma: Dma,
+    pub should_display_screen: bool,
+    double_speed: bool, // CGB: the cpu and timer run twice as fast as the lcd.
+    key1: u8, // CGB: bit 7 is the current speed, bit 0 arms the switch.
+}
+
+impl Default for System {
+    fn default() -> System {
+        System {
+            mem: Memory::default(),
+            timer: Timer::default(),
+            graphics: Graphics::default(),
+            joypad: Joypad::default(),
+            serial: Serial::default(),
+            apu: Apu::default(),
+            dma: Dma::default(),
+            should_display_screen: false,
+            double_speed: false,
+            key1: 0,
+        }
+    }
+}
+
+impl System {
+    pub fn restart(&mut self) {
+        self.mem.restart();
+        self.timer = Timer::default();
+        self.graphics.restart();
+        self.joypad = Joypad::default();
+        self.serial = Serial::default();
+        self.apu = Apu::default();
+        self.dma = Dma::default();
+        self.should_display_screen = false;
+        self.double_speed = false;
+        self.key1 = 0;
+    }
+
+    // leaves the registers as the bootstrap rom does. The sound ones are loaded
+    // directly: writing NR14 would trigger channel 1 and restart the boot sound.
+    fn disable_bootstrap(&mut self) {
+        self.mem.disable_bootstrap();
+        self.apu.load_register(0xFF26, 0xF1);
+        self.write_byte(0xFF05, 0x00);
+        self.write_byte(0xFF06, 0x00);
+        self.write_byte(0xFF07, 0x00);
+        self.apu.load_register(0xFF10, 0x80);
+        self.apu.load_register(0xFF11, 0xBF);
+        self.apu.load_register(0xFF12, 0xF3);
+        self.apu.load_register(0xFF14, 0xBF);
+        self.apu.load_register(0xFF16, 0x3F);
+        self.apu.load_register(0xFF17, 0x00);
+        self.apu.load_register(0xFF19, 0xBF);
+        self.apu.load_register(0xFF1A, 0x7F);
+        self.apu.load_register(0xFF1B, 0xFF);
+        self.apu.load_register(0xFF1C, 0x9F);
+        self.apu.load_register(0xFF1E, 0xBF);
+        self.apu.load_register(0xFF20, 0xFF);
+        self.apu.load_register(0xFF21, 0x00);
+        self.apu.load_register(0xFF22, 0x00);
+        self.apu.load_register(0xFF23, 0xBF);
+        self.apu.load_register(0xFF24, 0x77);
+        self.apu.load_register(0xFF25, 0xF3);
+        self.write_byte(0xFF40, 0x91);
+        self.write_byte(0xFF42, 0x00);
+        self.write_byte(0xFF43, 0x00);
+        self.write_byte(0xFF45, 0x00);
+        self.write_byte(0xFF47, 0xFC);
+        self.write_byte(0xFF48, 0xFF);
+        self.write_byte(0xFF49, 0xFF);
+        self.write_byte(0xFF4A, 0x00);
+        self.write_byte(0xFF4B, 0x00);
+        self.write_byte(0xFFFF, 0x00);
+    }
+
+    // the lcd owns VRAM during mode 3 and OAM during modes 2 and 3.
+    fn is_blocked_by_lcd(&self, address: u16) -> bool {
+        if !self.graphics.is_lcd_display_enable() {
+            return false;
+        }
+        match address {
+            0x8000...0x9FFF => self.graphics.mode() == 0b11,
+            0xFE00...0xFE9F => self.graphics.mode() >= 0b10,
+            _ => false,
+        }
+    }
+
+    // returns a string with the data the cpu sees from min_addr to max_addr.
+    pub fn format(&self, min_addr: Option<u16>, max_addr: Option<u16>) -> String {
+        let columns: u8 = 16;
+
+        let mut res: String = "".to_owned();
+
+        let mut to: usize = 0xffff;
+        let mut from: usize = 0;
+
+        if let Some(fr) = min_addr {
+            from = fr as usize;
+        }
+        if let Some(t) = max_addr {
+            to = t as usize;
+        }
+
+        let mut i: usize = from;
+        while i >= from && i < to {
+            if i as u8 % columns == 0 {
+                res = res + &format!("\n{:01$x}: ", i, 8);
+            }
+            let lhs: u8 = self.read_byte(i as u16);
+            i += 1;
+            let rhs: u8 = self.read_byte(i as u16);
+            res = res + &format!("{:01$x}", lhs, 2);
+            res = res + &format!("{:01$x} ", rhs, 2);
+
+            i += 1;
+        }
+        res
+    }
+
+    // what the cpu would read at 'address' right now. None of the reads has side
+    // effects, so the debugger can use it too.
+    pub fn read_byte(&self, address: u16) -> u8 {
+        if self.dma.is_blocked(address) {
+            return self.dma.conflict_read(address);
+        }
+        if self.is_blocked_by_lcd(address) {
+            return 0xFF;
+        }
+        match address {
+            cpu::consts::JOYPAD_REGISTER_ADDR => self.joypad.read(),
+            cpu::consts::SB_REGISTER_ADDR...cpu::consts::SC_REGISTER_ADDR => {
+                self.serial.read(address)
+            }
+            cpu::consts::DIV_REGISTER_ADDR...cpu::consts::TAC_REGISTER_ADDR => {
+                self.timer.read(address)
+            }
+            cpu::consts::IF_REGISTER_ADDR => self.mem.read_byte(address) | 0b1110_0000,
+            cpu::consts::SOUND_REGISTERS_ADDR_START...cpu::consts::SOUND_REGISTERS_ADDR_END => {
+                self.apu.read(address)
+            }
+            cpu::consts::DMA_REGISTER_ADDR => self.dma.read(),
+            cpu::consts::LCDC_REGISTER_ADDR...cpu::consts::WX_REGISTER_ADDR => {
+                self.graphics.read_register(address)
+            }
+            // KEY1 only exists on a CGB.
+            cpu::consts::KEY1_REGISTER_ADDR if self.mem.is_cgb_game() => {
+                self.key1 | 0b0111_1110
+            }
+            0xFF00...0xFF7F => 0xFF, // unused
+            _ => self.mem.read_byte(address),
+        }
+    }
+}
+
+// IO registers are handled by the component they belong to, so the side effects
+// of an access don't depend on which instruction made it.
+impl Bus for System {
+    fn read_byte(&mut self, address: u16) -> u8 {
+        System::read_byte(self, address)
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        if self.dma.is_blocked(address) || self.is_blocked_by_lcd(address) {
+            return;
+        }
+        match address {
+            cpu::consts::JOYPAD_REGISTER_ADDR => self.joypad.write(value, &mut self.mem),
+            cpu::consts::SB_REGISTER_ADDR...cpu::consts::SC_REGISTER_ADDR => {
+                self.serial.write(address, value)
+            }
+            cpu::consts::DIV_REGISTER_ADDR...cpu::consts::TAC_REGISTER_ADDR => {
+                self.timer.write(address, value)
+            }
+            cpu::consts::IF_REGISTER_ADDR => self.mem.write_byte(address, value),
+            cpu::consts::SOUND_REGISTERS_ADDR_START...cpu::consts::SOUND_REGISTERS_ADDR_END => {
+                self.apu.write(address, value)
+            }
+            cpu::consts::DMA_REGISTER_ADDR => self.dma.write(value),
+            cpu::consts::LCDC_REGISTER_ADDR...cpu::consts::WX_REGISTER_ADDR => {
+                self.graphics.write_register(address, value)
+            }
+            cpu::consts::KEY1_REGISTER_ADDR => {
+                // only the armed bit is writable.
+                self.key1 = (self.key1 & 0b1000_0000) | (value & 0b1);
+            }
+            cpu::consts::BOOTSTRAP_REGISTER_ADDR => {
+                if value != 0 && self.mem.is_bootstrap_enabled() {
+                    self.disable_bootstrap();
+                }
+            }
+            0xFF00...0xFF7F => (), // unused
+            _ => self.mem.write_byte(address, value),
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.timer.update(cycles, &mut self.mem);
+        self.serial.update(cycles, &mut self.mem);
+        self.dma.update(cycles, &mut self.mem);
+
+        let dots: u32 = if self.double_speed {
+            cycles / 2
+        } else {
+            cycles
+        };
+        self.apu.tick(dots);
+        if self.graphics.tick(dots, &mut self.mem) {
+            self.should_display_screen = true;
+        }
+    }
+
+    fn speed_switch(&mut self) -> bool {
+        if !self.mem.is_cgb_game() || self.key1 & 0b1 == 0b0 {
+            return false;
+        }
+        self.double_speed = !self.double_speed;
+        // bit 7 tells the current speed; the armed bit is cleared.
+        self.key1 = if self.double_speed {
+            0b1000_0000
+        } else {
+            0b0000_0000
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::System;
+    use cpu::consts;
+    use mem::bus::Bus;
+
+    #[test]
+    fn key1_reads_0xff_on_a_dmg() {
+        let mut system: System = System::default();
+        system.write_byte(consts::KEY1_REGISTER_ADDR, 0b1);
+        assert_eq!(system.read_byte(consts::KEY1_REGISTER_ADDR), 0xFF);
+        assert!(!system.speed_switch());
+    }
+
+    #[test]
+    fn memory_dump_shows_the_io_registers_of_the_components() {
+        let mut system: System = System::default();
+        system.write_byte(consts::TMA_REGISTER_ADDR, 0x42);
+        system.write_byte(consts::IF_REGISTER_ADDR, 0b0000_0100);
+        let dump: String = system.format(Some(0xFF06), Some(0xFF10));
+        // TMA, then TAC and IF with their unused bits set.
+        assert!(dump.contains("42f8"), "{}", dump);
+        assert!(dump.contains("ffe4"), "{}", dump);
+    }
+
+    #[test]
+    fn handing_over_from_the_boot_rom_doesnt_trigger_the_sound_channels() {
+        let mut system: System = System::default();
+        system.write_byte(consts::BOOTSTRAP_REGISTER_ADDR, 0x01);
+
+        assert!(!system.mem.is_bootstrap_enabled());
+        assert_eq!(system.read_byte(0xFF12), 0xF3);
+        // the sound is on, but none of the channels is playing.
+        assert_eq!(system.read_byte(0xFF26), 0xF0);
+    }
+
+    #[test]
+    fn handing_over_from_the_boot_rom_leaves_a_playing_channel_alone() {
+        let mut system: System = System::default();
+        system.write_byte(0xFF26, 0x80);
+        system.write_byte(0xFF12, 0xF0);
+        system.write_byte(0xFF11, 0x3F); // length 1
+        system.write_byte(0xFF14, 0xC0); // trigger, length enabled
+        system.write_byte(consts::BOOTSTRAP_REGISTER_ADDR, 0x01);
+
+        // NR14 isn't written again, so the length counter still runs out.
+        assert_eq!(system.read_byte(0xFF26) & 0b1, 0b1);
+        system.tick(consts::CPU_FREQUENCY_HZ / 256);
+        assert_eq!(system.read_byte(0xFF26) & 0b1, 0b0);
+    }
+
+    const VRAM: u16 = 0x8010;
+    const OAM: u16 = 0xFE10;
+
+    #[test]
+    fn vram_and_oam_are_free_while_the_lcd_is_off() {
+        let mut system: System = System::default();
+        system.write_byte(VRAM, 0x12);
+        system.write_byte(OAM, 0x34);
+
+        assert_eq!(system.read_byte(VRAM), 0x12);
+        assert_eq!(system.read_byte(OAM), 0x34);
+    }
+
+    #[test]
+    fn the_lcd_blocks_oam_in_modes_2_and_3_and_vram_in_mode_3() {
+        let mut system: System = System::default();
+        system.write_byte(VRAM, 0x12);
+        system.write_byte(OAM, 0x34);
+        system.write_byte(consts::LCDC_REGISTER_ADDR, 0b1001_0001);
+
+        // mode 2: only OAM is blocked.
+        assert_eq!(system.graphics.mode(), 0b10);
+        assert_eq!(system.read_byte(VRAM), 0x12);
+        assert_eq!(system.read_byte(OAM), 0xFF);
+        system.write_byte(OAM, 0x56);
+
+        system.tick(consts::STAT_MODE_2_DURATION_CYCLES);
+        assert_eq!(system.graphics.mode(), 0b11);
+        assert_eq!(system.read_byte(VRAM), 0xFF);
+        assert_eq!(system.read_byte(OAM), 0xFF);
+        system.write_byte(VRAM, 0x78);
+
+        system.tick(consts::STAT_MODE_3_DURATION_CYCLES);
+        assert_eq!(system.graphics.mode(), 0b00);
+        // and the writes made while blocked were ignored.
+        assert_eq!(system.read_byte(VRAM), 0x12);
+        assert_eq!(system.read_byte(OAM), 0x34);
+    }
+}
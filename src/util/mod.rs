@@ -1 +1,5 @@
 pub mod util;
+pub mod png;
+pub mod wav;
+#[cfg(test)]
+pub mod temp_file;
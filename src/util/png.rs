@@ -0,0 +1,152 @@
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+// Minimal PNG encoder for debug dumps: 8 bit RGBA, no filtering and deflate's
+// stored (uncompressed) blocks, so no compression library is needed.
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 0b1 == 0b1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b): (u32, u32) = (1, 0);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.push((value >> 24) as u8);
+    buffer.push((value >> 16) as u8);
+    buffer.push((value >> 8) as u8);
+    buffer.push(value as u8);
+}
+
+fn push_chunk(png: &mut Vec<u8>, chunk_type: &[u8], data: &[u8]) {
+    push_u32(png, data.len() as u32);
+    let start: usize = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc: u32 = crc32(&png[start..]);
+    push_u32(png, crc);
+}
+
+// zlib stream made of stored blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut zlib: Vec<u8> = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[0b1, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last: u8 = if blocks.peek().is_none() {
+            0b1
+        } else {
+            0b0
+        };
+        let len: u16 = block.len() as u16;
+        zlib.push(last);
+        zlib.push(len as u8);
+        zlib.push((len >> 8) as u8);
+        zlib.push(!len as u8);
+        zlib.push((!len >> 8) as u8);
+        zlib.extend_from_slice(block);
+    }
+    push_u32(&mut zlib, adler32(data));
+    zlib
+}
+
+pub fn encode_rgba(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    assert_eq!(rgba.len(), width as usize * height as usize * 4);
+    let mut png: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    let mut header: Vec<u8> = Vec::new();
+    push_u32(&mut header, width);
+    push_u32(&mut header, height);
+    // bit depth 8, color type RGBA, default compression, filter and interlace.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    push_chunk(&mut png, b"IHDR", &header);
+
+    // every row starts with its filter type, which is always none.
+    let mut raw: Vec<u8> = Vec::with_capacity(rgba.len() + height as usize);
+    for row in rgba.chunks(width as usize * 4) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    push_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    push_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+pub fn write_rgba(path: &Path, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    let mut file: File = try!(File::create(path));
+    file.write_all(&encode_rgba(width, height, rgba))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, encode_rgba, zlib_stored};
+
+    #[test]
+    fn crc32_check_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn adler32_check_values() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn zlib_stored_blocks() {
+        assert_eq!(zlib_stored(b""),
+                   vec![0x78, 0x01, 0x01, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(zlib_stored(b"abc"),
+                   vec![0x78, 0x01, 0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c', 0x02, 0x4D,
+                        0x01, 0x27]);
+    }
+
+    #[test]
+    fn zlib_stored_splits_blocks_at_64k() {
+        let data: Vec<u8> = vec![0xAB; 0x10000];
+        let zlib: Vec<u8> = zlib_stored(&data);
+        assert_eq!(zlib.len(), 2 + 5 + 0xFFFF + 5 + 1 + 4);
+        // first block: not the last one, 0xFFFF bytes.
+        assert_eq!(&zlib[2..7], &[0x00, 0xFF, 0xFF, 0x00, 0x00]);
+        // second block: the last one, a single byte.
+        assert_eq!(&zlib[0x10006..0x1000C], &[0x01, 0x01, 0x00, 0xFE, 0xFF, 0xAB]);
+    }
+
+    // checked against the output of python's zlib and binascii modules.
+    #[test]
+    fn encodes_known_bytes() {
+        let rgba: [u8; 8] = [255, 0, 0, 255, 0, 0, 255, 128];
+        let expected: [u8; 77] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00,
+                                  0x00, 0x0D, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x02,
+                                  0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0xF4,
+                                  0x22, 0x7F, 0x8A, 0x00, 0x00, 0x00, 0x14, 0x49, 0x44, 0x41,
+                                  0x54, 0x78, 0x01, 0x01, 0x09, 0x00, 0xF6, 0xFF, 0x00, 0xFF,
+                                  0x00, 0x00, 0xFF, 0x00, 0x00, 0xFF, 0x80, 0x0F, 0x7A, 0x03,
+                                  0x7E, 0x4A, 0x6E, 0x70, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x49,
+                                  0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82];
+        assert_eq!(&encode_rgba(2, 1, &rgba)[..], &expected[..]);
+    }
+}
@@ -0,0 +1,25 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// Files the tests write and read back. They go in the temp dir, with the process
+// id in the name so that two runs of the tests don't share them.
+
+pub fn path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("gebemula_{}_{}", process::id(), name))
+}
+
+// a new temp file holding 'contents'.
+pub fn write(name: &str, contents: &[u8]) -> PathBuf {
+    let path: PathBuf = path(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+// the contents of a temp file, which is removed.
+pub fn take(path: &Path) -> Vec<u8> {
+    let contents: Vec<u8> = fs::read(path).unwrap();
+    fs::remove_file(path).unwrap();
+    contents
+}
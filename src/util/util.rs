@@ -25,19 +25,6 @@ pub fn has_borrow(lhs: u8, rhs: u8) -> bool {
     lhs & 0xF < rhs & 0xF
 }
 
-#[inline]
-pub fn is_neg16(value: u16) -> bool {
-    ((value >> 15) & 0b1) == 0b1
-}
-
-pub fn twos_complement(mut value: u16) -> u16 {
-    if is_neg16(value) {
-        value = !value + 1;
-    }
-
-    value
-}
-
 pub fn sign_extend(value: u8) -> u16 {
     let mut res: u16 = value as u16;
     if (value >> 7) & 0b1 == 0b1 {
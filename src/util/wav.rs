@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// Writes 16 bit PCM wav files as the samples come. The sizes in the header are
+// only known at the end, so they're filled in by finish.
+pub struct WavWriter {
+    file: BufWriter<File>,
+    data_size: u32,
+}
+
+fn u16_bytes(value: u16) -> [u8; 2] {
+    [value as u8, (value >> 8) as u8]
+}
+
+fn u32_bytes(value: u32) -> [u8; 4] {
+    [value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, channels: u16, sample_rate: u32) -> io::Result<WavWriter> {
+        let mut file: BufWriter<File> = BufWriter::new(try!(File::create(path)));
+        let block_align: u16 = channels * 2;
+        try!(file.write_all(b"RIFF"));
+        try!(file.write_all(&u32_bytes(36))); // riff size, with no data.
+        try!(file.write_all(b"WAVE"));
+        try!(file.write_all(b"fmt "));
+        try!(file.write_all(&u32_bytes(16)));
+        try!(file.write_all(&u16_bytes(1))); // PCM
+        try!(file.write_all(&u16_bytes(channels)));
+        try!(file.write_all(&u32_bytes(sample_rate)));
+        try!(file.write_all(&u32_bytes(sample_rate * block_align as u32))); // byte rate
+        try!(file.write_all(&u16_bytes(block_align)));
+        try!(file.write_all(&u16_bytes(16))); // bits per sample
+        try!(file.write_all(b"data"));
+        try!(file.write_all(&u32_bytes(0)));
+        Ok(WavWriter {
+            file: file,
+            data_size: 0,
+        })
+    }
+
+    // interleaved samples when there's more than one channel.
+    pub fn write_samples(&mut self, samples: &[i16]) -> io::Result<()> {
+        for sample in samples {
+            try!(self.file.write_all(&u16_bytes(*sample as u16)));
+        }
+        self.data_size += samples.len() as u32 * 2;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        try!(self.file.seek(SeekFrom::Start(4)));
+        try!(self.file.write_all(&u32_bytes(36 + self.data_size)));
+        try!(self.file.seek(SeekFrom::Start(40)));
+        try!(self.file.write_all(&u32_bytes(self.data_size)));
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WavWriter;
+    use std::path::PathBuf;
+    use util::temp_file;
+
+    fn write_wav(name: &str, channels: u16, sample_rate: u32, batches: &[&[i16]]) -> Vec<u8> {
+        let path: PathBuf = temp_file::path(&format!("{}.wav", name));
+        let mut writer: WavWriter = WavWriter::create(&path, channels, sample_rate).unwrap();
+        for samples in batches {
+            writer.write_samples(samples).unwrap();
+        }
+        writer.finish().unwrap();
+        temp_file::take(&path)
+    }
+
+    fn u16_at(bytes: &[u8], pos: usize) -> u16 {
+        bytes[pos] as u16 | (bytes[pos + 1] as u16) << 8
+    }
+
+    fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+        u16_at(bytes, pos) as u32 | (u16_at(bytes, pos + 2) as u32) << 16
+    }
+
+    #[test]
+    fn empty_file_header() {
+        let bytes: Vec<u8> = write_wav("empty", 1, 8000, &[]);
+        assert_eq!(bytes.len(), 44);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32_at(&bytes, 4), 36);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32_at(&bytes, 40), 0);
+    }
+
+    #[test]
+    fn sizes_count_every_batch() {
+        let bytes: Vec<u8> = write_wav("batches", 1, 8000, &[&[1, 2, 3], &[4, 5]]);
+        assert_eq!(bytes.len(), 44 + 10);
+        assert_eq!(u32_at(&bytes, 4), 36 + 10);
+        assert_eq!(u32_at(&bytes, 40), 10);
+    }
+
+    #[test]
+    fn stereo_format() {
+        let bytes: Vec<u8> = write_wav("stereo", 2, 44100, &[&[1, -1]]);
+        assert_eq!(u16_at(&bytes, 22), 2); // channels
+        assert_eq!(u32_at(&bytes, 24), 44100);
+        assert_eq!(u32_at(&bytes, 28), 44100 * 4); // byte rate
+        assert_eq!(u16_at(&bytes, 32), 4); // block align
+        assert_eq!(u16_at(&bytes, 34), 16);
+        assert_eq!(u32_at(&bytes, 40), 4);
+    }
+}
@@ -0,0 +1,117 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use apu::apu::SAMPLE_RATE;
+use util::wav::WavWriter;
+
+// Writes the mixed sound to a stereo wav and, optionally, each channel to its
+// own mono one next to it: sound.wav, sound_ch1.wav, ..., sound_ch4.wav.
+pub struct WavDump {
+    mix: WavWriter,
+    stems: Vec<WavWriter>,
+}
+
+fn stem_path(path: &Path, channel: usize) -> PathBuf {
+    let name: String = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => "sound".to_owned(),
+    };
+    path.with_file_name(format!("{}_ch{}.wav", name, channel + 1))
+}
+
+impl WavDump {
+    pub fn start(path: &Path, stems: bool) -> io::Result<WavDump> {
+        let mix: WavWriter = try!(WavWriter::create(path, 2, SAMPLE_RATE));
+        let mut stem_writers: Vec<WavWriter> = Vec::new();
+        if stems {
+            for channel in 0..4 {
+                stem_writers.push(try!(WavWriter::create(&stem_path(path, channel),
+                                                         1,
+                                                         SAMPLE_RATE)));
+            }
+        }
+        Ok(WavDump {
+            mix: mix,
+            stems: stem_writers,
+        })
+    }
+
+    pub fn has_stems(&self) -> bool {
+        !self.stems.is_empty()
+    }
+
+    pub fn add(&mut self, samples: &[i16], stems: &[Vec<i16>; 4]) -> io::Result<()> {
+        try!(self.mix.write_samples(samples));
+        for (writer, stem) in self.stems.iter_mut().zip(stems.iter()) {
+            try!(writer.write_samples(stem));
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        try!(self.mix.finish());
+        for writer in self.stems {
+            try!(writer.finish());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stem_path, WavDump};
+    use std::path::PathBuf;
+    use util::temp_file;
+
+    const MIX: [u8; 52] = [0x52, 0x49, 0x46, 0x46, 0x2C, 0x00, 0x00, 0x00, 0x57, 0x41, 0x56,
+                           0x45, 0x66, 0x6D, 0x74, 0x20, 0x10, 0x00, 0x00, 0x00, 0x01, 0x00,
+                           0x02, 0x00, 0x44, 0xAC, 0x00, 0x00, 0x10, 0xB1, 0x02, 0x00, 0x04,
+                           0x00, 0x10, 0x00, 0x64, 0x61, 0x74, 0x61, 0x08, 0x00, 0x00, 0x00,
+                           0x01, 0x00, 0xFF, 0xFF, 0xFF, 0x7F, 0x00, 0x80];
+
+    // header of a mono 44100Hz file holding two samples.
+    const STEM_HEADER: [u8; 44] = [0x52, 0x49, 0x46, 0x46, 0x28, 0x00, 0x00, 0x00, 0x57, 0x41,
+                                   0x56, 0x45, 0x66, 0x6D, 0x74, 0x20, 0x10, 0x00, 0x00, 0x00,
+                                   0x01, 0x00, 0x01, 0x00, 0x44, 0xAC, 0x00, 0x00, 0x88, 0x58,
+                                   0x01, 0x00, 0x02, 0x00, 0x10, 0x00, 0x64, 0x61, 0x74, 0x61,
+                                   0x04, 0x00, 0x00, 0x00];
+
+    #[test]
+    fn stem_names() {
+        let path: PathBuf = PathBuf::from("out/sound.wav");
+        assert_eq!(stem_path(&path, 0), PathBuf::from("out/sound_ch1.wav"));
+        assert_eq!(stem_path(&path, 3), PathBuf::from("out/sound_ch4.wav"));
+    }
+
+    #[test]
+    fn mix_and_stems_bytes() {
+        let path: PathBuf = temp_file::path("dump.wav");
+        let mut dump: WavDump = WavDump::start(&path, true).unwrap();
+        assert!(dump.has_stems());
+        let stems: [Vec<i16>; 4] = [vec![0x1234, -2], vec![1, 2], vec![-1, -1], vec![0, 0]];
+        dump.add(&[1, -1], &stems).unwrap();
+        dump.add(&[32767, -32768], &[vec![], vec![], vec![], vec![]]).unwrap();
+        dump.finish().unwrap();
+
+        assert_eq!(temp_file::take(&path), &MIX[..]);
+        for (channel, samples) in stems.iter().enumerate() {
+            let stem: PathBuf = stem_path(&path, channel);
+            let mut expected: Vec<u8> = STEM_HEADER.to_vec();
+            for sample in samples {
+                expected.push(*sample as u8);
+                expected.push((*sample >> 8) as u8);
+            }
+            assert_eq!(temp_file::take(&stem), expected);
+        }
+    }
+
+    #[test]
+    fn no_stems() {
+        let path: PathBuf = temp_file::path("nostems.wav");
+        let dump: WavDump = WavDump::start(&path, false).unwrap();
+        assert!(!dump.has_stems());
+        dump.finish().unwrap();
+        assert!(!stem_path(&path, 0).exists());
+        temp_file::take(&path);
+    }
+}